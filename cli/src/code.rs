@@ -1,6 +1,7 @@
 use std::{
     fs::{metadata, read_to_string},
     io::{stdin, BufReader, IsTerminal, Read},
+    path::Path,
     process::{self, Command},
 };
 
@@ -8,27 +9,51 @@ use anyhow::bail;
 use clap::CommandFactory;
 use codesnap::{
     config::{
-        Code, CodeBuilder, CommandLineContent, CommandLineContentBuilder, Content, HighlightLine,
+        Annotation, AnnotationPlacement, Code, CodeBuilder, CommandLineContent,
+        CommandLineContentBuilder, Content, DiffBuilder, HighlightLine, HighlightToken,
+        HighlightTokenStyle, LineChange, MarkdownBuilder, Redaction,
     },
     utils::clipboard::Clipboard,
 };
 
-use crate::{highlight::HighlightLineRange, range::Range, CLI, STDIN_CODE_DEFAULT_CHAR};
+use crate::{highlight::HighlightLineRange, range::Ranges, CLI, STDIN_CODE_DEFAULT_CHAR};
 
 pub fn create_code(cli: &CLI, code_config: Code) -> anyhow::Result<Content> {
     let code = match cli.execute[..] {
+        [] if is_diff(cli) => {
+            let mut diff = DiffBuilder::default()
+                .diff(get_code_snippet(cli)?)
+                .build()?;
+
+            diff.file_path = cli.file_path.clone().or(code_config.file_path);
+            diff.language = cli.language.clone().or(code_config.language);
+
+            Content::Diff(diff)
+        }
+        [] if is_markdown(cli) => {
+            let mut markdown = MarkdownBuilder::default()
+                .markdown(get_code_snippet(cli)?)
+                .build()?;
+
+            markdown.file_path = cli
+                .from_file
+                .clone()
+                .or(cli.file_path.clone())
+                .or(code_config.file_path);
+
+            Content::Markdown(markdown)
+        }
         [] => {
-            let range = Range::from_opt_string(cli.range.clone())?;
             let code_snippet = get_code_snippet(cli)?;
-            let parsed_range = range.parse_range(&code_snippet)?;
-            let parsed_code_snippet = parsed_range.cut_code_snippet(&code_snippet)?;
+            let ranges = Ranges::from_opt_string(cli.range.clone(), &code_snippet)?;
+            let parsed_code_snippet = ranges.cut_code_snippet(&code_snippet)?;
             let mut code = CodeBuilder::default()
                 .content(parsed_code_snippet)
                 .build()?;
 
             code.start_line_number = cli
                 .has_line_number
-                .then_some(cli.start_line_number.unwrap_or(parsed_range.0 as u32));
+                .then_some(cli.start_line_number.unwrap_or(ranges.start() as u32));
 
             code.file_path = cli
                 .from_file
@@ -36,11 +61,25 @@ pub fn create_code(cli: &CLI, code_config: Code) -> anyhow::Result<Content> {
                 .or(cli.file_path.clone())
                 .or(code_config.file_path);
             code.language = cli.language.clone().or(code_config.language);
-            code.highlight_lines = create_highlight_lines(&cli, parsed_range, &code_snippet)?;
+            code.folds = ranges.folds();
+
+            let highlight_range =
+                HighlightLineRange::from(ranges, &code_snippet, cli.relative_highlight_range)?;
+
+            code.highlight_lines = create_highlight_lines(cli, &highlight_range)?;
+            code.highlight_tokens = create_highlight_tokens(cli, &highlight_range)?;
+            code.redactions = create_redactions(cli, &highlight_range)?;
+            code.annotations = create_annotations(cli, &highlight_range)?;
 
             Content::Code(code)
         }
         _ => {
+            // The redactions are located by the lines of code, the command output can only be
+            // redacted by the detectors
+            if !cli.redact.is_empty() {
+                bail!("The `--redact` option only works with code, use `--redact-secrets` instead");
+            }
+
             let command_content = cli
                 .execute
                 .clone()
@@ -67,6 +106,27 @@ pub fn create_code(cli: &CLI, code_config: Code) -> anyhow::Result<Content> {
     Ok(code)
 }
 
+fn is_diff(cli: &CLI) -> bool {
+    let is_diff_file = cli.from_file.as_ref().is_some_and(|file_path| {
+        Path::new(file_path)
+            .extension()
+            .is_some_and(|extension| extension == "diff" || extension == "patch")
+    });
+
+    cli.diff || is_diff_file
+}
+
+fn is_markdown(cli: &CLI) -> bool {
+    let is_markdown_file = cli.from_file.as_ref().is_some_and(|file_path| {
+        Path::new(file_path)
+            .extension()
+            .is_some_and(|extension| extension == "md" || extension == "markdown")
+    });
+
+    // The Markdown file is highlighted as the source code if the language is given explicitly
+    cli.markdown || (is_markdown_file && cli.language.is_none())
+}
+
 fn execute_command(command: &str) -> String {
     let output = if cfg!(target_os = "windows") {
         Command::new("cmd")
@@ -91,8 +151,7 @@ fn execute_command(command: &str) -> String {
 
 fn create_highlight_lines(
     cli: &CLI,
-    code_snippet_range: Range<usize>,
-    code_snippet: &str,
+    highlight_range: &HighlightLineRange,
 ) -> anyhow::Result<Vec<HighlightLine>> {
     if let Some(ref raw_highlight_lines) = cli.raw_highlight_lines {
         let highlight_lines = serde_json::from_str::<Vec<HighlightLine>>(raw_highlight_lines)?;
@@ -100,26 +159,93 @@ fn create_highlight_lines(
         return Ok(highlight_lines);
     }
 
-    let highlight_range = HighlightLineRange::from(
-        code_snippet_range,
-        code_snippet,
-        cli.relative_highlight_range,
-    )?;
     let highlight_lines = cli
         .highlight_range
         .clone()
         .and_then(|range| {
-            Some(highlight_range.create_highlight_lines(&range, &cli.highlight_range_color))
+            Some(highlight_range.create_highlight_lines(&range, &cli.highlight_range_color, None))
         })
         .unwrap_or(Ok(vec![]))?;
-    let delete_highlight_lines = highlight_range
-        .create_multiple_highlight_lines(&cli.delete_line, &cli.delete_line_color)?;
-    let new_highlight_lines =
-        highlight_range.create_multiple_highlight_lines(&cli.add_line, &cli.add_line_color)?;
+    let delete_highlight_lines = highlight_range.create_multiple_highlight_lines(
+        &cli.delete_line,
+        &cli.delete_line_color,
+        Some(LineChange::Deleted),
+    )?;
+    let new_highlight_lines = highlight_range.create_multiple_highlight_lines(
+        &cli.add_line,
+        &cli.add_line_color,
+        Some(LineChange::Added),
+    )?;
 
     Ok([highlight_lines, delete_highlight_lines, new_highlight_lines].concat())
 }
 
+fn create_highlight_tokens(
+    cli: &CLI,
+    highlight_range: &HighlightLineRange,
+) -> anyhow::Result<Vec<HighlightToken>> {
+    if let Some(ref raw_highlight_tokens) = cli.raw_highlight_tokens {
+        let highlight_tokens = serde_json::from_str::<Vec<HighlightToken>>(raw_highlight_tokens)?;
+
+        return Ok(highlight_tokens);
+    }
+
+    let style = match cli.highlight_token_style.as_deref() {
+        Some("underline") => HighlightTokenStyle::Underline,
+        Some("background") => HighlightTokenStyle::Background,
+        _ => HighlightTokenStyle::Box,
+    };
+
+    cli.highlight_token
+        .iter()
+        .map(|raw_token| {
+            highlight_range.create_highlight_token(
+                raw_token,
+                &cli.highlight_token_color,
+                style.clone(),
+            )
+        })
+        .collect()
+}
+
+fn create_redactions(
+    cli: &CLI,
+    highlight_range: &HighlightLineRange,
+) -> anyhow::Result<Vec<Redaction>> {
+    cli.redact
+        .iter()
+        .map(|raw_redaction| highlight_range.create_redaction(raw_redaction))
+        .collect()
+}
+
+fn create_annotations(
+    cli: &CLI,
+    highlight_range: &HighlightLineRange,
+) -> anyhow::Result<Vec<Annotation>> {
+    if let Some(ref raw_annotations) = cli.raw_annotations {
+        let annotations = serde_json::from_str::<Vec<Annotation>>(raw_annotations)?;
+
+        return Ok(annotations);
+    }
+
+    let placement = match cli.annotation_placement.as_deref() {
+        Some("below") => AnnotationPlacement::Below,
+        _ => AnnotationPlacement::Right,
+    };
+
+    cli.annotate
+        .iter()
+        .map(|raw_annotation| {
+            highlight_range.create_annotation(
+                raw_annotation,
+                &cli.annotation_color,
+                placement.clone(),
+                cli.numbered_annotations,
+            )
+        })
+        .collect()
+}
+
 fn get_code_snippet(cli: &CLI) -> anyhow::Result<String> {
     if let Some(ref file_path) = cli.from_file {
         if !metadata(file_path)?.is_file() {
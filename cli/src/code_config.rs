@@ -1,4 +1,7 @@
-use codesnap::config::{Breadcrumbs, CodeConfig, CodeConfigBuilder};
+use anyhow::Context;
+use codesnap::config::{
+    Breadcrumbs, CodeConfig, CodeConfigBuilder, HighlightBackend, MaxWidth, WrapMode,
+};
 
 use crate::CLI;
 
@@ -10,10 +13,42 @@ pub fn create_code_config(cli: &CLI, code_config: CodeConfig) -> anyhow::Result<
         .code_font_family
         .clone()
         .unwrap_or(code_config.font_family);
+    parsed_code_config.max_width = match cli.max_width {
+        Some(ref max_width) => Some(parse_max_width(max_width)?),
+        None => code_config.max_width,
+    };
+    parsed_code_config.wrap_mode = match cli.wrap_mode.as_deref() {
+        Some("none") => WrapMode::None,
+        Some("char") => WrapMode::Char,
+        Some("word") => WrapMode::Word,
+        _ => code_config.wrap_mode,
+    };
+    parsed_code_config.font_size = cli.code_font_size.unwrap_or(code_config.font_size);
+    parsed_code_config.line_height = cli.code_line_height.unwrap_or(code_config.line_height);
+    parsed_code_config.letter_spacing = cli
+        .code_letter_spacing
+        .unwrap_or(code_config.letter_spacing);
+    parsed_code_config.highlight_backend = match cli.highlight_backend.as_deref() {
+        Some("syntect") => HighlightBackend::Syntect,
+        Some("tree-sitter") => HighlightBackend::TreeSitter,
+        _ => code_config.highlight_backend,
+    };
 
     Ok(parsed_code_config)
 }
 
+// The max width is in columns by default, and in pixels if it ends with `px`, such as `600px`
+fn parse_max_width(max_width: &str) -> anyhow::Result<MaxWidth> {
+    match max_width.strip_suffix("px") {
+        Some(pixels) => Ok(MaxWidth::Pixels(
+            pixels.trim().parse().context("Invalid max width")?,
+        )),
+        None => Ok(MaxWidth::Columns(
+            max_width.trim().parse().context("Invalid max width")?,
+        )),
+    }
+}
+
 fn map_breadcrumbs(cli: &CLI, breadcrumbs_config: Breadcrumbs) -> Breadcrumbs {
     Breadcrumbs {
         enable: cli.has_breadcrumbs.unwrap_or(breadcrumbs_config.enable),
@@ -1,22 +1,25 @@
 use anyhow::bail;
-use codesnap::config::HighlightLine;
+use codesnap::config::{
+    Annotation, AnnotationPlacement, HighlightLine, HighlightToken, HighlightTokenStyle,
+    LineChange, Redaction, TokenTarget,
+};
 
-use crate::range::Range;
+use crate::range::{Range, Ranges};
 
 pub struct HighlightLineRange {
-    code_snippet_range: Range<usize>,
+    code_snippet_ranges: Ranges,
     code_snippet: String,
     is_relative_highlight_range: bool,
 }
 
 impl HighlightLineRange {
     pub fn from(
-        code_snippet_range: Range<usize>,
+        code_snippet_ranges: Ranges,
         code_snippet: &str,
         is_relative_highlight_range: bool,
     ) -> anyhow::Result<Self> {
         Ok(HighlightLineRange {
-            code_snippet_range,
+            code_snippet_ranges,
             code_snippet: code_snippet.to_string(),
             is_relative_highlight_range,
         })
@@ -27,8 +30,8 @@ impl HighlightLineRange {
         Range(start, end): Range<usize>,
     ) -> anyhow::Result<(usize, usize)> {
         // let Range(start, end) = Range::from_str(&raw_range)?.parse_range(&self.code_snippet)?;
-        let Range(code_snippet_start, code_snippet_end) = self.code_snippet_range;
-        let offset_start = code_snippet_start - 1;
+        let code_snippet_start = self.code_snippet_ranges.start();
+        let code_snippet_end = self.code_snippet_ranges.end();
 
         if start < code_snippet_start {
             bail!(
@@ -44,36 +47,43 @@ impl HighlightLineRange {
             );
         }
 
-        // Users may specify a range to generate snapshot from a part of the whole code snippet,
+        // Users may specify ranges to generate snapshot from parts of the whole code snippet,
         // however the highlight range line number is start from already sliced code snippet
-        // so we need to known the start range to calculate the correct highlight range line number
-        let start = start - offset_start;
-        let end = end - offset_start;
+        // so we need to map the line number to the sliced code snippet
+        let to_snippet_line_number = |line_number| {
+            self.code_snippet_ranges
+                .snippet_line_number(line_number)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("The highlight line {} is hidden by the range", line_number)
+                })
+        };
 
-        Ok((start, end))
+        Ok((to_snippet_line_number(start)?, to_snippet_line_number(end)?))
     }
 
     fn get_relative_highlight_range(
         &self,
         Range(start, end): Range<usize>,
     ) -> anyhow::Result<(usize, usize)> {
-        let Range(code_snippet_start, code_snippet_end) = self.code_snippet_range;
-        let code_snippet_range_size = code_snippet_end - code_snippet_start;
+        let code_snippet_line_count = self.code_snippet_ranges.line_count();
 
-        if end > code_snippet_range_size + 1 {
+        if end > code_snippet_line_count {
             bail!(
                 "The highlight end range should be less than or equal to {}",
-                code_snippet_range_size
+                code_snippet_line_count
             );
         }
 
         Ok((start, end))
     }
 
+    // The changed lines are marked by the change, so that they are drawn as `+` or `-` in ASCII
+    // snapshot, the other highlighted lines are marked by the `>`
     pub fn create_highlight_lines(
         &self,
         raw_range: &str,
         highlight_color: &str,
+        change: Option<LineChange>,
     ) -> anyhow::Result<Vec<HighlightLine>> {
         let Range(start, end) = Range::from_str(&raw_range)?.parse_range(&self.code_snippet)?;
         let (start, end) = if self.is_relative_highlight_range {
@@ -82,21 +92,123 @@ impl HighlightLineRange {
             self.get_absolute_highlight_range(Range(start, end))
         }?;
 
-        Ok(vec![HighlightLine::Range(
-            start as u32,
-            end as u32,
-            highlight_color.to_string(),
-        )])
+        let (start, end, color) = (start as u32, end as u32, highlight_color.to_string());
+
+        Ok(vec![match change {
+            Some(change) => HighlightLine::Changed(start, end, color, change),
+            None => HighlightLine::Range(start, end, color),
+        }])
+    }
+
+    // The raw token is the line and the columns of the token, such as `3:5-9` or `3:5`, or the
+    // line and a regex which is wrapped by slashes, such as `3:/\bfoo\b/`
+    pub fn create_highlight_token(
+        &self,
+        raw_token: &str,
+        color: &str,
+        style: HighlightTokenStyle,
+    ) -> anyhow::Result<HighlightToken> {
+        let Some((line, target)) = raw_token.split_once(':') else {
+            bail!("Invalid highlight token: {}", raw_token);
+        };
+
+        Ok(HighlightToken {
+            line: self.parse_line(line)?,
+            target: parse_token_target(target)?,
+            color: color.to_string(),
+            style,
+        })
+    }
+
+    // The raw redaction is in the same format as the raw highlight token
+    pub fn create_redaction(&self, raw_redaction: &str) -> anyhow::Result<Redaction> {
+        let Some((line, target)) = raw_redaction.split_once(':') else {
+            bail!("Invalid redaction: {}", raw_redaction);
+        };
+
+        Ok(Redaction {
+            line: self.parse_line(line)?,
+            target: parse_token_target(target)?,
+        })
+    }
+
+    // The raw annotation is the line (and the token of the line) and the text separated by `=`,
+    // such as `3=Some note` or `3:/\bfoo\b/=Some note`, the `\n` in the text breaks the line
+    pub fn create_annotation(
+        &self,
+        raw_annotation: &str,
+        color: &str,
+        placement: AnnotationPlacement,
+        numbered: bool,
+    ) -> anyhow::Result<Annotation> {
+        let equal = raw_annotation.find('=');
+        // The regex of token may contain `=`, so the text is separated by the `=` after the regex
+        let separator = match raw_annotation.find(":/") {
+            Some(start) if equal.is_none_or(|equal| start < equal) => raw_annotation[start + 2..]
+                .find("/=")
+                .map(|end| start + 3 + end),
+            _ => equal,
+        };
+        let (anchor, text) = match separator {
+            Some(separator) => (
+                &raw_annotation[..separator],
+                &raw_annotation[separator + 1..],
+            ),
+            None => (raw_annotation, ""),
+        };
+        let (line, token) = match anchor.split_once(':') {
+            Some((line, target)) => (line, Some(parse_token_target(target)?)),
+            None => (anchor, None),
+        };
+
+        Ok(Annotation {
+            line: self.parse_line(line)?,
+            token,
+            text: text.replace("\\n", "\n"),
+            numbered,
+            placement,
+            color: color.to_string(),
+        })
+    }
+
+    fn parse_line(&self, line: &str) -> anyhow::Result<u32> {
+        let line = line.trim().parse::<usize>()?;
+        let (line, _) = if self.is_relative_highlight_range {
+            self.get_relative_highlight_range(Range(line, line))
+        } else {
+            self.get_absolute_highlight_range(Range(line, line))
+        }?;
+
+        Ok(line as u32)
     }
 
     pub fn create_multiple_highlight_lines(
         &self,
         raw_ranges: &Vec<String>,
         highlight_color: &str,
+        change: Option<LineChange>,
     ) -> anyhow::Result<Vec<HighlightLine>> {
         raw_ranges.iter().try_fold(vec![], |mut acc, range| {
-            acc.extend(self.create_highlight_lines(range, highlight_color)?);
+            acc.extend(self.create_highlight_lines(range, highlight_color, change)?);
             Ok(acc)
         })
     }
 }
+
+// The target is the columns of the token, such as `5-9` or `5`, or a regex which is wrapped by
+// slashes, such as `/\bfoo\b/`
+fn parse_token_target(target: &str) -> anyhow::Result<TokenTarget> {
+    let target = match target
+        .strip_prefix('/')
+        .and_then(|pattern| pattern.strip_suffix('/'))
+    {
+        Some(pattern) => TokenTarget::Pattern(pattern.to_string()),
+        None => {
+            let (start, end) = target.split_once('-').unwrap_or((target, target));
+
+            TokenTarget::Columns(start.trim().parse()?, end.trim().parse()?)
+        }
+    };
+
+    Ok(target)
+}
@@ -20,8 +20,19 @@ use code::create_code;
 use code_config::create_code_config;
 use codesnap::assets::Assets;
 use codesnap::assets::AssetsURL;
+use codesnap::config::ANSIColorMode;
+use codesnap::config::ASCIIFrameStyle;
 use codesnap::config::CodeSnap;
+use codesnap::config::Content;
+use codesnap::config::DiffLayout;
+use codesnap::config::FocusStyle;
+use codesnap::config::RedactionStyle;
+use codesnap::config::RevealMode;
+use codesnap::config::SecretDetector;
 use codesnap::config::SnapshotConfig;
+use codesnap::snapshot::snapshot_data::SnapshotData;
+use codesnap::snapshot::terminal_renderer::TerminalProtocol;
+use codesnap::utils::syntax_provider::SyntaxProvider;
 use config::CodeSnapCLIConfig;
 use egg::say;
 use theme_converter::{parser::Parser as ThemeParser, vscode};
@@ -35,6 +46,8 @@ use indicatif::{ProgressBar, ProgressStyle};
 
 pub const STDIN_CODE_DEFAULT_CHAR: &'static str = "-";
 
+pub const STDOUT_OUTPUT: &str = "-";
+
 /// CodeSnap is a CLI tool to generate beautiful snapshots of your code from terminal.
 #[derive(Parser)]
 #[command(bin_name = "codesnap")]
@@ -52,18 +65,71 @@ struct CLI {
     #[arg(long)]
     from_clipboard: bool,
 
+    /// Treat the input as a unified diff (such as the output of `git diff`) and render it as a
+    /// diff view, this is enabled automatically for `.diff` and `.patch` files
+    #[arg(long)]
+    diff: bool,
+
+    /// Draw the diff in one pane (`unified`), or draw the old and new versions side by side
+    /// (`split`)
+    #[arg(long, value_parser=["unified", "split"])]
+    diff_layout: Option<String>,
+
+    /// Treat the input as Markdown, the prose is rendered in a proportional font and each fenced
+    /// code block is highlighted by its own language, this is enabled automatically for `.md` and
+    /// `.markdown` files unless the `--language` is provided
+    #[arg(long)]
+    markdown: bool,
+
     /// Output path for the snapshot.
     /// Available value:
     ///
     /// - clipboard: Copy the snapshot to clipboard
+    /// - terminal: Display the snapshot in the terminal, the terminal should support kitty, iTerm2
+    ///   or sixel image protocol
     /// - file path: Save the snapshot to the file path
+    /// - -: Write the snapshot to stdout, the format should be specified by `--format`
     ///
-    /// Currently CodeSnap supports SVG format and PNG format
+    /// Currently CodeSnap supports SVG, PNG, JPEG, WebP, AVIF, HTML and PDF format, and ASCII
+    /// snapshot can be saved as `.txt` or `.md` file
     /// If output is directory, CodeSnap will generate a temporary file name to save the snapshot
     /// to the directory.
-    #[arg(short, long)]
+    #[arg(
+        short,
+        long,
+        required_unless_present = "list_languages",
+        default_value = ""
+    )]
     output: String,
 
+    /// The format of the snapshot, CodeSnap infers the format from the extension of output path
+    /// by default, this option is required when writing the snapshot to stdout
+    #[arg(long, value_parser=["png", "jpg", "jpeg", "webp", "avif", "svg", "html", "pdf", "gif", "txt", "md"])]
+    format: Option<String>,
+
+    /// The quality of lossy image formats (JPEG, WebP and AVIF), range from 1 to 100
+    #[arg(long)]
+    quality: Option<u8>,
+
+    /// Encode WebP losslessly
+    #[arg(long)]
+    lossless: bool,
+
+    /// JPEG doesn't support transparency, the transparent pixels are flattened onto the matte
+    /// color
+    #[arg(long)]
+    matte_color: Option<String>,
+
+    /// The image protocol used to display the snapshot in the terminal, CodeSnap detects the
+    /// protocol from environment variables by default, and fallback to sixel
+    #[arg(long, value_parser=["kitty", "iterm2", "sixel"])]
+    terminal_protocol: Option<String>,
+
+    /// Inline the fonts into the HTML output as base64 `@font-face`, so that the HTML file can
+    /// be opened anywhere without installing the fonts
+    #[arg(long)]
+    self_contained: bool,
+
     /// Executing a command and taking the output as the code snippet.
     #[arg(long, short, num_args=1..)]
     execute: Vec<String>,
@@ -79,6 +145,8 @@ struct CLI {
     /// represent the 3rd to the end, or use :5 to represent the start to the 5th.
     /// This option is useful when you use the `from_file` option as the input, and you just want
     /// to display part of the code snippet.
+    /// Multiple ranges are separated by commas, such as 1:5,40:52,90:, the lines between the
+    /// ranges are collapsed into fold markers.
     #[arg(long)]
     range: Option<String>,
 
@@ -86,10 +154,50 @@ struct CLI {
     #[arg(long)]
     code_font_family: Option<String>,
 
+    /// The max width of the code, the lines which are longer than the max width are wrapped, the
+    /// value is in columns by default, such as `80`, and in pixels if it ends with `px`, such as
+    /// `600px`
+    #[arg(long)]
+    max_width: Option<String>,
+
+    /// Wrap the long lines at the max width (`char`), or at the last word boundary before the
+    /// max width (`word`)
+    #[arg(long, value_parser=["none", "char", "word"])]
+    wrap_mode: Option<String>,
+
+    /// The font size of the code in pixels
+    #[arg(long)]
+    code_font_size: Option<f32>,
+
+    /// The height of each code line in pixels
+    #[arg(long)]
+    code_line_height: Option<f32>,
+
+    /// The extra space between chars of the code in pixels
+    #[arg(long, allow_hyphen_values = true)]
+    code_letter_spacing: Option<f32>,
+
+    /// Highlight the code by the Sublime Text grammars (`syntect`), or by the tree-sitter
+    /// grammars (`tree-sitter`), the languages which have no tree-sitter grammar are still
+    /// highlighted by the Sublime Text grammars
+    #[arg(long, value_parser=["syntect", "tree-sitter"])]
+    highlight_backend: Option<String>,
+
     /// Code theme for the code snippet
     #[arg(long)]
     code_theme: Option<String>,
 
+    /// Load the syntax definitions (`.sublime-syntax` and `.packdump` files) from the folders,
+    /// so that the languages which are not supported by default can be highlighted
+    #[arg(long, num_args=1..)]
+    syntaxes_folder: Vec<String>,
+
+    /// Download the syntax definition from the URL, the value is the name and the URL separated
+    /// by `@`, the same as the remote code theme, for example:
+    /// dsl@https://example.com/dsl.sublime-syntax
+    #[arg(long, num_args=1..)]
+    remote_syntax: Vec<String>,
+
     /// Breadcrumbs is a useful and unique feature in CodeSnap, it shows the path of the file
     /// so that users can know where the code snippet comes from.
     #[arg(long, default_value = "false")]
@@ -159,11 +267,106 @@ struct CLI {
     #[arg(long)]
     raw_highlight_lines: Option<String>,
 
+    /// Focus on the highlighted lines, the other lines are dimmed
+    #[arg(long, default_value = "false")]
+    focus: bool,
+
+    /// Reduce the opacity of the unfocused lines (`dim`), or turn them gray (`desaturate`)
+    #[arg(long, value_parser=["dim", "desaturate"])]
+    focus_style: Option<String>,
+
+    /// The opacity of the unfocused lines, range from 0 to 1
+    #[arg(long)]
+    focus_opacity: Option<f32>,
+
+    /// Blur the unfocused lines by the radius in pixels
+    #[arg(long)]
+    focus_blur: Option<f32>,
+
+    /// Highlight a part of a line, the value is the line and the columns (counted by chars
+    /// from 1) of the token, such as `3:5-9`, or the line and a regex wrapped by slashes which
+    /// matches the tokens, such as `3:/\bfoo\b/`
+    #[arg(long, num_args=1..)]
+    highlight_token: Vec<String>,
+
+    /// Highlight color for the highlighted tokens
+    #[arg(long, default_value = "#ffcb6b")]
+    highlight_token_color: String,
+
+    /// Draw a rounded box around the highlighted tokens (`box`), draw a line under them
+    /// (`underline`), or paint their background (`background`)
+    #[arg(long, value_parser=["box", "underline", "background"])]
+    highlight_token_style: Option<String>,
+
+    /// The highlight tokens in JSON, for example:
+    /// "[
+    ///   {"line": 3, "columns": [5, 9]},
+    ///   {"line": 4, "pattern": "foo", "color": "#ff0000", "style": "underline"}
+    /// ]"
+    #[arg(long)]
+    raw_highlight_tokens: Option<String>,
+
+    /// Hide a part of a line, the value is in the same format as `highlight_token`, such as
+    /// `3:5-9` or `3:/\bfoo\b/`, the chars are blurred in the image and replaced with `█` in the
+    /// ASCII snapshot
+    #[arg(long, num_args=1..)]
+    redact: Vec<String>,
+
+    /// Redact the secrets found by the built-in detectors, such as API keys, JWTs, AWS keys,
+    /// emails and IP addresses
+    #[arg(long, default_value = "false")]
+    redact_secrets: bool,
+
+    /// Blur (`blur`) or pixelate (`pixelate`) the redacted chars in the image
+    #[arg(long, value_parser=["blur", "pixelate"])]
+    redact_style: Option<String>,
+
+    /// Attach a note to a line or a token, the value is the line (or the line and the token in
+    /// the same format as `highlight_token`) and the text separated by `=`, such as
+    /// `3=Allocate the buffer` or `3:/\bbuf\b/=Allocate the buffer`, use `\n` to break the text
+    #[arg(long, num_args=1..)]
+    annotate: Vec<String>,
+
+    /// Draw the callouts of annotations in the right margin (`right`) or below the lines (`below`)
+    #[arg(long, value_parser=["right", "below"])]
+    annotation_placement: Option<String>,
+
+    /// Color of the callouts of annotations
+    #[arg(long, default_value = "#82aaff")]
+    annotation_color: String,
+
+    /// Prefix the callouts with numbered markers such as ①, ② and ③, so that they can be
+    /// referred from the surrounding prose
+    #[arg(long, default_value = "false")]
+    numbered_annotations: bool,
+
+    /// The annotations in JSON, for example:
+    /// "[
+    ///   {"line": 3, "text": "Allocate the buffer"},
+    ///   {"line": 5, "token": {"pattern": "len"}, "text": "Length", "placement": "below"}
+    /// ]"
+    #[arg(long)]
+    raw_annotations: Option<String>,
+
     /// Set the language of the code snippet, If you using the `file` option, CodeSnap will
     /// automatically detect the language from the file extension.
     #[arg(long, short)]
     language: Option<String>,
 
+    /// Map the alias onto the name or the extension of a language, the value is the alias and the
+    /// language separated by `=`, for example: tsx=TypeScriptReact
+    #[arg(long, num_args=1..)]
+    language_alias: Vec<String>,
+
+    /// Fail with the close matches of the language instead of rendering the code as plain text
+    /// if the language is not found or not detected
+    #[arg(long, default_value = "false")]
+    strict_language: bool,
+
+    /// List all the languages with their extensions and aliases, the snapshot is not generated
+    #[arg(long, default_value = "false")]
+    list_languages: bool,
+
     /// Used to detect the language of the code snippet, if you want to set language manually,
     /// use `--language` or `-l` option.
     #[arg(long)]
@@ -228,13 +431,96 @@ struct CLI {
     #[arg(long)]
     background: Option<String>,
 
-    #[arg(long, value_parser=["ascii", "image"], default_value="image")]
+    /// The type of snapshot, `animation` generates an animated "typing" snapshot, the code is
+    /// revealed progressively, the output format is determined by the extension of output path,
+    /// which can be `.gif`, `.png` (APNG) or `.webp`
+    ///
+    /// `ansi` generates an ASCII snapshot with the code highlighted by ANSI escape sequences,
+    /// the colors are disabled if the `NO_COLOR` environment variable is set
+    #[arg(long, value_parser=["ascii", "ansi", "image", "animation"], default_value="image")]
     r#type: String,
 
+    /// The frame style of ASCII snapshot, `plain` uses `+-|` for the environments which don't
+    /// support Unicode, and `markdown` wraps the code in a fenced code block, which is the
+    /// default style when saving ASCII snapshot to a `.md` file
+    #[arg(long, value_parser=["rounded", "double", "heavy", "plain", "markdown"])]
+    ascii_frame_style: Option<String>,
+
+    /// The colors of ANSI snapshot, use `256` for the terminals which don't support 24-bit colors
+    #[arg(long, value_parser=["truecolor", "256"])]
+    ansi_color_mode: Option<String>,
+
+    /// Reveal the code char by char or line by line in the animated snapshot
+    #[arg(long, value_parser=["char", "line"])]
+    reveal_mode: Option<String>,
+
+    /// The duration of each frame of the animated snapshot in milliseconds
+    #[arg(long)]
+    frame_delay: Option<u16>,
+
     #[arg(long)]
     config: Option<String>,
 }
 
+// The format of the snapshot, the `--format` option takes precedence over the extension of
+// output path
+fn output_format(cli: &CLI) -> anyhow::Result<String> {
+    if let Some(ref format) = cli.format {
+        return Ok(format.clone());
+    }
+
+    if cli.output == STDOUT_OUTPUT {
+        bail!("The `--format` option is required when writing the snapshot to stdout");
+    }
+
+    Path::new(&cli.output)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .context("Unsupported output format")
+}
+
+fn create_snapshot_data(cli: &CLI, snapshot: &SnapshotConfig) -> anyhow::Result<SnapshotData> {
+    match cli.r#type.as_str() {
+        "ascii" | "ansi" => {
+            if cli.output != STDOUT_OUTPUT && !matches!(output_format(cli)?.as_str(), "txt" | "md")
+            {
+                bail!("ASCII snapshot only supports `.txt` and `.md` format");
+            }
+
+            match cli.r#type.as_str() {
+                "ansi" => snapshot.create_ansi_snapshot()?.raw_data(),
+                _ => snapshot.create_ascii_snapshot()?.raw_data(),
+            }
+        }
+        "animation" => {
+            let animation_snapshot = snapshot.create_animation_snapshot()?;
+
+            match output_format(cli)?.as_str() {
+                "gif" => animation_snapshot.gif_data(),
+                "png" => animation_snapshot.apng_data(),
+                "webp" => animation_snapshot.webp_data(),
+                _ => bail!("Unsupported animation format"),
+            }
+        }
+        _ => {
+            let image_snapshot = snapshot.create_snapshot()?;
+
+            match output_format(cli)?.as_str() {
+                "png" => image_snapshot.png_data(),
+                "jpg" | "jpeg" => image_snapshot.jpeg_data(),
+                "webp" => image_snapshot.webp_data(),
+                "avif" => image_snapshot.avif_data(),
+                "svg" => image_snapshot.svg_data(),
+                "html" if cli.self_contained => image_snapshot.self_contained_html_data(),
+                "html" => image_snapshot.html_data(),
+                "pdf" => image_snapshot.pdf_data(),
+                _ => bail!("Unsupported output format"),
+            }
+        }
+    }
+}
+
 fn output_snapshot(cli: &CLI, snapshot: &SnapshotConfig) -> anyhow::Result<String> {
     // Save snapshot to clipboard
     if cli.output == "clipboard" {
@@ -242,6 +528,9 @@ fn output_snapshot(cli: &CLI, snapshot: &SnapshotConfig) -> anyhow::Result<Strin
             "ascii" => {
                 snapshot.create_ascii_snapshot()?.raw_data()?.copy()?;
             }
+            "ansi" => {
+                snapshot.create_ansi_snapshot()?.raw_data()?.copy()?;
+            }
             "image" => {
                 snapshot.create_snapshot()?.raw_data()?.copy()?;
             }
@@ -253,39 +542,57 @@ fn output_snapshot(cli: &CLI, snapshot: &SnapshotConfig) -> anyhow::Result<Strin
         return Ok("Snapshot copied to clipboard".to_string());
     }
 
-    let image_snapshot = snapshot.create_snapshot()?;
+    let snapshot_data = create_snapshot_data(cli, snapshot)?;
+
+    // Write snapshot to stdout
+    if cli.output == STDOUT_OUTPUT {
+        snapshot_data.write_to_stdout()?;
+
+        return Ok("Snapshot written to stdout".to_string());
+    }
 
     // Save snapshot to file
-    match cli.output.as_str() {
-        output if output.ends_with(".png") => {
-            image_snapshot.png_data()?.save(&cli.output)?;
-        }
-        output if output.ends_with(".svg") => {
-            image_snapshot.svg_data()?.save(&cli.output)?;
-        }
-        output if output.ends_with(".html") => {
-            image_snapshot.html_data()?.save(&cli.output)?;
-        }
-        _ => {
-            bail!("Unsupported output format");
-        }
-    };
+    snapshot_data.save(&cli.output)?;
 
-    Ok(format!("Snapshot saved to {} successful!", cli.output))
+    Ok(match cli.r#type.as_str() {
+        "animation" => format!("Animation saved to {} successful!", cli.output),
+        _ => format!("Snapshot saved to {} successful!", cli.output),
+    })
 }
 
 async fn generate_snapshot_with_config(cli: &CLI, codesnap: CodeSnap) -> anyhow::Result<()> {
     let snapshot = create_snapshot_config(&cli, codesnap).await?;
     let snapshot_type = cli.r#type.clone();
 
-    if snapshot_type == "ascii" && cli.output != "clipboard" {
-        logger::warn("ASCII snapshot only supports copying to clipboard");
+    if cli.output == "terminal" {
+        if snapshot_type == "animation" {
+            bail!(
+                "The animated snapshot cannot be previewed in terminal, save it to a file instead"
+            );
+        }
+
+        let protocol = match &cli.terminal_protocol {
+            Some(protocol) => protocol.parse::<TerminalProtocol>()?,
+            None => TerminalProtocol::detect(),
+        };
+        let preview = with_spinner(|| snapshot.create_snapshot()?.to_terminal(protocol))?;
+
+        println!("{}", preview);
+
+        return Ok(());
+    }
+
+    if snapshot_type == "animation" && cli.output == "clipboard" {
+        logger::warn("Animated snapshot cannot be copied to clipboard");
         return Ok(());
     }
 
     let message = with_spinner(|| output_snapshot(&cli, &snapshot))?;
 
-    logger::success(&message);
+    // Keep stdout clean for the piped snapshot
+    if cli.output != STDOUT_OUTPUT {
+        logger::success(&message);
+    }
 
     Ok(())
 }
@@ -321,6 +628,77 @@ async fn parse_code_theme(path: &str, code_theme: &str) -> anyhow::Result<String
     }
 }
 
+// Download the remote syntaxes, and return the folder which stores them
+async fn download_remote_syntaxes(remote_syntaxes: &[String]) -> anyhow::Result<String> {
+    let remote_syntaxes_path = home::home_dir()
+        .context("Unable to get your home dir")?
+        .join(".config")
+        .join("CodeSnap")
+        .join("remote_syntaxes");
+    std::fs::create_dir_all(&remote_syntaxes_path)?;
+
+    let remote_syntaxes_path = remote_syntaxes_path
+        .to_str()
+        .context("Invalid remote syntax path")?;
+    let assets = Assets::from(remote_syntaxes_path);
+
+    for remote_syntax in remote_syntaxes {
+        assets.download(remote_syntax).await?;
+    }
+
+    Ok(remote_syntaxes_path.to_string())
+}
+
+// The syntaxes and the language aliases, which are used to resolve the language of the code
+async fn create_language_config(cli: &CLI, codesnap: &mut SnapshotConfig) -> anyhow::Result<()> {
+    codesnap
+        .syntaxes_folders
+        .extend(cli.syntaxes_folder.iter().cloned());
+
+    if !cli.remote_syntax.is_empty() {
+        codesnap
+            .syntaxes_folders
+            .push(download_remote_syntaxes(&cli.remote_syntax).await?);
+    }
+
+    for language_alias in &cli.language_alias {
+        let (alias, language) = language_alias
+            .split_once('=')
+            .context(format!("Invalid language alias {}", language_alias))?;
+
+        codesnap
+            .language_config
+            .aliases
+            .insert(alias.trim().to_string(), language.trim().to_string());
+    }
+
+    codesnap.language_config.strict = cli.strict_language || codesnap.language_config.strict;
+
+    Ok(())
+}
+
+async fn list_languages(cli: &CLI, mut codesnap: CodeSnap) -> anyhow::Result<()> {
+    // The code is not needed for listing languages, an empty code is used to build the config
+    let mut codesnap = codesnap.map_code(|code| Ok(Content::Code(code)))?.build()?;
+
+    create_language_config(cli, &mut codesnap).await?;
+
+    let languages = SyntaxProvider::from_config(&codesnap)?.languages();
+    let name_width = languages
+        .iter()
+        .map(|language| language.name.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    for language in languages {
+        let tokens = [language.extensions, language.aliases].concat().join(", ");
+
+        println!("{:<name_width$}  {}", language.name, tokens);
+    }
+
+    Ok(())
+}
+
 async fn create_snapshot_config(
     cli: &CLI,
     mut codesnap: CodeSnap,
@@ -351,8 +729,57 @@ async fn create_snapshot_config(
 
     codesnap.themes_folders = themes_folders;
     codesnap.fonts_folders = codesnap.fonts_folders;
+    create_language_config(cli, &mut codesnap).await?;
+
     codesnap.line_number_color = cli.line_number_color.clone();
     codesnap.title = cli.title.clone();
+    codesnap.encoding.quality = cli.quality.unwrap_or(codesnap.encoding.quality);
+    codesnap.encoding.lossless = cli.lossless || codesnap.encoding.lossless;
+    codesnap.encoding.matte_color = cli
+        .matte_color
+        .clone()
+        .unwrap_or(codesnap.encoding.matte_color);
+    codesnap.animation.frame_delay = cli.frame_delay.unwrap_or(codesnap.animation.frame_delay);
+    codesnap.animation.reveal_mode = match cli.reveal_mode.as_deref() {
+        Some("line") => RevealMode::Line,
+        Some("char") => RevealMode::Char,
+        _ => codesnap.animation.reveal_mode,
+    };
+    codesnap.diff_config.layout = match cli.diff_layout.as_deref() {
+        Some("unified") => DiffLayout::Unified,
+        Some("split") => DiffLayout::Split,
+        _ => codesnap.diff_config.layout,
+    };
+    if cli.redact_secrets {
+        codesnap.redact_config.detectors = SecretDetector::all();
+    }
+    codesnap.redact_config.style = match cli.redact_style.as_deref() {
+        Some("blur") => RedactionStyle::Blur,
+        Some("pixelate") => RedactionStyle::Pixelate,
+        _ => codesnap.redact_config.style,
+    };
+    codesnap.focus_config.enabled = cli.focus || codesnap.focus_config.enabled;
+    codesnap.focus_config.style = match cli.focus_style.as_deref() {
+        Some("dim") => FocusStyle::Dim,
+        Some("desaturate") => FocusStyle::Desaturate,
+        _ => codesnap.focus_config.style,
+    };
+    codesnap.focus_config.opacity = cli.focus_opacity.unwrap_or(codesnap.focus_config.opacity);
+    codesnap.focus_config.blur = cli.focus_blur.unwrap_or(codesnap.focus_config.blur);
+    codesnap.ascii_config.frame_style = match cli.ascii_frame_style.as_deref() {
+        Some("rounded") => ASCIIFrameStyle::Rounded,
+        Some("double") => ASCIIFrameStyle::Double,
+        Some("heavy") => ASCIIFrameStyle::Heavy,
+        Some("plain") => ASCIIFrameStyle::Plain,
+        Some("markdown") => ASCIIFrameStyle::Markdown,
+        _ if output_format(cli).is_ok_and(|format| format == "md") => ASCIIFrameStyle::Markdown,
+        _ => codesnap.ascii_config.frame_style,
+    };
+    codesnap.ascii_config.color_mode = match cli.ansi_color_mode.as_deref() {
+        Some("truecolor") => ANSIColorMode::TrueColor,
+        Some("256") => ANSIColorMode::Ansi256,
+        _ => codesnap.ascii_config.color_mode,
+    };
     codesnap.theme = parse_code_theme(
         &remote_themes_path,
         cli.code_theme
@@ -398,6 +825,10 @@ async fn generate_snapshot() -> anyhow::Result<()> {
         CodeSnapCLIConfig::from(&config::get_config_content()?)?
     };
 
+    if cli.list_languages {
+        return list_languages(&cli, codesnap_cli_config.snapshot_config).await;
+    }
+
     generate_snapshot_with_config(&cli, codesnap_cli_config.snapshot_config).await?;
 
     if codesnap_cli_config.print_eggs {
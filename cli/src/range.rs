@@ -1,11 +1,18 @@
 use anyhow::bail;
+use codesnap::config::Fold;
 
 const RANGE_SEPARATOR: &'static str = ":";
+const RANGES_SEPARATOR: &str = ",";
 pub const DEFAULT_RANGE: &'static str = "start:end";
 
 #[derive(Debug)]
 pub struct Range<T>(pub T, pub T);
 
+// Multiple ranges such as "1:5,40:52,90:" display several parts of the code snippet in one
+// snapshot, the lines between the ranges are collapsed into fold markers
+#[derive(Debug)]
+pub struct Ranges(Vec<Range<usize>>);
+
 impl Range<String> {
     // Prepare range string by following rules:
     //
@@ -21,10 +28,6 @@ impl Range<String> {
         }
     }
 
-    pub fn from_opt_string(opt_str: Option<String>) -> anyhow::Result<Self> {
-        Range::from_str(&opt_str.unwrap_or(String::from(DEFAULT_RANGE)))
-    }
-
     pub fn from_str(range_str: &str) -> anyhow::Result<Self> {
         let range = Range::prepare_range(range_str);
         let range_points = range.split(RANGE_SEPARATOR).collect::<Vec<_>>();
@@ -71,6 +74,91 @@ impl Range<usize> {
     }
 }
 
+impl Ranges {
+    pub fn from_opt_string(opt_str: Option<String>, code_snippet: &str) -> anyhow::Result<Self> {
+        let mut ranges = opt_str
+            .unwrap_or(String::from(DEFAULT_RANGE))
+            .split(RANGES_SEPARATOR)
+            .map(|raw_range| Range::from_str(raw_range.trim())?.parse_range(code_snippet))
+            .collect::<anyhow::Result<Vec<Range<usize>>>>()?;
+
+        ranges.sort_by_key(|Range(start, _)| *start);
+
+        // Merge the overlapping and adjacent ranges, so that there is no empty fold between them
+        let ranges = ranges
+            .into_iter()
+            .fold(vec![], |mut merged: Vec<Range<usize>>, range| {
+                match merged.last_mut() {
+                    Some(last) if range.0 <= last.1 + 1 => last.1 = last.1.max(range.1),
+                    _ => merged.push(range),
+                }
+
+                merged
+            });
+
+        Ok(Ranges(ranges))
+    }
+
+    pub fn start(&self) -> usize {
+        self.0[0].0
+    }
+
+    pub fn end(&self) -> usize {
+        self.0[self.0.len() - 1].1
+    }
+
+    // The number of lines of the cut code snippet
+    pub fn line_count(&self) -> usize {
+        self.0.iter().map(|Range(start, end)| end + 1 - start).sum()
+    }
+
+    pub fn cut_code_snippet(&self, code_snippet: &str) -> anyhow::Result<String> {
+        Ok(self
+            .0
+            .iter()
+            .map(|range| range.cut_code_snippet(code_snippet))
+            .collect::<anyhow::Result<Vec<String>>>()?
+            .join("\n"))
+    }
+
+    // Each gap between two ranges is a fold, which is drawn before the first line of the latter
+    // range
+    pub fn folds(&self) -> Vec<Fold> {
+        let mut line = 1;
+
+        self.0
+            .windows(2)
+            .map(|ranges| {
+                let (Range(start, end), Range(next_start, _)) = (&ranges[0], &ranges[1]);
+
+                line += end + 1 - start;
+
+                Fold {
+                    line: line as u32,
+                    hidden_lines: (next_start - end - 1) as u32,
+                    label: None,
+                }
+            })
+            .collect()
+    }
+
+    // Convert the line number of the original code to the line number of the cut code snippet,
+    // returns None if the line is not in any range
+    pub fn snippet_line_number(&self, line_number: usize) -> Option<usize> {
+        let mut offset = 0;
+
+        for Range(start, end) in &self.0 {
+            if (*start..=*end).contains(&line_number) {
+                return Some(offset + line_number - start + 1);
+            }
+
+            offset += end + 1 - start;
+        }
+
+        None
+    }
+}
+
 fn parse_range_point(point: &str, lines: &Vec<&str>) -> anyhow::Result<usize> {
     let point = match point {
         "start" => 1,
@@ -80,3 +168,67 @@ fn parse_range_point(point: &str, lines: &Vec<&str>) -> anyhow::Result<usize> {
 
     Ok(point)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10";
+
+    fn ranges(raw_ranges: &str) -> Ranges {
+        Ranges::from_opt_string(Some(raw_ranges.to_string()), CODE).unwrap()
+    }
+
+    fn folds(raw_ranges: &str) -> Vec<(u32, u32)> {
+        ranges(raw_ranges)
+            .folds()
+            .into_iter()
+            .map(|fold| (fold.line, fold.hidden_lines))
+            .collect()
+    }
+
+    #[test]
+    fn parses_ranges() {
+        let ranges = Ranges::from_opt_string(None, CODE).unwrap();
+
+        assert_eq!((ranges.start(), ranges.end()), (1, 10));
+        assert_eq!(self::ranges("8:").start(), 8);
+        assert_eq!(self::ranges(":3").end(), 3);
+        // The reversed range is swapped
+        assert_eq!(self::ranges("5:2").line_count(), 4);
+        assert!(Ranges::from_opt_string(Some("1:2:3".to_string()), CODE).is_err());
+        assert!(Ranges::from_opt_string(Some("a:3".to_string()), CODE).is_err());
+    }
+
+    #[test]
+    fn merges_overlapping_and_adjacent_ranges() {
+        let ranges = ranges("6:8, 1:2, 3:4, 7:9");
+
+        assert_eq!(ranges.line_count(), 8);
+        assert_eq!(
+            ranges.cut_code_snippet(CODE).unwrap(),
+            "1\n2\n3\n4\n6\n7\n8\n9"
+        );
+        assert_eq!(folds("6:8, 1:2, 3:4, 7:9"), [(5, 1)]);
+    }
+
+    #[test]
+    fn folds_gaps_between_ranges() {
+        assert_eq!(folds("1:2,5:6,9:"), [(3, 2), (5, 2)]);
+        assert_eq!(folds("3"), []);
+        assert_eq!(folds("1:9,10"), []);
+    }
+
+    #[test]
+    fn converts_line_numbers_to_snippet() {
+        let ranges = ranges("2:3,7:8");
+
+        assert_eq!(ranges.snippet_line_number(2), Some(1));
+        assert_eq!(ranges.snippet_line_number(3), Some(2));
+        assert_eq!(ranges.snippet_line_number(7), Some(3));
+        assert_eq!(ranges.snippet_line_number(8), Some(4));
+        assert_eq!(ranges.snippet_line_number(1), None);
+        assert_eq!(ranges.snippet_line_number(5), None);
+        assert_eq!(ranges.snippet_line_number(9), None);
+    }
+}
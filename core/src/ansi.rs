@@ -1,18 +1,18 @@
 use cansi::v3::{categorise_text, Color};
-use cosmic_text::{Attrs, Family};
+use cosmic_text::Attrs;
 
 use crate::utils::color::parse_hex_to_cosmic_color;
 
-pub struct ANSI {
+pub struct ANSI<'a> {
     raw_text: String,
-    font_family: String,
+    attrs: Attrs<'a>,
 }
 
-impl ANSI {
-    pub fn from(text: &str, font_family: String) -> Self {
+impl<'a> ANSI<'a> {
+    pub fn from(text: &str, attrs: Attrs<'a>) -> Self {
         Self {
             raw_text: text.to_string(),
-            font_family,
+            attrs,
         }
     }
 
@@ -25,9 +25,9 @@ impl ANSI {
 
                 (
                     category.text,
-                    Attrs::new()
-                        .color(parse_hex_to_cosmic_color(hex_color))
-                        .family(Family::Name(self.font_family.as_str())),
+                    self.attrs
+                        .clone()
+                        .color(parse_hex_to_cosmic_color(hex_color)),
                 )
             })
             .collect::<Vec<(&str, Attrs)>>()
@@ -1,3 +1,4 @@
+pub mod annotations;
 pub mod background;
 pub mod breadcrumbs;
 pub mod code_block;
@@ -8,5 +9,7 @@ pub mod highlight_code_block;
 pub mod interface;
 pub mod layout;
 pub mod line_number;
+pub mod markdown;
 pub mod rect;
+pub mod split_pane;
 pub mod watermark;
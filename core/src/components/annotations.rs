@@ -0,0 +1,505 @@
+use std::{collections::HashMap, ops::Range};
+
+use tiny_skia::{Color, FillRule, Paint, PathBuilder, Rect, Transform};
+
+use crate::{
+    config::{self, Annotation, AnnotationPlacement, Fold, TokenTarget},
+    utils::{
+        annotation::{below_callout_rows, callout_texts},
+        code::prepare_code,
+        color::RgbaColor,
+        fold::folds_by_line,
+        token::target_ranges,
+        wrap::wrap_code,
+    },
+};
+
+use super::{
+    editor::code::{
+        code_attrs, code_metrics, fold_markers, insert_layout_spans, rounded_rect_path, tag_spans,
+    },
+    interface::{
+        component::{Component, ComponentContext, RenderParams},
+        render_error,
+        scene::SceneElement,
+        style::{ComponentStyle, RawComponentStyle, Size, Style},
+    },
+};
+
+// The gap between the code and the callouts in the right margin, the connectors turn in the middle
+// of the gap
+const CALLOUT_GAP: f32 = 32.;
+const CALLOUT_SPACING: f32 = 4.;
+const CALLOUT_PADDING: f32 = 6.;
+const CALLOUT_RADIUS: f32 = 4.;
+const CONNECTOR_WIDTH: f32 = 1.;
+const ANCHOR_DOT_OFFSET: f32 = 6.;
+const ANCHOR_DOT_RADIUS: f32 = 2.5;
+// The distance between the bracket and the bottom of the line, and the height of the bracket ends
+const BRACKET_OFFSET: f32 = 2.;
+const BRACKET_HEIGHT: f32 = 4.;
+
+// The whole line is annotated without the leading and trailing spaces
+const LINE_PATTERN: &str = r"\S(?:.*\S)?";
+
+// The bounds of the annotated chars in the last visual row of them, the bounds of an empty line
+// is at the start of the row and has no width
+#[derive(Clone, Copy)]
+struct Anchor {
+    left: f32,
+    right: f32,
+    top: f32,
+    bottom: f32,
+}
+
+struct Callout {
+    anchor: Anchor,
+    bubble: Rect,
+    text: String,
+    color: Color,
+    placement: AnnotationPlacement,
+}
+
+// The callouts are placed around the code, the code is the only child of this component, and the
+// size of this component includes the callouts, so that the window is large enough for them
+pub struct Annotations {
+    children: Vec<Box<dyn Component>>,
+    code: String,
+    folds: Vec<Fold>,
+    annotations: Vec<Annotation>,
+    callout_texts: Vec<String>,
+    // The byte ranges of the annotated chars in the prepared code, the annotation of an empty line
+    // is attached to the start of the line
+    ranges: Vec<Option<Range<usize>>>,
+}
+
+impl Component for Annotations {
+    fn name(&self) -> &str {
+        "Annotations"
+    }
+
+    fn children(&self) -> &Vec<Box<dyn Component>> {
+        &self.children
+    }
+
+    fn style(&self, context: &ComponentContext) -> RawComponentStyle {
+        let code_style = self.code_style(context);
+        let (width, height) = self.callouts(context, 0., 0.).iter().fold(
+            (code_style.width, code_style.height),
+            |(w, h), callout| {
+                (
+                    w.max(callout.bubble.right()),
+                    h.max(callout.bubble.bottom()),
+                )
+            },
+        );
+
+        Style::default().size(Size::Num(width), Size::Num(height))
+    }
+
+    fn draw_self(
+        &self,
+        pixmap: &mut tiny_skia::Pixmap,
+        context: &ComponentContext,
+        render_params: &RenderParams,
+        _style: &ComponentStyle,
+        _parent_style: &ComponentStyle,
+    ) -> render_error::Result<()> {
+        let code_config = &context.take_snapshot_params.code_config;
+        let callout_column = render_params.x + self.code_style(context).width + CALLOUT_GAP;
+        let background: Color = context.theme_provider.theme_background().into();
+        let background = background.to_color_u8();
+        // The text is drawn in the background color of editor, so that it's readable on the
+        // callouts which are colored as the annotations
+        let text_attrs = code_attrs(code_config).color(cosmic_text::Color::rgb(
+            background.red(),
+            background.green(),
+            background.blue(),
+        ));
+
+        for callout in self.callouts(context, render_params.x, render_params.y) {
+            let (anchor, bubble) = (callout.anchor, callout.bubble);
+            let anchor_middle = (anchor.top + anchor.bottom) / 2.;
+            let bubble_middle = (bubble.top() + bubble.bottom()) / 2.;
+
+            match callout.placement {
+                AnnotationPlacement::Right => {
+                    let dot_x = anchor.right + ANCHOR_DOT_OFFSET;
+                    let turn_x = callout_column - CALLOUT_GAP / 2.;
+
+                    draw_dot(pixmap, context, dot_x, anchor_middle, callout.color);
+                    draw_segments(
+                        pixmap,
+                        context,
+                        &[
+                            (dot_x, anchor_middle, turn_x, anchor_middle),
+                            (turn_x, anchor_middle, turn_x, bubble_middle),
+                            (turn_x, bubble_middle, bubble.left(), bubble_middle),
+                        ],
+                        callout.color,
+                    );
+                }
+                AnnotationPlacement::Below => {
+                    let bracket_y = anchor.bottom + BRACKET_OFFSET;
+                    let middle_x = (anchor.left + anchor.right) / 2.;
+
+                    draw_segments(
+                        pixmap,
+                        context,
+                        &[
+                            (
+                                anchor.left,
+                                bracket_y - BRACKET_HEIGHT,
+                                anchor.left,
+                                bracket_y,
+                            ),
+                            (anchor.left, bracket_y, anchor.right, bracket_y),
+                            (
+                                anchor.right,
+                                bracket_y - BRACKET_HEIGHT,
+                                anchor.right,
+                                bracket_y,
+                            ),
+                            (middle_x, bracket_y, middle_x, bubble.top()),
+                        ],
+                        callout.color,
+                    );
+                }
+            }
+
+            draw_bubble(pixmap, context, bubble, callout.color);
+
+            let text = context.font_renderer.lock().unwrap().draw_text(
+                bubble.left() + CALLOUT_PADDING,
+                bubble.top(),
+                code_metrics(code_config),
+                vec![(callout.text.as_str(), text_attrs.clone())],
+                pixmap,
+            );
+            context.scene.lock().unwrap().push(text);
+        }
+
+        Ok(())
+    }
+}
+
+impl Annotations {
+    pub fn new(code_content: &config::Code, code: Box<dyn Component>) -> anyhow::Result<Self> {
+        let line_pattern = TokenTarget::Pattern(LINE_PATTERN.to_string());
+        let (token_targets, line_targets): (Vec<_>, Vec<_>) = code_content
+            .annotations
+            .iter()
+            .map(|annotation| {
+                (
+                    (
+                        annotation.line,
+                        annotation.token.as_ref().unwrap_or(&line_pattern),
+                    ),
+                    (annotation.line, &line_pattern),
+                )
+            })
+            .unzip();
+        // The annotation is attached to the whole line if its token is not found
+        let ranges = first_ranges(&code_content.content, &token_targets)?
+            .into_iter()
+            .zip(first_ranges(&code_content.content, &line_targets)?)
+            .map(|(token_range, line_range)| token_range.or(line_range))
+            .collect();
+
+        Ok(Annotations {
+            children: vec![code],
+            code: prepare_code(&code_content.content),
+            folds: code_content.folds.clone(),
+            annotations: code_content.annotations.clone(),
+            callout_texts: callout_texts(&code_content.annotations),
+            ranges,
+        })
+    }
+
+    fn code_style(&self, context: &ComponentContext) -> ComponentStyle {
+        self.children[0].parsed_style(None, context)
+    }
+
+    // The index of the last visual row of each line, the fold markers and the wrapped rows are
+    // counted
+    fn last_rows(&self, context: &ComponentContext) -> Vec<usize> {
+        let wrapped_lines = wrap_code(
+            &self.code,
+            &context.take_snapshot_params.code_config,
+            &mut context.font_renderer.lock().unwrap(),
+        );
+        let mut rows = 0;
+
+        wrapped_lines
+            .iter()
+            .zip(folds_by_line(wrapped_lines.len(), &self.folds))
+            .map(|(breaks, line_folds)| {
+                rows += line_folds.len() + breaks.len() + 1;
+                rows - 1
+            })
+            .collect()
+    }
+
+    // The anchors of annotations, the annotated chars are tagged and laid out as the code, so that
+    // the anchors are measured by the shaped glyphs
+    fn anchors(&self, context: &ComponentContext, x: f32, y: f32) -> Vec<Option<Anchor>> {
+        let code_config = &context.take_snapshot_params.code_config;
+        let mut ranges: Vec<Range<usize>> = vec![];
+        // The annotations which are attached to the same chars share the same tag
+        let tags = self
+            .ranges
+            .iter()
+            .map(|range| {
+                range.as_ref().map(
+                    |range| match ranges.iter().position(|tagged| tagged == range) {
+                        Some(index) => index + 1,
+                        None => {
+                            ranges.push(range.clone());
+                            ranges.len()
+                        }
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let fold_markers = fold_markers(&self.code, &self.folds);
+        let spans = insert_layout_spans(
+            tag_spans(vec![(self.code.as_str(), code_attrs(code_config))], &ranges),
+            &self.code,
+            &fold_markers,
+            context,
+            code_attrs(code_config),
+        );
+        let rects = context.font_renderer.lock().unwrap().span_rects(
+            x,
+            y,
+            code_metrics(code_config),
+            spans,
+            None,
+        );
+        let last_rows = self.last_rows(context);
+
+        self.annotations
+            .iter()
+            .zip(tags)
+            .map(|(annotation, tag)| {
+                let tagged_rect = tag.and_then(|tag| {
+                    rects
+                        .iter()
+                        .rev()
+                        .find(|(rect_tag, _)| *rect_tag == tag)
+                        .map(|(_, rect)| *rect)
+                });
+
+                match tagged_rect {
+                    Some(rect) => Some(Anchor {
+                        left: rect.left(),
+                        right: rect.right(),
+                        top: rect.top(),
+                        bottom: rect.bottom(),
+                    }),
+                    None => {
+                        let row = *last_rows.get((annotation.line as usize).checked_sub(1)?)?;
+                        let top = y + row as f32 * code_config.line_height;
+
+                        Some(Anchor {
+                            left: x,
+                            right: x,
+                            top,
+                            bottom: top + code_config.line_height,
+                        })
+                    }
+                }
+            })
+            .collect()
+    }
+
+    // Place the callouts of annotations, the callouts in the right margin are placed in the rows of
+    // their anchors, and pushed down if they overlap the previous one. The callouts below the line
+    // are placed in the rows reserved by `reserve_annotation_rows`
+    fn callouts(&self, context: &ComponentContext, x: f32, y: f32) -> Vec<Callout> {
+        let code_config = &context.take_snapshot_params.code_config;
+        let callout_column = x + self.code_style(context).width + CALLOUT_GAP;
+        let mut right_bottom = f32::MIN;
+        // The rows which are taken by the callouts below each line
+        let mut below_rows: HashMap<u32, usize> = HashMap::new();
+        let mut callouts = vec![];
+
+        for ((annotation, text), anchor) in self
+            .annotations
+            .iter()
+            .zip(&self.callout_texts)
+            .zip(self.anchors(context, x, y))
+        {
+            let Some(anchor) = anchor else {
+                continue;
+            };
+            let (text_width, text_height) = context
+                .font_renderer
+                .lock()
+                .unwrap()
+                .measure_text_with_attrs(code_metrics(code_config), text, &code_attrs(code_config));
+            let (left, top) = match annotation.placement {
+                AnnotationPlacement::Right => {
+                    let top = match right_bottom > anchor.top - CALLOUT_SPACING {
+                        true => right_bottom + CALLOUT_SPACING,
+                        false => anchor.top,
+                    };
+
+                    right_bottom = top + text_height;
+                    (callout_column, top)
+                }
+                AnnotationPlacement::Below => {
+                    let line_rows = below_rows.entry(annotation.line).or_default();
+                    let rows = *line_rows;
+
+                    *line_rows += below_callout_rows(text);
+
+                    // The callout is centered under the anchor, but doesn't exceed the left edge
+                    // of the code
+                    (
+                        ((anchor.left + anchor.right - text_width) / 2. - CALLOUT_PADDING)
+                            .max(x - CALLOUT_PADDING),
+                        anchor.bottom + (rows + 1) as f32 * code_config.line_height,
+                    )
+                }
+            };
+            let Some(bubble) =
+                Rect::from_xywh(left, top, text_width + 2. * CALLOUT_PADDING, text_height)
+            else {
+                continue;
+            };
+            let color: RgbaColor = annotation.color.as_str().into();
+
+            callouts.push(Callout {
+                anchor,
+                bubble,
+                text: text.clone(),
+                color: color.into(),
+                placement: annotation.placement.clone(),
+            });
+        }
+
+        callouts
+    }
+}
+
+// The first range of each target, the pattern may match multiple times
+fn first_ranges(
+    code: &str,
+    targets: &[(u32, &TokenTarget)],
+) -> anyhow::Result<Vec<Option<Range<usize>>>> {
+    let ranges = target_ranges(code, targets)?;
+
+    Ok((0..targets.len())
+        .map(|index| {
+            ranges
+                .iter()
+                .find(|(_, target_index)| *target_index == index)
+                .map(|(range, _)| range.clone())
+        })
+        .collect())
+}
+
+fn draw_bubble(
+    pixmap: &mut tiny_skia::Pixmap,
+    context: &ComponentContext,
+    bubble: Rect,
+    color: Color,
+) {
+    let Some(path) = rounded_rect_path(bubble, CALLOUT_RADIUS) else {
+        return;
+    };
+    let mut paint = Paint::default();
+
+    paint.set_color(color);
+    paint.anti_alias = true;
+    pixmap.fill_path(
+        &path,
+        &paint,
+        FillRule::Winding,
+        Transform::from_scale(context.scale_factor, context.scale_factor),
+        None,
+    );
+    context.scene.lock().unwrap().push(SceneElement::Rect {
+        x: bubble.x(),
+        y: bubble.y(),
+        width: bubble.width(),
+        height: bubble.height(),
+        radius: CALLOUT_RADIUS,
+        color,
+    });
+}
+
+fn draw_dot(
+    pixmap: &mut tiny_skia::Pixmap,
+    context: &ComponentContext,
+    x: f32,
+    y: f32,
+    color: Color,
+) {
+    let mut path_builder = PathBuilder::new();
+
+    path_builder.push_circle(x, y, ANCHOR_DOT_RADIUS);
+
+    let Some(path) = path_builder.finish() else {
+        return;
+    };
+    let mut paint = Paint::default();
+
+    paint.set_color(color);
+    paint.anti_alias = true;
+    pixmap.fill_path(
+        &path,
+        &paint,
+        FillRule::Winding,
+        Transform::from_scale(context.scale_factor, context.scale_factor),
+        None,
+    );
+    context.scene.lock().unwrap().push(SceneElement::Circle {
+        cx: x,
+        cy: y,
+        radius: ANCHOR_DOT_RADIUS,
+        color,
+    });
+}
+
+// The connectors are made of horizontal and vertical segments, so that they can be drawn as rects
+// in all outputs, the segment without length is skipped
+fn draw_segments(
+    pixmap: &mut tiny_skia::Pixmap,
+    context: &ComponentContext,
+    segments: &[(f32, f32, f32, f32)],
+    color: Color,
+) {
+    let mut paint = Paint::default();
+
+    paint.set_color(color);
+
+    for (x1, y1, x2, y2) in segments {
+        let half_width = CONNECTOR_WIDTH / 2.;
+        let Some(rect) = Rect::from_ltrb(
+            x1.min(*x2) - half_width,
+            y1.min(*y2) - half_width,
+            x1.max(*x2) + half_width,
+            y1.max(*y2) + half_width,
+        )
+        .filter(|_| x1 != x2 || y1 != y2) else {
+            continue;
+        };
+
+        pixmap.fill_rect(
+            rect,
+            &paint,
+            Transform::from_scale(context.scale_factor, context.scale_factor),
+            None,
+        );
+        context.scene.lock().unwrap().push(SceneElement::Rect {
+            x: rect.x(),
+            y: rect.y(),
+            width: rect.width(),
+            height: rect.height(),
+            radius: 0.,
+            color,
+        });
+    }
+}
@@ -1,6 +1,7 @@
-use tiny_skia::{LinearGradient, Paint, Pixmap, Point, Rect, SpreadMode, Transform};
+use tiny_skia::{Color, LinearGradient, Paint, Pixmap, Point, Rect, SpreadMode, Transform};
 
 use crate::{
+    config,
     edges::{edge::Edge, padding::Padding},
     utils::{color::RgbaColor, helpers::convert_vecs},
 };
@@ -8,6 +9,7 @@ use crate::{
 use super::interface::{
     component::{Component, ComponentContext, RenderParams},
     render_error::{self},
+    scene::{SceneElement, ScenePaint},
     style::{ComponentAlign, ComponentStyle, RawComponentStyle},
 };
 
@@ -27,7 +29,7 @@ impl Background {
 }
 
 impl Component for Background {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "Background"
     }
 
@@ -60,15 +62,36 @@ impl Component for Background {
 
         paint.anti_alias = false;
 
-        match &params.background {
+        let scene_paint = match &params.background {
             crate::config::Background::Solid(solid_background) => {
                 let rgba_color: RgbaColor = solid_background.as_str().into();
+                let color: Color = rgba_color.into();
 
-                paint.set_color(rgba_color.into());
+                paint.set_color(color);
+                ScenePaint::Solid(color)
             }
             crate::config::Background::Gradient(gradient_background) => {
                 let start = gradient_background.start.into_f32_point(w, h);
                 let end = gradient_background.end.into_f32_point(w, h);
+                let scene_paint = ScenePaint::LinearGradient {
+                    start: config::Point {
+                        x: start.x / context.scale_factor,
+                        y: start.y / context.scale_factor,
+                    },
+                    end: config::Point {
+                        x: end.x / context.scale_factor,
+                        y: end.y / context.scale_factor,
+                    },
+                    stops: gradient_background
+                        .stops
+                        .iter()
+                        .map(|stop| {
+                            let rgba_color: RgbaColor = stop.color.as_str().into();
+
+                            (stop.position, rgba_color.into())
+                        })
+                        .collect(),
+                };
 
                 paint.shader = LinearGradient::new(
                     Point::from_xy(start.x, start.y),
@@ -78,6 +101,7 @@ impl Component for Background {
                     Transform::identity(),
                 )
                 .unwrap();
+                scene_paint
             }
         };
 
@@ -87,6 +111,15 @@ impl Component for Background {
             Transform::identity(),
             None,
         );
+        context
+            .scene
+            .lock()
+            .unwrap()
+            .push(SceneElement::Background {
+                width: w / context.scale_factor,
+                height: h / context.scale_factor,
+                paint: scene_paint,
+            });
 
         Ok(())
     }
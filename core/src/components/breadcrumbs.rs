@@ -5,7 +5,7 @@ use regex::Regex;
 
 use crate::{
     edges::margin::Margin,
-    utils::{code::calc_wh_with_min_width, color::RgbaColor},
+    utils::{code::min_width, color::RgbaColor},
 };
 
 use super::interface::{
@@ -21,7 +21,7 @@ pub struct Breadcrumbs {
 }
 
 impl Component for Breadcrumbs {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "Breadcrumbs"
     }
 
@@ -43,7 +43,23 @@ impl Component for Breadcrumbs {
         self.path
             .as_ref()
             .and_then(|path| {
-                let (w, h) = calc_wh_with_min_width(&path, 8., LINE_HEIGHT);
+                let path = parse_separator(
+                    path,
+                    &context
+                        .take_snapshot_params
+                        .code_config
+                        .breadcrumbs
+                        .separator,
+                );
+                let attrs = Attrs::new().family(Family::Name(
+                    &context.take_snapshot_params.code_config.font_family,
+                ));
+                let (w, h) = context
+                    .font_renderer
+                    .lock()
+                    .unwrap()
+                    .measure_text_with_attrs(Metrics::new(12., LINE_HEIGHT), &path, &attrs);
+                let w = min_width(w);
 
                 return Some(
                     style
@@ -80,13 +96,14 @@ impl Component for Breadcrumbs {
                 &context.take_snapshot_params.code_config.font_family,
             ));
 
-            context.font_renderer.lock().unwrap().draw_text(
+            let text = context.font_renderer.lock().unwrap().draw_text(
                 render_params.x,
                 render_params.y,
                 Metrics::new(12., LINE_HEIGHT),
                 vec![(&path, attrs)],
                 pixmap,
             );
+            context.scene.lock().unwrap().push(text);
         }
 
         Ok(())
@@ -8,7 +8,7 @@ pub struct CodeBlock {
 }
 
 impl Component for CodeBlock {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "CodeBlock"
     }
 
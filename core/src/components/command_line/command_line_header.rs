@@ -1,10 +1,13 @@
-use cosmic_text::{Attrs, Family, Metrics, Weight};
+use cosmic_text::Weight;
 
 use crate::{
-    components::interface::{
-        component::{Component, ComponentContext, RenderParams},
-        render_error,
-        style::{ComponentStyle, RawComponentStyle, Size, Style},
+    components::{
+        editor::code::{code_attrs, command_line_metrics, push_redacted_text, secret_rects},
+        interface::{
+            component::{Component, ComponentContext, RenderParams},
+            render_error,
+            style::{ComponentStyle, RawComponentStyle, Size, Style},
+        },
     },
     utils::color::parse_hex_to_cosmic_color,
 };
@@ -12,7 +15,6 @@ use crate::{
 pub struct CommandLineHeader {
     children: Vec<Box<dyn Component>>,
     full_command: String,
-    metrics: Metrics,
 }
 
 impl Component for CommandLineHeader {
@@ -25,11 +27,16 @@ impl Component for CommandLineHeader {
             "{} {}",
             context.take_snapshot_params.command_output_config.prompt, self.full_command
         );
+        let code_config = &context.take_snapshot_params.code_config;
         let (w, h) = context
             .font_renderer
             .lock()
             .unwrap()
-            .measure_text(self.metrics, parsed_line.as_str());
+            .measure_text_with_attrs(
+                command_line_metrics(code_config),
+                parsed_line.as_str(),
+                &code_attrs(code_config),
+            );
 
         Style::default().size(Size::Num(w), Size::Num(h))
     }
@@ -45,15 +52,8 @@ impl Component for CommandLineHeader {
         let command_config = context.take_snapshot_params.command_output_config.clone();
         let command_and_args = self.full_command.split_whitespace().collect::<Vec<&str>>();
         let command_str = command_and_args[0];
-        let create_attrs = || {
-            Attrs::new().family(Family::Name(
-                context
-                    .take_snapshot_params
-                    .code_config
-                    .font_family
-                    .as_str(),
-            ))
-        };
+        let code_config = &context.take_snapshot_params.code_config;
+        let create_attrs = || code_attrs(code_config);
         let with_space = |str: &str| format!("{} ", str);
         let prompt = with_space(&context.take_snapshot_params.command_output_config.prompt);
         let command_str = with_space(command_str);
@@ -71,14 +71,24 @@ impl Component for CommandLineHeader {
             ),
             (args.as_str(), create_attrs()),
         ];
+        let metrics = command_line_metrics(code_config);
+        let redaction_rects = secret_rects(
+            context,
+            render_params.x,
+            render_params.y,
+            metrics,
+            spans.clone(),
+            None,
+        );
 
-        context.font_renderer.lock().unwrap().draw_text(
+        let text = context.font_renderer.lock().unwrap().draw_text(
             render_params.x,
             render_params.y,
-            self.metrics,
+            metrics,
             spans,
             pixmap,
         );
+        push_redacted_text(pixmap, context, text, redaction_rects);
 
         Ok(())
     }
@@ -89,7 +99,6 @@ impl CommandLineHeader {
         CommandLineHeader {
             full_command: full_command.to_string(),
             children: vec![],
-            metrics: Metrics::new(12.5, 20.),
         }
     }
 }
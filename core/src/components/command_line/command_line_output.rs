@@ -1,18 +1,18 @@
-use cosmic_text::Metrics;
-
 use crate::{
     ansi::ANSI,
-    components::interface::{
-        component::{self, Component, ComponentContext},
-        render_error,
-        style::{self, RawComponentStyle, Size, Style},
+    components::{
+        editor::code::{code_attrs, command_line_metrics, push_redacted_text, secret_rects},
+        interface::{
+            component::{self, Component, ComponentContext},
+            render_error,
+            style::{self, RawComponentStyle, Size, Style},
+        },
     },
 };
 
 pub struct CommandLineOutput {
     ansi_text: String,
     children: Vec<Box<dyn Component>>,
-    metrics: Metrics,
 }
 
 impl Component for CommandLineOutput {
@@ -21,11 +21,16 @@ impl Component for CommandLineOutput {
     }
 
     fn style(&self, context: &ComponentContext) -> RawComponentStyle {
+        let code_config = &context.take_snapshot_params.code_config;
         let (w, h) = context
             .font_renderer
             .lock()
             .unwrap()
-            .measure_text(self.metrics, &self.ansi_text);
+            .measure_text_with_attrs(
+                command_line_metrics(code_config),
+                &self.ansi_text,
+                &code_attrs(code_config),
+            );
 
         Style::default().size(Size::Num(w), Size::Num(h))
     }
@@ -38,24 +43,32 @@ impl Component for CommandLineOutput {
         _style: &style::ComponentStyle,
         _parent_style: &style::ComponentStyle,
     ) -> render_error::Result<()> {
-        let ansi = ANSI::from(
-            &self.ansi_text,
-            context.take_snapshot_params.code_config.font_family.clone(),
-        );
+        let code_config = &context.take_snapshot_params.code_config;
+        let ansi = ANSI::from(&self.ansi_text, code_attrs(code_config));
         let spans = ansi.colorize();
+        let metrics = command_line_metrics(code_config);
+        let redaction_rects = secret_rects(
+            context,
+            render_params.x,
+            render_params.y,
+            metrics,
+            spans.clone(),
+            None,
+        );
 
-        context.font_renderer.lock().unwrap().draw_text(
+        let text = context.font_renderer.lock().unwrap().draw_text(
             render_params.x,
             render_params.y,
-            self.metrics,
+            metrics,
             spans.clone(),
             pixmap,
         );
+        push_redacted_text(pixmap, context, text, redaction_rects);
 
         Ok(())
     }
 
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "CommandLineOutput"
     }
 }
@@ -65,7 +78,6 @@ impl CommandLineOutput {
         CommandLineOutput {
             ansi_text: ansi_text.to_string(),
             children: vec![],
-            metrics: Metrics::new(12.5, 20.),
         }
     }
 }
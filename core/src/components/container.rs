@@ -11,7 +11,7 @@ pub struct Container {
 }
 
 impl Component for Container {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "Container"
     }
 
@@ -33,6 +33,11 @@ impl Container {
         )
         .unwrap();
 
+        context
+            .scene
+            .lock()
+            .unwrap()
+            .resize(style.width, style.height, context.scale_factor);
         self.draw(
             &mut pixmap,
             context,
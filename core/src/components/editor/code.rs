@@ -1,28 +1,577 @@
-use cosmic_text::Metrics;
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use cosmic_text::{Attrs, Family, Metrics};
 use syntect::{
     easy::HighlightLines,
     parsing::{SyntaxReference, SyntaxSet},
 };
 
+#[cfg(feature = "tree-sitter")]
+use crate::config::HighlightBackend;
 use crate::{
     components::interface::{
         component::{Component, ComponentContext, RenderParams},
         render_error,
+        scene::SceneElement,
         style::{ComponentStyle, RawComponentStyle, Size, Style},
     },
-    config::{self},
-    utils::{code::prepare_code, highlight::Highlight, syntax_provider::SyntaxProvider},
+    config::{self, CodeConfig, Fold, HighlightLine, HighlightTokenStyle, RedactionStyle},
+    utils::{
+        blur::{apply, ImageRefMut},
+        code::prepare_code,
+        color::{parse_hex_to_cosmic_color, RgbaColor},
+        focus::unfocused_lines,
+        fold::{fold_marker, folds_by_line},
+        highlight::{Highlight, HighlightResult},
+        redact::{redaction_target_ranges, secret_ranges},
+        syntax_provider::SyntaxProvider,
+        text::{FontRenderer, DECORATION_METADATA},
+        token::token_ranges,
+        wrap::{wrap_code, wrap_text, WRAP_INDICATOR},
+    },
 };
+use rgb::{FromSlice, RGBA8};
+use tiny_skia::{
+    BlendMode, Color, IntRect, Paint, Path, PathBuilder, Pixmap, PixmapPaint, Rect, Stroke,
+    Transform,
+};
+
+// The line numbers are drawn slightly bigger than the code
+const LINE_NUMBER_FONT_SCALE: f32 = 1.12;
+const COMMAND_LINE_LEADING: f32 = 2.;
+
+// The box of highlight token is slightly wider than the chars, and doesn't touch the box in the
+// adjacent row
+const TOKEN_BOX_PADDING: f32 = 2.;
+const TOKEN_BOX_INSET: f32 = 1.;
+const TOKEN_BOX_RADIUS: f32 = 3.;
+const TOKEN_BOX_STROKE_WIDTH: f32 = 1.;
+// The distance between the underline and the bottom of the row
+const UNDERLINE_OFFSET: f32 = 3.;
+const UNDERLINE_THICKNESS: f32 = 1.5;
 
-const FONT_SIZE: f32 = 12.5;
-pub(crate) const CODE_LINE_HEIGHT: f32 = 18.;
+// The redacted chars are blurred by a fraction of the row height, or pixelated into the blocks
+// which are a fraction of the row height, both of them make the chars unreadable
+const REDACTION_BLUR_RATIO: f32 = 0.3;
+const REDACTION_PIXELATE_BLOCKS: f32 = 3.;
+const REDACTION_RADIUS: f32 = 2.;
+
+// The blur of unfocused lines spreads about 3 times of the radius, the blurred area is extended by
+// it, so that the blur isn't cut off at the ends of the lines
+const FOCUS_BLUR_SPREAD: f32 = 3.;
+
+pub(crate) fn code_metrics(code_config: &CodeConfig) -> Metrics {
+    Metrics::new(code_config.font_size, code_config.line_height)
+}
+
+pub(crate) fn line_number_metrics(code_config: &CodeConfig) -> Metrics {
+    Metrics::new(
+        code_config.font_size * LINE_NUMBER_FONT_SCALE,
+        code_config.line_height,
+    )
+}
+
+// The command line has a little more leading than the code
+pub(crate) fn command_line_metrics(code_config: &CodeConfig) -> Metrics {
+    Metrics::new(
+        code_config.font_size,
+        code_config.line_height + COMMAND_LINE_LEADING,
+    )
+}
+
+// The letter spacing of the config is in pixels, but cosmic-text expects it in EM
+pub(crate) fn code_attrs(code_config: &CodeConfig) -> Attrs<'_> {
+    Attrs::new()
+        .family(Family::Name(&code_config.font_family))
+        .letter_spacing(code_config.letter_spacing / code_config.font_size)
+}
 
 pub struct Code {
     children: Vec<Box<dyn Component>>,
     value: String,
-    metrics: Metrics,
+    folds: Vec<Fold>,
     syntax: SyntaxReference,
     syntax_set: SyntaxSet,
+    // The number of chars to be drawn, the size of the component is always measured by the whole
+    // code, so that the layout stays the same while the code is revealing
+    reveal: Option<usize>,
+    // The byte ranges of code which are highlighted by the color and style, such as the highlight
+    // tokens and the changed chars of split diff, only the first one of the overlapped ranges is
+    // drawn
+    token_highlights: Vec<(Range<usize>, String, HighlightTokenStyle)>,
+    // The byte ranges of code which are redacted by the `redactions` of code, the secrets found by
+    // the detectors are redacted as well when drawing
+    redactions: Vec<Range<usize>>,
+    // The lines outside the highlighted lines are dimmed in the focus mode
+    highlight_lines: Vec<HighlightLine>,
+    // The name overrides the default name, so that the style of each code is cached separately
+    // when there are multiple code components (such as the panes of split diff)
+    name: Option<String>,
+    // The position where the code is drawn is reported to the snapshot, so that the snapshot can
+    // draw the code again by itself, such as the frames of animation
+    position: Option<Arc<Mutex<Option<RenderParams>>>>,
+}
+
+pub(crate) fn rounded_rect_path(rect: Rect, radius: f32) -> Option<Path> {
+    let (left, top, right, bottom) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+    let radius = radius.min(rect.width() / 2.).min(rect.height() / 2.);
+    let mut path_builder = PathBuilder::new();
+
+    path_builder.move_to(left + radius, top);
+    path_builder.line_to(right - radius, top);
+    path_builder.quad_to(right, top, right, top + radius);
+    path_builder.line_to(right, bottom - radius);
+    path_builder.quad_to(right, bottom, right - radius, bottom);
+    path_builder.line_to(left + radius, bottom);
+    path_builder.quad_to(left, bottom, left, bottom - radius);
+    path_builder.line_to(left, top + radius);
+    path_builder.quad_to(left, top, left + radius, top);
+    path_builder.close();
+    path_builder.finish()
+}
+
+// Remove the glyphs of redacted chars from the scene, so that the redacted chars can't be found
+// in the vector outputs
+fn hide_redacted_glyphs(text: SceneElement, redaction_rects: &[Rect]) -> SceneElement {
+    match text {
+        SceneElement::Text(glyphs) if !redaction_rects.is_empty() => SceneElement::Text(
+            glyphs
+                .into_iter()
+                .filter(|glyph| {
+                    let center = glyph.x + glyph.width / 2.;
+
+                    !redaction_rects.iter().any(|rect| {
+                        (rect.left()..=rect.right()).contains(&center)
+                            && (rect.top()..=rect.bottom()).contains(&glyph.y)
+                    })
+                })
+                .collect(),
+        ),
+        text => text,
+    }
+}
+
+// Average the colors of each block of pixels, the colors are premultiplied, so the average is
+// still a valid premultiplied color
+fn pixelate(pixmap: &mut Pixmap, block_size: usize) {
+    let width = pixmap.width() as usize;
+    let height = pixmap.height() as usize;
+    let pixels = pixmap.data_mut().as_rgba_mut();
+
+    for block_y in (0..height).step_by(block_size) {
+        for block_x in (0..width).step_by(block_size) {
+            let indexes = (block_y..(block_y + block_size).min(height))
+                .flat_map(|y| {
+                    (block_x..(block_x + block_size).min(width)).map(move |x| y * width + x)
+                })
+                .collect::<Vec<_>>();
+            let sum = indexes.iter().fold([0usize; 4], |sum, index| {
+                let pixel = pixels[*index];
+
+                [
+                    sum[0] + pixel.r as usize,
+                    sum[1] + pixel.g as usize,
+                    sum[2] + pixel.b as usize,
+                    sum[3] + pixel.a as usize,
+                ]
+            });
+            let average = |value: usize| (value / indexes.len()) as u8;
+            let color = RGBA8::new(
+                average(sum[0]),
+                average(sum[1]),
+                average(sum[2]),
+                average(sum[3]),
+            );
+
+            for index in indexes {
+                pixels[index] = color;
+            }
+        }
+    }
+}
+
+// The region of the pixmap which is covered by the rect, the rect is in the logical pixels
+fn pixmap_region(pixmap: &Pixmap, rect: Rect, scale_factor: f32) -> Option<IntRect> {
+    Rect::from_ltrb(
+        rect.left() * scale_factor,
+        rect.top() * scale_factor,
+        rect.right() * scale_factor,
+        rect.bottom() * scale_factor,
+    )
+    .and_then(|region| region.round_out())
+    .and_then(|region| {
+        IntRect::from_xywh(0, 0, pixmap.width(), pixmap.height())?.intersect(&region)
+    })
+}
+
+// The pixels outside the image are treated as transparent by the blur, blur a larger area and
+// only keep the region, so that the edges of the region aren't faded
+fn blurred_region(pixmap: &Pixmap, region: IntRect, sigma: f32) -> Option<Pixmap> {
+    let padding = (sigma * 3.).ceil() as i32;
+    let padded = IntRect::from_ltrb(
+        region.left() - padding,
+        region.top() - padding,
+        region.right() + padding,
+        region.bottom() + padding,
+    )
+    .and_then(|padded| {
+        IntRect::from_xywh(0, 0, pixmap.width(), pixmap.height())?.intersect(&padded)
+    })?;
+    let mut blurred = pixmap.clone_rect(padded)?;
+    let (width, height) = (blurred.width(), blurred.height());
+
+    apply(
+        sigma as f64,
+        sigma as f64,
+        ImageRefMut::new(width, height, blurred.data_mut().as_rgba_mut()),
+    );
+
+    IntRect::from_xywh(
+        region.x() - padded.x(),
+        region.y() - padded.y(),
+        region.width(),
+        region.height(),
+    )
+    .and_then(|inner| blurred.clone_rect(inner))
+}
+
+// Replace the region of the pixmap with the processed pixels
+fn replace_region(pixmap: &mut Pixmap, region: IntRect, replacement: &Pixmap) {
+    pixmap.draw_pixmap(
+        region.x(),
+        region.y(),
+        replacement.as_ref(),
+        &PixmapPaint {
+            blend_mode: BlendMode::Source,
+            ..PixmapPaint::default()
+        },
+        Transform::identity(),
+        None,
+    );
+}
+
+// Blur the unfocused line on the pixmap, the blurred area is extended horizontally but not beyond
+// the left edge of the code, so that the line numbers stay sharp
+fn blur_unfocused_rect(
+    pixmap: &mut Pixmap,
+    context: &ComponentContext,
+    render_params: &RenderParams,
+    rect: Rect,
+) {
+    let radius = context.take_snapshot_params.focus_config.blur;
+    let spread = radius * FOCUS_BLUR_SPREAD;
+    let Some(region) = Rect::from_ltrb(
+        (rect.left() - spread).max(render_params.x),
+        rect.top(),
+        rect.right() + spread,
+        rect.bottom(),
+    )
+    .and_then(|rect| pixmap_region(pixmap, rect, context.scale_factor)) else {
+        return;
+    };
+
+    if let Some(blurred) = blurred_region(pixmap, region, radius * context.scale_factor) {
+        replace_region(pixmap, region, &blurred);
+    }
+}
+
+// Blur or pixelate the redacted chars on the pixmap, the vector outputs can't be blurred, so the
+// chars are covered by a block in the color of line numbers instead
+fn redact_rect(pixmap: &mut Pixmap, context: &ComponentContext, rect: Rect) {
+    let scale_factor = context.scale_factor;
+
+    if let Some(region) = pixmap_region(pixmap, rect, scale_factor) {
+        let row_height = rect.height() * scale_factor;
+        let redacted = match context.take_snapshot_params.redact_config.style {
+            RedactionStyle::Blur => {
+                blurred_region(pixmap, region, row_height * REDACTION_BLUR_RATIO)
+            }
+            RedactionStyle::Pixelate => pixmap.clone_rect(region).map(|mut redacted| {
+                pixelate(
+                    &mut redacted,
+                    ((row_height / REDACTION_PIXELATE_BLOCKS) as usize).max(1),
+                );
+                redacted
+            }),
+        };
+
+        if let Some(redacted) = redacted {
+            replace_region(pixmap, region, &redacted);
+        }
+    }
+
+    let color: RgbaColor = context
+        .take_snapshot_params
+        .line_number_color
+        .as_str()
+        .into();
+
+    context.scene.lock().unwrap().push(SceneElement::Rect {
+        x: rect.x(),
+        y: rect.y(),
+        width: rect.width(),
+        height: rect.height(),
+        radius: REDACTION_RADIUS,
+        color: color.into(),
+    });
+}
+
+// The rects of the secrets found by the detectors in the text which isn't code, such as the
+// command output and the Markdown prose, the text has no `redactions`, so only the detectors are
+// applied. The text is wrapped at the width if it's provided
+pub(crate) fn secret_rects(
+    context: &ComponentContext,
+    x: f32,
+    y: f32,
+    metrics: Metrics,
+    spans: HighlightResult,
+    width: Option<f32>,
+) -> Vec<Rect> {
+    let detectors = &context.take_snapshot_params.redact_config.detectors;
+
+    if detectors.is_empty() {
+        return vec![];
+    }
+
+    let text = spans.iter().map(|(text, _)| *text).collect::<String>();
+    let ranges = secret_ranges(&text, detectors);
+
+    if ranges.is_empty() {
+        return vec![];
+    }
+
+    context
+        .font_renderer
+        .lock()
+        .unwrap()
+        .span_rects(x, y, metrics, tag_spans(spans, &ranges), width)
+        .into_iter()
+        .map(|(_, rect)| rect)
+        .collect()
+}
+
+// Push the drawn text to the scene without the redacted glyphs, and redact the chars on the pixmap
+pub(crate) fn push_redacted_text(
+    pixmap: &mut Pixmap,
+    context: &ComponentContext,
+    text: SceneElement,
+    redaction_rects: Vec<Rect>,
+) {
+    context
+        .scene
+        .lock()
+        .unwrap()
+        .push(hide_redacted_glyphs(text, &redaction_rects));
+
+    for rect in redaction_rects {
+        redact_rect(pixmap, context, rect);
+    }
+}
+
+// Paint the underlines and strikethroughs which are measured by `FontRenderer::decoration_rects`
+pub(crate) fn fill_decoration_rects(
+    pixmap: &mut Pixmap,
+    context: &ComponentContext,
+    rects: Vec<(Rect, cosmic_text::Color)>,
+) {
+    let transform = Transform::from_scale(context.scale_factor, context.scale_factor);
+
+    for (rect, color) in rects {
+        let color = Color::from_rgba8(color.r(), color.g(), color.b(), color.a());
+        let mut paint = Paint::default();
+
+        paint.set_color(color);
+        paint.anti_alias = true;
+        pixmap.fill_rect(rect, &paint, transform, None);
+        context.scene.lock().unwrap().push(SceneElement::Rect {
+            x: rect.x(),
+            y: rect.y(),
+            width: rect.width(),
+            height: rect.height(),
+            radius: 0.,
+            color,
+        });
+    }
+}
+
+// Keep the first `count` chars of the highlighted code
+fn truncate_highlight(highlight_result: HighlightResult<'_>, count: usize) -> HighlightResult<'_> {
+    let mut rest = count;
+
+    highlight_result
+        .into_iter()
+        .map_while(|(text, attrs)| {
+            if rest == 0 {
+                return None;
+            }
+
+            let end = text
+                .char_indices()
+                .nth(rest)
+                .map(|(index, _)| index)
+                .unwrap_or(text.len());
+
+            rest -= text[..end].chars().count();
+
+            Some((&text[..end], attrs))
+        })
+        .collect()
+}
+
+// Insert the spans into the highlighted code at the byte offsets (in the whole code), the
+// highlighted span is split if the offset is in the middle of it, the insertions should be sorted
+// by offsets
+fn insert_spans<'a>(
+    highlight_result: HighlightResult<'a>,
+    insertions: Vec<(usize, HighlightResult<'a>)>,
+) -> HighlightResult<'a> {
+    let mut insertions = insertions.into_iter().peekable();
+    let mut offset = 0;
+    let mut result = vec![];
+
+    for (mut text, attrs) in highlight_result {
+        while let Some((insert_offset, _)) = insertions.peek() {
+            if *insert_offset >= offset + text.len() {
+                break;
+            }
+
+            let (insert_offset, spans) = insertions.next().unwrap();
+            let (head, tail) = text.split_at(insert_offset - offset);
+
+            if !head.is_empty() {
+                result.push((head, attrs.clone()));
+            }
+
+            result.extend(spans);
+            text = tail;
+            offset = insert_offset;
+        }
+
+        offset += text.len();
+        result.push((text, attrs));
+    }
+
+    result
+}
+
+// The fold markers and the byte offsets of the lines they are drawn before
+pub(crate) fn fold_markers(code: &str, folds: &[Fold]) -> Vec<(usize, String)> {
+    let lines = code.split('\n').collect::<Vec<_>>();
+    let mut line_start = 0;
+    let mut markers = vec![];
+
+    for (line, line_folds) in lines.iter().zip(folds_by_line(lines.len(), folds)) {
+        markers.extend(
+            line_folds
+                .into_iter()
+                .map(|fold| (line_start, fold_marker(fold))),
+        );
+        line_start += line.len() + 1;
+    }
+
+    markers
+}
+
+// Insert the wrap indicators and the fold markers into the highlighted code, so that the code is
+// laid out in the same visual rows as the line numbers
+pub(crate) fn insert_layout_spans<'a>(
+    highlight_result: HighlightResult<'a>,
+    code: &str,
+    fold_markers: &'a [(usize, String)],
+    context: &ComponentContext,
+    indicator_attrs: Attrs<'a>,
+) -> HighlightResult<'a> {
+    let fold_attrs = indicator_attrs.clone().style(cosmic_text::Style::Italic);
+    let mut insertions = wrap_code(
+        code,
+        &context.take_snapshot_params.code_config,
+        &mut context.font_renderer.lock().unwrap(),
+    )
+    .concat()
+    .into_iter()
+    .map(|offset| {
+        (
+            offset,
+            vec![
+                ("\n", indicator_attrs.clone()),
+                (WRAP_INDICATOR, indicator_attrs.clone()),
+            ],
+        )
+    })
+    .chain(fold_markers.iter().map(|(offset, marker)| {
+        (
+            *offset,
+            vec![
+                (marker.as_str(), fold_attrs.clone()),
+                ("\n", fold_attrs.clone()),
+            ],
+        )
+    }))
+    .collect::<Vec<_>>();
+
+    insertions.sort_by_key(|(offset, _)| *offset);
+    insert_spans(highlight_result, insertions)
+}
+
+// The byte ranges of the lines which are marked in `lines`, the line breaks are excluded
+fn line_ranges(code: &str, lines: &[bool]) -> Vec<Range<usize>> {
+    let mut offset = 0;
+
+    code.split('\n')
+        .zip(lines)
+        .filter_map(|(line, is_marked)| {
+            let range = offset..offset + line.len();
+
+            offset = range.end + 1;
+            is_marked.then_some(range)
+        })
+        .collect()
+}
+
+// Tag the chars in the ranges with the index of the range (starts from 1) as the metadata of
+// attrs, the highlighted span is split at the bounds of the ranges, and the decorations of the
+// span are kept
+pub(crate) fn tag_spans<'a>(
+    highlight_result: HighlightResult<'a>,
+    ranges: &[Range<usize>],
+) -> HighlightResult<'a> {
+    let mut offset = 0;
+    let mut result = vec![];
+
+    for (text, attrs) in highlight_result {
+        let span = offset..offset + text.len();
+        let mut bounds = ranges
+            .iter()
+            .flat_map(|range| [range.start, range.end])
+            .filter(|bound| span.contains(bound) && *bound > span.start)
+            .chain([span.start, span.end])
+            .collect::<Vec<usize>>();
+
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        for piece in bounds.windows(2) {
+            let tag = ranges
+                .iter()
+                .position(|range| range.contains(&piece[0]))
+                .map(|index| index + 1)
+                .unwrap_or(attrs.metadata & !DECORATION_METADATA);
+
+            result.push((
+                &text[piece[0] - offset..piece[1] - offset],
+                attrs
+                    .clone()
+                    .metadata(tag | (attrs.metadata & DECORATION_METADATA)),
+            ));
+        }
+
+        offset = span.end;
+    }
+
+    result
 }
 
 impl Component for Code {
@@ -31,11 +580,14 @@ impl Component for Code {
     }
 
     fn style(&self, context: &ComponentContext) -> RawComponentStyle {
-        let (w, h) = context
-            .font_renderer
-            .lock()
-            .unwrap()
-            .measure_text(self.metrics, &self.value);
+        let code_config = &context.take_snapshot_params.code_config;
+        let mut font_renderer = context.font_renderer.lock().unwrap();
+        let text = self.measured_text(code_config, &mut font_renderer);
+        let (w, h) = font_renderer.measure_text_with_attrs(
+            code_metrics(code_config),
+            &text,
+            &code_attrs(code_config),
+        );
 
         Style::default().size(Size::Num(w), Size::Num(h))
     }
@@ -48,49 +600,384 @@ impl Component for Code {
         _style: &ComponentStyle,
         _parent_style: &ComponentStyle,
     ) -> render_error::Result<()> {
-        let highlight = Highlight::new(
-            self.value.clone(),
-            context.take_snapshot_params.code_config.font_family.clone(),
+        if let Some(ref position) = self.position {
+            *position.lock().unwrap() = Some(render_params.clone());
+        }
+
+        let code_config = &context.take_snapshot_params.code_config;
+        let focus_config = &context.take_snapshot_params.focus_config;
+        let unfocused_lines = unfocused_lines(
+            &self.highlight_lines,
+            self.value.lines().count(),
+            focus_config,
         );
+        let highlight = Highlight::new(self.value.clone(), code_attrs(code_config))
+            .unfocus(unfocused_lines.clone(), focus_config.clone());
         let (mut highlight_lines, syntax_set) = (
             HighlightLines::new(&self.syntax, &context.theme_provider.theme),
             &self.syntax_set,
         );
-        let highlight_result = highlight.parse(&mut highlight_lines, syntax_set)?;
+        let highlight_result =
+            self.highlight(context, &highlight, &mut highlight_lines, syntax_set)?;
+        let highlight_result = match self.reveal {
+            Some(count) => truncate_highlight(highlight_result, count),
+            None => highlight_result,
+        };
+        let fold_markers = fold_markers(&self.value, &self.folds);
+        let redaction_rects = self.redaction_rects(
+            context,
+            render_params,
+            highlight_result.clone(),
+            &fold_markers,
+        );
+        let unfocused_rects = match focus_config.blur > 0. {
+            true => self.range_rects(
+                context,
+                render_params,
+                highlight_result.clone(),
+                &fold_markers,
+                &line_ranges(&self.value, &unfocused_lines),
+            ),
+            false => vec![],
+        };
+        let highlight_result = match self.token_highlights.is_empty() {
+            true => highlight_result,
+            false => tag_spans(
+                highlight_result,
+                &self
+                    .token_highlights
+                    .iter()
+                    .map(|(range, _, _)| range.clone())
+                    .collect::<Vec<_>>(),
+            ),
+        };
+        let indicator_attrs = code_attrs(code_config).color(parse_hex_to_cosmic_color(
+            &context.take_snapshot_params.line_number_color,
+        ));
+        let highlight_result = insert_layout_spans(
+            highlight_result,
+            &self.value,
+            &fold_markers,
+            context,
+            indicator_attrs,
+        );
 
-        context.font_renderer.lock().unwrap().draw_text(
+        self.draw_token_highlights(pixmap, context, render_params, &highlight_result);
+
+        let text = context.font_renderer.lock().unwrap().draw_text(
             render_params.x,
             render_params.y,
-            self.metrics,
+            code_metrics(code_config),
             highlight_result.clone(),
             pixmap,
         );
+        context
+            .scene
+            .lock()
+            .unwrap()
+            .push(hide_redacted_glyphs(text, &redaction_rects));
+        self.draw_decorations(pixmap, context, render_params, &highlight_result);
+
+        for rect in unfocused_rects {
+            blur_unfocused_rect(pixmap, context, render_params, rect);
+        }
+
+        for rect in redaction_rects {
+            redact_rect(pixmap, context, rect);
+        }
 
         Ok(())
     }
 
-    fn name(&self) -> &'static str {
-        "Code"
+    fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("Code")
     }
 }
 
 impl Code {
-    pub fn new(code_content: config::Code) -> anyhow::Result<Self> {
+    pub fn new(
+        code_content: config::Code,
+        syntax_provider: &SyntaxProvider,
+    ) -> anyhow::Result<Self> {
         let value = prepare_code(&code_content.content);
-        let metrics = Metrics::new(FONT_SIZE, CODE_LINE_HEIGHT);
-        let syntax_provider = SyntaxProvider::new();
         let syntax = syntax_provider.guess_syntax(
             code_content.language.clone(),
             code_content.file_path.clone(),
             &value,
         )?;
 
+        let token_highlights = token_ranges(&code_content.content, &code_content.highlight_tokens)?
+            .into_iter()
+            .map(|(range, highlight_token)| (range, highlight_token.color, highlight_token.style))
+            .collect();
+
+        let redactions = redaction_target_ranges(&code_content.content, &code_content.redactions)?;
+
         Ok(Code {
-            value: prepare_code(&code_content.content),
+            value,
+            folds: code_content.folds,
             children: vec![],
-            metrics,
             syntax,
-            syntax_set: syntax_provider.syntax_set,
+            syntax_set: syntax_provider.syntax_set.clone(),
+            reveal: None,
+            token_highlights,
+            redactions,
+            highlight_lines: code_content.highlight_lines,
+            name: None,
+            position: None,
         })
     }
+
+    pub fn reveal(mut self, count: Option<usize>) -> Self {
+        self.reveal = count;
+        self
+    }
+
+    // Paint the background of the chars in the ranges
+    pub fn emphasize(mut self, ranges: Vec<Range<usize>>, color: &str) -> Self {
+        self.token_highlights.extend(
+            ranges
+                .into_iter()
+                .map(|range| (range, color.to_string(), HighlightTokenStyle::Background)),
+        );
+        self
+    }
+
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn report_position(mut self, position: Arc<Mutex<Option<RenderParams>>>) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    // Highlight the code by the backend of the config, the code is highlighted by syntect if the
+    // tree-sitter backend is not available or the language has no tree-sitter grammar
+    #[cfg_attr(not(feature = "tree-sitter"), allow(unused_variables))]
+    fn highlight<'a>(
+        &self,
+        context: &ComponentContext,
+        highlight: &'a Highlight,
+        highlight_lines: &mut HighlightLines,
+        syntax_set: &SyntaxSet,
+    ) -> render_error::Result<HighlightResult<'a>> {
+        #[cfg(feature = "tree-sitter")]
+        if context.take_snapshot_params.code_config.highlight_backend
+            == HighlightBackend::TreeSitter
+        {
+            if let Some(highlight_result) =
+                highlight.parse_tree_sitter(&self.syntax, &context.theme_provider.theme)?
+            {
+                return Ok(highlight_result);
+            }
+        }
+
+        highlight.parse(highlight_lines, syntax_set)
+    }
+
+    // Draw the highlights of tokens, the rects are measured by the shaped glyphs, so they are
+    // aligned with the chars even if the code contains wide chars or ligatures, the token which is
+    // wrapped has a rect in each row
+    fn draw_token_highlights(
+        &self,
+        pixmap: &mut tiny_skia::Pixmap,
+        context: &ComponentContext,
+        render_params: &RenderParams,
+        highlight_result: &HighlightResult,
+    ) {
+        if self.token_highlights.is_empty() {
+            return;
+        }
+
+        let rects = context.font_renderer.lock().unwrap().span_rects(
+            render_params.x,
+            render_params.y,
+            code_metrics(&context.take_snapshot_params.code_config),
+            highlight_result.clone(),
+            None,
+        );
+        let transform = Transform::from_scale(context.scale_factor, context.scale_factor);
+
+        for (tag, rect) in rects {
+            let Some((_, color, style)) = self.token_highlights.get(tag - 1) else {
+                continue;
+            };
+            let color: RgbaColor = color.as_str().into();
+            let color: Color = color.into();
+            let mut paint = Paint::default();
+
+            paint.set_color(color);
+            paint.anti_alias = true;
+
+            let element = match style {
+                HighlightTokenStyle::Background => {
+                    pixmap.fill_rect(rect, &paint, transform, None);
+                    SceneElement::Rect {
+                        x: rect.x(),
+                        y: rect.y(),
+                        width: rect.width(),
+                        height: rect.height(),
+                        radius: 0.,
+                        color,
+                    }
+                }
+                HighlightTokenStyle::Underline => {
+                    let Some(underline) = Rect::from_xywh(
+                        rect.x(),
+                        rect.bottom() - UNDERLINE_OFFSET,
+                        rect.width(),
+                        UNDERLINE_THICKNESS,
+                    ) else {
+                        continue;
+                    };
+
+                    pixmap.fill_rect(underline, &paint, transform, None);
+                    SceneElement::Rect {
+                        x: underline.x(),
+                        y: underline.y(),
+                        width: underline.width(),
+                        height: underline.height(),
+                        radius: 0.,
+                        color,
+                    }
+                }
+                HighlightTokenStyle::Box => {
+                    let Some(path) = Rect::from_ltrb(
+                        rect.left() - TOKEN_BOX_PADDING,
+                        rect.top() + TOKEN_BOX_INSET,
+                        rect.right() + TOKEN_BOX_PADDING,
+                        rect.bottom() - TOKEN_BOX_INSET,
+                    )
+                    .and_then(|rect| rounded_rect_path(rect, TOKEN_BOX_RADIUS)) else {
+                        continue;
+                    };
+                    let bounds = path.bounds();
+                    let stroke = Stroke {
+                        width: TOKEN_BOX_STROKE_WIDTH,
+                        ..Stroke::default()
+                    };
+
+                    pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+                    SceneElement::StrokeRect {
+                        x: bounds.x(),
+                        y: bounds.y(),
+                        width: bounds.width(),
+                        height: bounds.height(),
+                        radius: TOKEN_BOX_RADIUS,
+                        stroke_width: TOKEN_BOX_STROKE_WIDTH,
+                        color,
+                    }
+                }
+            };
+
+            context.scene.lock().unwrap().push(element);
+        }
+    }
+
+    // Draw the underlines and strikethroughs of the decorated spans, cosmic-text only draws the
+    // glyphs
+    fn draw_decorations(
+        &self,
+        pixmap: &mut tiny_skia::Pixmap,
+        context: &ComponentContext,
+        render_params: &RenderParams,
+        highlight_result: &HighlightResult,
+    ) {
+        if highlight_result
+            .iter()
+            .all(|(_, attrs)| attrs.metadata & DECORATION_METADATA == 0)
+        {
+            return;
+        }
+
+        let rects = context.font_renderer.lock().unwrap().decoration_rects(
+            render_params.x,
+            render_params.y,
+            code_metrics(&context.take_snapshot_params.code_config),
+            highlight_result.clone(),
+            None,
+        );
+
+        fill_decoration_rects(pixmap, context, rects);
+    }
+
+    // The rects of redacted chars in each visual row, the redactions of the code and the secrets
+    // found by the detectors are merged
+    fn redaction_rects(
+        &self,
+        context: &ComponentContext,
+        render_params: &RenderParams,
+        highlight_result: HighlightResult,
+        fold_markers: &[(usize, String)],
+    ) -> Vec<Rect> {
+        let detectors = &context.take_snapshot_params.redact_config.detectors;
+
+        if self.redactions.is_empty() && detectors.is_empty() {
+            return vec![];
+        }
+
+        let ranges = [
+            self.redactions.clone(),
+            secret_ranges(&self.value, detectors),
+        ]
+        .concat();
+
+        self.range_rects(
+            context,
+            render_params,
+            highlight_result,
+            fold_markers,
+            &ranges,
+        )
+    }
+
+    // The rects of the chars in the byte ranges, the chars which are wrapped have a rect in each
+    // visual row
+    fn range_rects(
+        &self,
+        context: &ComponentContext,
+        render_params: &RenderParams,
+        highlight_result: HighlightResult,
+        fold_markers: &[(usize, String)],
+        ranges: &[Range<usize>],
+    ) -> Vec<Rect> {
+        if ranges.is_empty() {
+            return vec![];
+        }
+
+        let code_config = &context.take_snapshot_params.code_config;
+        let spans = insert_layout_spans(
+            tag_spans(highlight_result, ranges),
+            &self.value,
+            fold_markers,
+            context,
+            code_attrs(code_config),
+        );
+
+        context
+            .font_renderer
+            .lock()
+            .unwrap()
+            .span_rects(
+                render_params.x,
+                render_params.y,
+                code_metrics(code_config),
+                spans,
+                None,
+            )
+            .into_iter()
+            .map(|(_, rect)| rect)
+            .collect()
+    }
+
+    // The fold markers take whole rows, the order of rows doesn't matter when measuring, so they
+    // are simply appended to the wrapped code
+    fn measured_text(&self, code_config: &CodeConfig, font_renderer: &mut FontRenderer) -> String {
+        fold_markers(&self.value, &self.folds).into_iter().fold(
+            wrap_text(&self.value, code_config, font_renderer),
+            |text, (_, marker)| format!("{}\n{}", text, marker),
+        )
+    }
 }
@@ -4,6 +4,7 @@ use crate::{
     components::interface::{
         component::{Component, ComponentContext, RenderParams},
         render_error,
+        scene::SceneElement,
         style::{ComponentStyle, RawComponentStyle, Size, Style},
     },
     edges::margin::Margin,
@@ -57,13 +58,13 @@ impl Component for MacTitleBar {
                 Color::from_rgba8(43, 200, 65, 255),
             ],
             4.,
-            Transform::from_scale(context.scale_factor, context.scale_factor),
+            context,
         );
 
         Ok(())
     }
 
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "MacTitleBar"
     }
 }
@@ -83,7 +84,7 @@ impl MacTitleBar {
         pixmap: &mut tiny_skia::Pixmap,
         colors: Vec<Color>,
         gap: f32,
-        transform: Transform,
+        context: &ComponentContext,
     ) {
         for (index, color) in colors.into_iter().enumerate() {
             let diameter = RADIUS * 2.;
@@ -94,7 +95,7 @@ impl MacTitleBar {
                 pixmap,
                 color,
                 index as f32 * (diameter + gap),
-                transform,
+                context,
             );
         }
     }
@@ -106,7 +107,7 @@ impl MacTitleBar {
         pixmap: &mut tiny_skia::Pixmap,
         color: Color,
         x_offset: f32,
-        transform: Transform,
+        context: &ComponentContext,
     ) {
         let mut path_builder = PathBuilder::new();
 
@@ -117,6 +118,18 @@ impl MacTitleBar {
         let mut paint = Paint::default();
 
         paint.set_color(color);
-        pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+        pixmap.fill_path(
+            &path,
+            &paint,
+            FillRule::Winding,
+            Transform::from_scale(context.scale_factor, context.scale_factor),
+            None,
+        );
+        context.scene.lock().unwrap().push(SceneElement::Circle {
+            cx: x + x_offset,
+            cy: y,
+            radius: RADIUS,
+            color,
+        });
     }
 }
@@ -16,7 +16,7 @@ pub struct Title {
 }
 
 impl Component for Title {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "Title"
     }
 
@@ -53,7 +53,7 @@ impl Component for Title {
             .color(parse_hex_to_cosmic_color(&config.color))
             .family(Family::Name(&config.font_family));
 
-        context.font_renderer.lock().unwrap().draw_line(
+        let text = context.font_renderer.lock().unwrap().draw_line(
             0.,
             render_params.y,
             Metrics::new(10., 10.),
@@ -62,6 +62,7 @@ impl Component for Title {
             Some(Align::Center),
             pixmap,
         );
+        context.scene.lock().unwrap().push(text);
 
         Ok(())
     }
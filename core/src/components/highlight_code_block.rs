@@ -1,27 +1,51 @@
-use std::{collections::HashMap, sync::Mutex};
-
-use crate::{config::HighlightLine, edges::padding::Padding, utils::color::RgbaColor};
+use crate::{
+    config::{Code, HighlightLine},
+    edges::padding::Padding,
+    utils::{code::prepare_code, color::RgbaColor, fold::folds_by_line, wrap::wrap_code},
+};
 
 use super::{
-    editor::code::CODE_LINE_HEIGHT,
     interface::{
         component::{Component, ComponentContext, RenderParams},
+        scene::SceneElement,
         style::ComponentStyle,
     },
+    split_pane::SPLIT_PANE_GAP,
 };
 use tiny_skia::{Paint, Rect, Transform};
 
+// The horizontal extent of the highlighted lines
+#[derive(Default, Clone, Copy)]
+pub enum HighlightExtent {
+    // The highlight spans the whole editor
+    #[default]
+    Editor,
+    // The highlight spans from the left edge of the editor to the middle of the gap after the
+    // pane, the pane is named by the given name
+    LeadingPane(&'static str),
+    // The highlight spans from the middle of the gap before the pane to the right edge of the
+    // editor, the leading pane (named by the given name) is placed before the pane
+    TrailingPane(&'static str),
+}
+
 #[derive(Default)]
 pub struct HighlightCodeBlock {
     children: Vec<Box<dyn Component>>,
     highlight_lines: Vec<HighlightLine>,
     editor_padding: Padding,
     code_line_count: usize,
+    code: String,
+    // The number of fold markers before each line
+    fold_rows: Vec<u32>,
+    extent: HighlightExtent,
+    // The name overrides the default name, so that the style of each pane of split diff is
+    // cached separately
+    name: Option<&'static str>,
 }
 
 impl Component for HighlightCodeBlock {
-    fn name(&self) -> &'static str {
-        "HighlightCodeBlock"
+    fn name(&self) -> &str {
+        self.name.unwrap_or("HighlightCodeBlock")
     }
 
     fn children(&self) -> &Vec<Box<dyn Component>> {
@@ -41,18 +65,14 @@ impl Component for HighlightCodeBlock {
         parent_style: &ComponentStyle,
     ) -> super::interface::render_error::Result<()> {
         for highlight_line in &self.highlight_lines {
-            let (start_line_number, end_line_number, color) = match highlight_line {
-                HighlightLine::Single(line_number, color) => (line_number, line_number, color),
-                HighlightLine::Range(start_line_number, end_line_number, color) => {
-                    (start_line_number, end_line_number, color)
-                }
-            };
+            let (start_line_number, end_line_number) = highlight_line.range();
+            let color = highlight_line.color();
             let (rect, paint) = self.draw_highlight_line(
-                &context.style_map,
+                context,
                 render_params,
                 parent_style,
-                *start_line_number,
-                *end_line_number,
+                start_line_number,
+                end_line_number,
                 color,
             );
 
@@ -62,6 +82,17 @@ impl Component for HighlightCodeBlock {
                 Transform::from_scale(context.scale_factor, context.scale_factor),
                 None,
             );
+
+            let color: RgbaColor = color.into();
+
+            context.scene.lock().unwrap().push(SceneElement::Rect {
+                x: rect.x(),
+                y: rect.y(),
+                width: rect.width(),
+                height: rect.height(),
+                radius: 0.,
+                color: color.into(),
+            });
         }
 
         Ok(())
@@ -69,22 +100,73 @@ impl Component for HighlightCodeBlock {
 }
 
 impl HighlightCodeBlock {
-    pub fn from(
-        highlight_lines: Vec<HighlightLine>,
-        code_line_count: usize,
-        editor_padding: Padding,
-    ) -> HighlightCodeBlock {
+    pub fn from(code_content: &Code, editor_padding: Padding) -> HighlightCodeBlock {
+        let code_line_count = code_content.content.lines().count();
+
         HighlightCodeBlock {
             children: vec![],
             code_line_count,
-            highlight_lines,
+            code: prepare_code(&code_content.content),
+            fold_rows: folds_by_line(code_line_count, &code_content.folds)
+                .iter()
+                .map(|line_folds| line_folds.len() as u32)
+                .collect(),
+            highlight_lines: code_content.highlight_lines.clone(),
             editor_padding,
+            extent: HighlightExtent::Editor,
+            name: None,
         }
     }
 
+    pub fn extent(mut self, extent: HighlightExtent) -> Self {
+        self.extent = extent;
+        self
+    }
+
+    pub fn named(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    // The left edge and the width of the highlight
+    fn horizontal_bounds(&self, context: &ComponentContext, x: f32) -> (f32, f32) {
+        let style_map = context.style_map.lock().unwrap();
+        let editor_width = style_map.get("RectInnerLayer").unwrap().width;
+        let pane_width = |name| style_map.get(name).map(|style| style.width).unwrap_or(0.);
+        let editor_left = x - self.editor_padding.left;
+
+        match self.extent {
+            HighlightExtent::Editor => (editor_left, editor_width),
+            HighlightExtent::LeadingPane(name) => (
+                editor_left,
+                self.editor_padding.left + pane_width(name) - SPLIT_PANE_GAP / 2.,
+            ),
+            HighlightExtent::TrailingPane(leading_name) => {
+                let left = x - SPLIT_PANE_GAP / 2.;
+                let editor_left = editor_left - pane_width(leading_name);
+
+                (left, editor_left + editor_width - left)
+            }
+        }
+    }
+
+    // The number of visual rows of each line, a wrapped line takes multiple rows, and the fold
+    // markers before the line are counted in the line
+    fn visual_rows(&self, context: &ComponentContext) -> Vec<u32> {
+        wrap_code(
+            &self.code,
+            &context.take_snapshot_params.code_config,
+            &mut context.font_renderer.lock().unwrap(),
+        )
+        .iter()
+        .zip(&self.fold_rows)
+        .map(|(breaks, fold_rows)| fold_rows + breaks.len() as u32 + 1)
+        .collect()
+    }
+
     fn draw_highlight_line(
         &self,
-        style_map: &Mutex<HashMap<&'static str, ComponentStyle>>,
+        context: &ComponentContext,
         render_params: &RenderParams,
         parent_style: &ComponentStyle,
         start_line_number: u32,
@@ -94,7 +176,7 @@ impl HighlightCodeBlock {
         // If the start_line_number is greater than end_line_number, swap them
         if start_line_number > end_line_number {
             return self.draw_highlight_line(
-                style_map,
+                context,
                 render_params,
                 parent_style,
                 end_line_number,
@@ -103,18 +185,35 @@ impl HighlightCodeBlock {
             );
         }
 
-        let style_map = style_map.lock().unwrap();
-        let editor_style = style_map.get("RectInnerLayer").unwrap();
+        let visual_rows = self.visual_rows(context);
+        let line_height = context.take_snapshot_params.code_config.line_height;
+        let (left, width) = self.horizontal_bounds(context, render_params.x);
         let end_line_number = end_line_number.min(self.code_line_count as u32);
         let mut paint = Paint::default();
-        // If the start line number is start at n, the y offset should be (n - 1) * line_height
-        let start_y_offset = (start_line_number - 1) as f32 * CODE_LINE_HEIGHT;
+        // Count the visual rows before the start line, and the visual rows of the highlighted
+        // lines, so that the highlight covers all the rows of wrapped lines, the fold markers
+        // before the start line are not highlighted
+        let start_fold_rows = self
+            .fold_rows
+            .get(start_line_number as usize - 1)
+            .copied()
+            .unwrap_or(0);
+        let rows_before = visual_rows
+            .iter()
+            .take(start_line_number as usize - 1)
+            .sum::<u32>()
+            + start_fold_rows;
+        let highlighted_rows = visual_rows
+            .iter()
+            .skip(start_line_number as usize - 1)
+            .take(end_line_number.saturating_sub(start_line_number) as usize + 1)
+            .sum::<u32>()
+            .saturating_sub(start_fold_rows);
         let rect = Rect::from_xywh(
-            render_params.x - self.editor_padding.left,
-            render_params.y + start_y_offset,
-            editor_style.width,
-            // If end_line_number is equal to start_line_number, the height should be line_height
-            (end_line_number - start_line_number + 1) as f32 * CODE_LINE_HEIGHT,
+            left,
+            render_params.y + rows_before as f32 * line_height,
+            width,
+            highlighted_rows as f32 * line_height,
         )
         .unwrap();
         let color: RgbaColor = hex.into();
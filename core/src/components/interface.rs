@@ -1,3 +1,4 @@
 pub mod component;
 pub mod render_error;
+pub mod scene;
 pub mod style;
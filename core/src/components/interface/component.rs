@@ -1,5 +1,6 @@
 use super::{
     render_error,
+    scene::Scene,
     style::{ComponentAlign, ComponentStyle, RawComponentStyle, Size, Style},
 };
 use crate::{
@@ -18,7 +19,8 @@ pub struct ComponentContext {
     pub take_snapshot_params: Arc<SnapshotConfig>,
     pub theme_provider: ThemeProvider,
     pub font_renderer: Mutex<FontRenderer>,
-    pub style_map: Mutex<HashMap<&'static str, Style<f32>>>,
+    pub style_map: Mutex<HashMap<String, Style<f32>>>,
+    pub scene: Mutex<Scene>,
 }
 
 #[derive(Default, Clone)]
@@ -87,7 +89,7 @@ pub trait Component {
         Ok(())
     }
 
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         // Stub component means this component no need to cache its style
         // For instance, "Row" and "Col" component, they are just layout components
         // and their style is determined by their children, so they don't need to cache
@@ -157,7 +159,7 @@ pub trait Component {
             .style_map
             .lock()
             .unwrap()
-            .insert(self.name(), style.clone());
+            .insert(self.name().to_string(), style.clone());
 
         style
     }
@@ -9,4 +9,17 @@ pub enum RenderError {
 
     #[error("No such file {0}")]
     NoSuchFile(String),
+
+    #[error("No such language {0}{}", did_you_mean(.1))]
+    NoSuchLanguage(String, Vec<String>),
+
+    #[error("Invalid highlight queries of {0}: {1}")]
+    InvalidHighlightQuery(String, String),
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions.is_empty() {
+        true => String::new(),
+        false => format!(", did you mean {}?", suggestions.join(", ")),
+    }
 }
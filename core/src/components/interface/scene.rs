@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use cosmic_text::{fontdb, Font};
+use tiny_skia::Color;
+
+use crate::config::Point;
+
+// The scene is a flat list of drawing primitives which recorded while components drawing on the
+// pixmap, it shares the same layout pass with the pixmap, so that vector outputs (SVG, PDF, etc.)
+// can replay the scene and line up with the PNG output pixel for pixel.
+//
+// All coordinates in the scene are logical, which means the scale factor is not applied.
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub width: f32,
+    pub height: f32,
+    pub scale_factor: f32,
+    pub elements: Vec<SceneElement>,
+    // The paused scene records nothing, such as the frames of animation which are only drawn on
+    // the pixmap
+    pub is_paused: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum ScenePaint {
+    Solid(Color),
+    LinearGradient {
+        start: Point<f32>,
+        end: Point<f32>,
+        stops: Vec<(f32, Color)>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct SceneFont {
+    pub id: fontdb::ID,
+    pub family: String,
+    pub post_script_name: String,
+    pub weight: u16,
+    pub italic: bool,
+    // The face index in the font data, the font data may be a font collection
+    pub index: u32,
+    // Some renderers need to embed the font data, such as PDF
+    pub font: Option<Arc<Font>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SceneGlyph {
+    pub x: f32,
+    // The y coordinate of the glyph baseline
+    pub y: f32,
+    pub width: f32,
+    pub font_size: f32,
+    pub glyph_id: u16,
+    // The original text of the glyph cluster, a cluster may contain multiple chars
+    pub text: String,
+    pub color: Color,
+    pub font: Arc<SceneFont>,
+    // The glyph is slanted from the upright face, since the font has no italic face of the weight
+    pub oblique: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum SceneElement {
+    Background {
+        width: f32,
+        height: f32,
+        paint: ScenePaint,
+    },
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        radius: f32,
+        color: Color,
+    },
+    // The outline of a rect, the stroke is centered on the edges of the rect
+    StrokeRect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        radius: f32,
+        stroke_width: f32,
+        color: Color,
+    },
+    Shadow {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        blur: f32,
+        color: Color,
+    },
+    Circle {
+        cx: f32,
+        cy: f32,
+        radius: f32,
+        color: Color,
+    },
+    Text(Vec<SceneGlyph>),
+}
+
+impl Scene {
+    pub fn resize(&mut self, width: f32, height: f32, scale_factor: f32) {
+        self.width = width;
+        self.height = height;
+        self.scale_factor = scale_factor;
+    }
+
+    pub fn push(&mut self, element: SceneElement) {
+        if !self.is_paused {
+            self.elements.push(element);
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.is_paused = true;
+    }
+}
@@ -1,5 +1,5 @@
 use super::{
-    editor::code::CODE_LINE_HEIGHT,
+    editor::code::{code_attrs, line_number_metrics},
     interface::{
         component::{Component, ComponentContext, RenderParams},
         render_error,
@@ -7,23 +7,43 @@ use super::{
     },
 };
 use crate::{
-    config::Code,
+    config::{Code, DiffConfig},
     edges::margin::Margin,
-    utils::{code::CHAR_WIDTH, color::parse_hex_to_cosmic_color},
+    utils::{
+        code::prepare_code,
+        color::parse_hex_to_cosmic_color,
+        diff::{DiffLineKind, DiffPane, ParsedDiff},
+        fold::{folds_by_line, original_line_numbers},
+        wrap::wrap_code,
+    },
 };
-use cosmic_text::{Attrs, Family, Metrics};
 
 #[derive(Default)]
 pub struct LineNumber {
     children: Vec<Box<dyn Component>>,
     render_condition: bool,
     line_number_content: Vec<String>,
-    number_of_digit: usize,
+    code: String,
+    // The number of fold markers before each line
+    fold_rows: Vec<usize>,
+    // The kinds of lines of diff content, the `+` and `-` markers are drawn after the line numbers
+    diff_kinds: Vec<DiffLineKind>,
+    // The name overrides the default name, so that the style of each pane of split diff is
+    // cached separately
+    name: Option<&'static str>,
+}
+
+fn diff_marker_color(kind: DiffLineKind, diff_config: &DiffConfig) -> Option<&str> {
+    match kind {
+        DiffLineKind::Added => Some(&diff_config.added_marker_color),
+        DiffLineKind::Deleted => Some(&diff_config.deleted_marker_color),
+        DiffLineKind::Context => None,
+    }
 }
 
 impl Component for LineNumber {
-    fn name(&self) -> &'static str {
-        "LineNumber"
+    fn name(&self) -> &str {
+        self.name.unwrap_or("LineNumber")
     }
 
     fn render_condition(&self, _context: &ComponentContext) -> bool {
@@ -34,11 +54,33 @@ impl Component for LineNumber {
         &self.children
     }
 
-    fn style(&self, _context: &ComponentContext) -> RawComponentStyle {
+    fn style(&self, context: &ComponentContext) -> RawComponentStyle {
+        let code_config = &context.take_snapshot_params.code_config;
+        // All the line numbers are padded to the same digits, so the width of the line numbers is
+        // the width of anyone of them
+        let line_number = self
+            .line_number_content
+            .first()
+            .cloned()
+            .unwrap_or_default();
+        let widest_line_number = match self.diff_kinds.is_empty() {
+            true => line_number,
+            false => format!("{} +", line_number),
+        };
+        let (width, _) = context
+            .font_renderer
+            .lock()
+            .unwrap()
+            .measure_text_with_attrs(
+                line_number_metrics(code_config),
+                &widest_line_number,
+                &code_attrs(code_config),
+            );
+
         Style::default()
             .size(
-                Size::Num(CHAR_WIDTH * self.number_of_digit as f32),
-                Size::Num(self.line_number_content.len() as f32 * CODE_LINE_HEIGHT),
+                Size::Num(width),
+                Size::Num(self.visual_lines(context).len() as f32 * code_config.line_height),
             )
             .margin(Margin {
                 right: 10.,
@@ -54,22 +96,43 @@ impl Component for LineNumber {
         _style: &ComponentStyle,
         _parent_style: &ComponentStyle,
     ) -> render_error::Result<()> {
-        context.font_renderer.lock().unwrap().draw_text(
+        let code_config = &context.take_snapshot_params.code_config;
+        let diff_config = &context.take_snapshot_params.diff_config;
+        let attrs = code_attrs(code_config).color(parse_hex_to_cosmic_color(
+            context.take_snapshot_params.line_number_color.as_str(),
+        ));
+        let spans = self
+            .visual_lines(context)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                let separator = (row > 0).then(|| ("\n", attrs.clone()));
+                let line_number = line
+                    .and_then(|line| self.line_number_content.get(line))
+                    .map(|line_number| (line_number.as_str(), attrs.clone()));
+                let marker = line.and_then(|line| self.diff_kinds.get(line)).map(|kind| {
+                    let attrs = match diff_marker_color(*kind, diff_config) {
+                        Some(color) => attrs.clone().color(parse_hex_to_cosmic_color(color)),
+                        None => attrs.clone(),
+                    };
+
+                    [(" ", attrs.clone()), (kind.marker(), attrs)]
+                });
+
+                separator
+                    .into_iter()
+                    .chain(line_number)
+                    .chain(marker.into_iter().flatten())
+            })
+            .collect();
+        let text = context.font_renderer.lock().unwrap().draw_text(
             render_params.x,
             render_params.y,
-            Metrics::new(14., CODE_LINE_HEIGHT),
-            vec![(
-                &self.line_number_content.join("\n"),
-                Attrs::new()
-                    .color(parse_hex_to_cosmic_color(
-                        context.take_snapshot_params.line_number_color.as_str(),
-                    ))
-                    .family(Family::Name(
-                        &context.take_snapshot_params.code_config.font_family,
-                    )),
-            )],
+            line_number_metrics(code_config),
+            spans,
             pixmap,
         );
+        context.scene.lock().unwrap().push(text);
 
         Ok(())
     }
@@ -81,11 +144,14 @@ impl LineNumber {
             None => LineNumber::default(),
             Some(ref start_line_number) => {
                 let lines = code_content.content.split("\n").collect::<Vec<&str>>();
-                let max_line_number = lines.len() as u32 + start_line_number;
-                let number_of_digit = (max_line_number - 1).to_string().len();
+                let line_numbers =
+                    original_line_numbers(*start_line_number, lines.len(), &code_content.folds);
+                let max_line_number = line_numbers.last().copied().unwrap_or(*start_line_number);
+                let number_of_digit = max_line_number.to_string().len();
 
                 LineNumber {
-                    line_number_content: (*start_line_number..max_line_number)
+                    line_number_content: line_numbers
+                        .into_iter()
                         .map(|line_number| {
                             format!(
                                 "{:>width$}",
@@ -94,11 +160,74 @@ impl LineNumber {
                             )
                         })
                         .collect::<Vec<String>>(),
-                    number_of_digit,
                     children: vec![],
                     render_condition: true,
+                    code: prepare_code(&code_content.content),
+                    fold_rows: folds_by_line(lines.len(), &code_content.folds)
+                        .iter()
+                        .map(Vec::len)
+                        .collect(),
+                    diff_kinds: vec![],
+                    name: None,
                 }
             }
         }
     }
+
+    pub fn from_diff(parsed_diff: &ParsedDiff, code_content: &Code) -> LineNumber {
+        LineNumber {
+            line_number_content: parsed_diff.line_number_columns(),
+            children: vec![],
+            render_condition: true,
+            code: prepare_code(&code_content.content),
+            fold_rows: folds_by_line(parsed_diff.lines.len(), &code_content.folds)
+                .iter()
+                .map(Vec::len)
+                .collect(),
+            diff_kinds: parsed_diff.lines.iter().map(|line| line.kind).collect(),
+            name: None,
+        }
+    }
+
+    pub fn from_diff_pane(diff_pane: &DiffPane) -> LineNumber {
+        LineNumber {
+            line_number_content: diff_pane.line_number_column(),
+            children: vec![],
+            render_condition: true,
+            code: prepare_code(&diff_pane.code.content),
+            fold_rows: folds_by_line(diff_pane.line_numbers.len(), &diff_pane.code.folds)
+                .iter()
+                .map(Vec::len)
+                .collect(),
+            diff_kinds: diff_pane.kinds.clone(),
+            name: None,
+        }
+    }
+
+    pub fn named(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    // The index of line whose number is drawn in each visual row, the fold markers and the
+    // continuation rows of wrapped lines have no line number
+    fn visual_lines(&self, context: &ComponentContext) -> Vec<Option<usize>> {
+        let wrapped_lines = wrap_code(
+            &self.code,
+            &context.take_snapshot_params.code_config,
+            &mut context.font_renderer.lock().unwrap(),
+        );
+
+        wrapped_lines
+            .iter()
+            .zip(&self.fold_rows)
+            .enumerate()
+            .flat_map(|(line, (breaks, fold_rows))| {
+                (0..*fold_rows)
+                    .map(|_| None)
+                    .chain([Some(line)])
+                    .chain((0..breaks.len()).map(|_| None))
+            })
+            .collect()
+    }
 }
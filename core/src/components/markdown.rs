@@ -0,0 +1,2 @@
+pub mod code_fence;
+pub mod prose;
@@ -0,0 +1,115 @@
+use tiny_skia::{Color, FillRule, Paint, Rect, Transform};
+
+use crate::{
+    components::{
+        editor::code::rounded_rect_path,
+        interface::{
+            component::{Component, ComponentContext, RenderParams},
+            render_error,
+            scene::SceneElement,
+            style::{ComponentStyle, RawComponentStyle, Style},
+        },
+    },
+    edges::{edge::Edge, margin::Margin, padding::Padding},
+    utils::color::RgbaColor,
+};
+
+const CODE_FENCE_PADDING: f32 = 10.;
+const CODE_FENCE_RADIUS: f32 = 6.;
+
+// The fenced code block of Markdown, the code is drawn on a rounded background which is as wide
+// as the content of the window
+pub struct CodeFence {
+    children: Vec<Box<dyn Component>>,
+    window_padding: Padding,
+    spacing: f32,
+}
+
+impl Component for CodeFence {
+    fn children(&self) -> &Vec<Box<dyn Component>> {
+        &self.children
+    }
+
+    fn style(&self, _context: &ComponentContext) -> RawComponentStyle {
+        Style::default()
+            .padding(Padding::from_value(CODE_FENCE_PADDING))
+            .margin(Margin {
+                bottom: self.spacing,
+                ..Margin::default()
+            })
+    }
+
+    fn draw_self(
+        &self,
+        pixmap: &mut tiny_skia::Pixmap,
+        context: &ComponentContext,
+        render_params: &RenderParams,
+        style: &ComponentStyle,
+        _parent_style: &ComponentStyle,
+    ) -> render_error::Result<()> {
+        let editor_width = context
+            .style_map
+            .lock()
+            .unwrap()
+            .get("RectInnerLayer")
+            .unwrap()
+            .width;
+        let width = (editor_width - self.window_padding.horizontal())
+            .max(style.width - style.margin.horizontal());
+        let Some(rect) = Rect::from_xywh(
+            render_params.x,
+            render_params.y,
+            width,
+            style.height - style.margin.vertical(),
+        ) else {
+            return Ok(());
+        };
+        let Some(path) = rounded_rect_path(rect, CODE_FENCE_RADIUS) else {
+            return Ok(());
+        };
+        let color: RgbaColor = context
+            .take_snapshot_params
+            .markdown_config
+            .code_background
+            .as_str()
+            .into();
+        let color: Color = color.into();
+        let mut paint = Paint::default();
+
+        paint.set_color(color);
+        paint.anti_alias = true;
+        pixmap.fill_path(
+            &path,
+            &paint,
+            FillRule::Winding,
+            Transform::from_scale(context.scale_factor, context.scale_factor),
+            None,
+        );
+        context.scene.lock().unwrap().push(SceneElement::Rect {
+            x: rect.x(),
+            y: rect.y(),
+            width: rect.width(),
+            height: rect.height(),
+            radius: CODE_FENCE_RADIUS,
+            color,
+        });
+
+        Ok(())
+    }
+}
+
+impl CodeFence {
+    pub fn new(code: Box<dyn Component>, window_padding: Padding) -> CodeFence {
+        CodeFence {
+            children: vec![code],
+            window_padding,
+            spacing: 0.,
+        }
+    }
+
+    // The space below the block
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+}
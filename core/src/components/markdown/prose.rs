@@ -0,0 +1,281 @@
+use cosmic_text::{Attrs, Color, Family, Metrics, Style as FontStyle, Weight};
+use syntect::{
+    highlighting::{Highlighter, Theme},
+    parsing::Scope,
+};
+
+use crate::{
+    components::{
+        editor::code::{fill_decoration_rects, push_redacted_text, secret_rects},
+        interface::{
+            component::{Component, ComponentContext, RenderParams},
+            render_error,
+            style::{ComponentStyle, RawComponentStyle, Size, Style},
+        },
+    },
+    config::SnapshotConfig,
+    edges::margin::Margin,
+    utils::{
+        markdown::{InlineSpan, InlineStyle},
+        text::{DECORATION_METADATA, STRIKETHROUGH_METADATA, UNDERLINE_METADATA},
+    },
+};
+
+// The font sizes of headings relative to the paragraphs, from `#` to `######`
+const HEADING_SCALES: [f32; 6] = [1.6, 1.4, 1.2, 1.1, 1., 0.9];
+// The indent of each nesting level of lists, relative to the font size, the marker is drawn in
+// the indent
+const LIST_INDENT_SCALE: f32 = 1.6;
+
+#[derive(Clone, Debug)]
+pub enum ProseKind {
+    Heading(usize),
+    Paragraph,
+    ListItem { depth: usize, marker: String },
+}
+
+// The colors of prose are taken from the Markdown scopes of the theme, the scopes which are not
+// styled by the theme fallback to the foreground color
+struct ProseColors {
+    text: Color,
+    heading: Color,
+    bold: Color,
+    italic: Color,
+    code: Color,
+    link: Color,
+    marker: Color,
+}
+
+impl ProseColors {
+    fn from_theme(theme: &Theme) -> ProseColors {
+        let highlighter = Highlighter::new(theme);
+        let color = |scope: &str| {
+            let color = highlighter
+                .style_for_stack(&[Scope::new(scope).unwrap()])
+                .foreground;
+
+            Color::rgba(color.r, color.g, color.b, color.a)
+        };
+
+        ProseColors {
+            text: color("text"),
+            heading: color("markup.heading"),
+            bold: color("markup.bold"),
+            italic: color("markup.italic"),
+            code: color("markup.raw.inline"),
+            link: color("markup.underline.link"),
+            marker: color("punctuation.definition.list.begin.markdown"),
+        }
+    }
+}
+
+// A heading, paragraph or list item of Markdown, the inline spans are wrapped at the max width of
+// `markdown_config`
+pub struct Prose {
+    children: Vec<Box<dyn Component>>,
+    kind: ProseKind,
+    spans: Vec<InlineSpan>,
+    spacing: f32,
+}
+
+impl Component for Prose {
+    fn children(&self) -> &Vec<Box<dyn Component>> {
+        &self.children
+    }
+
+    fn style(&self, context: &ComponentContext) -> RawComponentStyle {
+        let config = &context.take_snapshot_params;
+        let colors = ProseColors::from_theme(&context.theme_provider.theme);
+        let (w, h) = context.font_renderer.lock().unwrap().measure_rich_text(
+            self.metrics(config),
+            self.attrs_spans(config, &colors),
+            Some(self.text_width(config)),
+        );
+
+        Style::default()
+            .size(Size::Num(self.indent(config) + w), Size::Num(h))
+            .margin(Margin {
+                bottom: self.spacing,
+                ..Margin::default()
+            })
+    }
+
+    fn draw_self(
+        &self,
+        pixmap: &mut tiny_skia::Pixmap,
+        context: &ComponentContext,
+        render_params: &RenderParams,
+        _style: &ComponentStyle,
+        _parent_style: &ComponentStyle,
+    ) -> render_error::Result<()> {
+        let config = &context.take_snapshot_params;
+        let colors = ProseColors::from_theme(&context.theme_provider.theme);
+        let metrics = self.metrics(config);
+        let x = render_params.x + self.indent(config);
+        let width = self.text_width(config);
+        let mut font_renderer = context.font_renderer.lock().unwrap();
+
+        if let ProseKind::ListItem { depth, ref marker } = self.kind {
+            let marker_x = render_params.x + depth as f32 * list_indent(config);
+            let text = font_renderer.draw_text(
+                marker_x,
+                render_params.y,
+                metrics,
+                vec![(marker, base_attrs(config).color(colors.marker))],
+                pixmap,
+            );
+
+            context.scene.lock().unwrap().push(text);
+        }
+
+        let spans = self.attrs_spans(config, &colors);
+        let text = font_renderer.draw_wrapped_text(
+            x,
+            render_params.y,
+            metrics,
+            spans.clone(),
+            width,
+            pixmap,
+        );
+        let decoration_rects = match spans
+            .iter()
+            .any(|(_, attrs)| attrs.metadata & DECORATION_METADATA != 0)
+        {
+            true => font_renderer.decoration_rects(
+                x,
+                render_params.y,
+                metrics,
+                spans.clone(),
+                Some(width),
+            ),
+            false => vec![],
+        };
+
+        drop(font_renderer);
+
+        // The decorations are drawn before redacting, so that the links which are secrets are
+        // covered as well
+        let redaction_rects =
+            secret_rects(context, x, render_params.y, metrics, spans, Some(width));
+
+        fill_decoration_rects(pixmap, context, decoration_rects);
+        push_redacted_text(pixmap, context, text, redaction_rects);
+
+        Ok(())
+    }
+}
+
+fn base_attrs(config: &SnapshotConfig) -> Attrs<'_> {
+    let family = match config.markdown_config.font_family {
+        Some(ref font_family) => Family::Name(font_family),
+        None => Family::SansSerif,
+    };
+
+    Attrs::new().family(family)
+}
+
+fn list_indent(config: &SnapshotConfig) -> f32 {
+    config.markdown_config.font_size * LIST_INDENT_SCALE
+}
+
+impl Prose {
+    pub fn new(kind: ProseKind, spans: Vec<InlineSpan>) -> Prose {
+        Prose {
+            children: vec![],
+            kind,
+            spans,
+            spacing: 0.,
+        }
+    }
+
+    // The space below the block
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    fn metrics(&self, config: &SnapshotConfig) -> Metrics {
+        let markdown_config = &config.markdown_config;
+        let scale = match self.kind {
+            ProseKind::Heading(level) => HEADING_SCALES[level.clamp(1, 6) - 1],
+            _ => 1.,
+        };
+
+        Metrics::new(
+            markdown_config.font_size * scale,
+            markdown_config.line_height * scale,
+        )
+    }
+
+    // The list item is indented by its nesting level and the marker
+    fn indent(&self, config: &SnapshotConfig) -> f32 {
+        match self.kind {
+            ProseKind::ListItem { depth, .. } => (depth + 1) as f32 * list_indent(config),
+            _ => 0.,
+        }
+    }
+
+    fn text_width(&self, config: &SnapshotConfig) -> f32 {
+        (config.markdown_config.max_width - self.indent(config)).max(list_indent(config))
+    }
+
+    fn attrs_spans<'a>(
+        &'a self,
+        config: &'a SnapshotConfig,
+        colors: &ProseColors,
+    ) -> Vec<(&'a str, Attrs<'a>)> {
+        let is_heading = matches!(self.kind, ProseKind::Heading(_));
+
+        self.spans
+            .iter()
+            .map(|span| {
+                (
+                    span.text.as_str(),
+                    inline_attrs(config, colors, span.style, is_heading),
+                )
+            })
+            .collect()
+    }
+}
+
+fn inline_attrs<'a>(
+    config: &'a SnapshotConfig,
+    colors: &ProseColors,
+    style: InlineStyle,
+    is_heading: bool,
+) -> Attrs<'a> {
+    let attrs = match style.code {
+        true => Attrs::new().family(Family::Name(&config.code_config.font_family)),
+        false => base_attrs(config),
+    };
+    let color = if style.link {
+        colors.link
+    } else if style.code {
+        colors.code
+    } else if is_heading {
+        colors.heading
+    } else if style.bold {
+        colors.bold
+    } else if style.italic {
+        colors.italic
+    } else {
+        colors.text
+    };
+    let attrs = attrs.color(color);
+    let attrs = match style.bold || is_heading {
+        true => attrs.weight(Weight::BOLD),
+        false => attrs,
+    };
+    let attrs = match style.italic {
+        true => attrs.style(FontStyle::Italic),
+        false => attrs,
+    };
+    let metadata = match (style.link, style.strikethrough) {
+        (true, true) => DECORATION_METADATA,
+        (true, false) => UNDERLINE_METADATA,
+        (false, true) => STRIKETHROUGH_METADATA,
+        (false, false) => 0,
+    };
+
+    attrs.metadata(metadata)
+}
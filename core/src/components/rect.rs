@@ -1,6 +1,7 @@
 use super::interface::{
     component::{Component, ComponentContext, RenderParams},
     render_error,
+    scene::SceneElement,
     style::{ComponentAlign, ComponentStyle, RawComponentStyle, Style},
 };
 use crate::{
@@ -31,7 +32,7 @@ pub struct Rect {
 }
 
 impl Component for Rect {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         self.component_name
     }
 
@@ -109,6 +110,14 @@ impl Component for Rect {
         }
 
         pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+        context.scene.lock().unwrap().push(SceneElement::Rect {
+            x,
+            y,
+            width: w,
+            height: h,
+            radius: self.radius,
+            color: self.color,
+        });
 
         Ok(())
     }
@@ -196,6 +205,14 @@ impl Rect {
             transform,
             None,
         );
+        context.scene.lock().unwrap().push(SceneElement::Shadow {
+            x: render_params.x + shadow.x,
+            y: render_params.y + shadow.y,
+            width: w,
+            height: h,
+            blur: shadow.blur,
+            color: shadow.color,
+        });
     }
 
     // The implementation of boder in CodeSnap is create a new Rect component with border color
@@ -0,0 +1,107 @@
+use tiny_skia::{Color, Paint, Rect, Transform};
+
+use crate::{edges::margin::Margin, utils::color::RgbaColor};
+
+use super::interface::{
+    component::{Component, ComponentContext, RenderParams},
+    render_error,
+    scene::SceneElement,
+    style::{ComponentStyle, RawComponentStyle, Style},
+};
+
+// The gap between the panes, the divider is drawn in the middle of the gap
+pub const SPLIT_PANE_GAP: f32 = 20.;
+const DIVIDER_WIDTH: f32 = 1.;
+
+// A pane of split view, such as the old and new versions of split diff, the panes are placed in a
+// row, and each of them is as wide as its content
+pub struct SplitPane {
+    children: Vec<Box<dyn Component>>,
+    name: &'static str,
+    // The leading pane is followed by the gap, and the trailing pane draws the divider before it
+    leading: bool,
+}
+
+impl Component for SplitPane {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn children(&self) -> &Vec<Box<dyn Component>> {
+        &self.children
+    }
+
+    fn style(&self, _context: &ComponentContext) -> RawComponentStyle {
+        match self.leading {
+            true => Style::default().margin(Margin {
+                right: SPLIT_PANE_GAP,
+                ..Margin::default()
+            }),
+            false => Style::default(),
+        }
+    }
+
+    fn draw_self(
+        &self,
+        pixmap: &mut tiny_skia::Pixmap,
+        context: &ComponentContext,
+        render_params: &RenderParams,
+        style: &ComponentStyle,
+        _parent_style: &ComponentStyle,
+    ) -> render_error::Result<()> {
+        if self.leading {
+            return Ok(());
+        }
+
+        let color: RgbaColor = context
+            .take_snapshot_params
+            .line_number_color
+            .as_str()
+            .into();
+        let color: Color = color.into();
+        let mut paint = Paint::default();
+        let rect = Rect::from_xywh(
+            render_params.x - (SPLIT_PANE_GAP + DIVIDER_WIDTH) / 2.,
+            render_params.y,
+            DIVIDER_WIDTH,
+            style.height,
+        )
+        .unwrap();
+
+        paint.set_color(color);
+        pixmap.fill_rect(
+            rect,
+            &paint,
+            Transform::from_scale(context.scale_factor, context.scale_factor),
+            None,
+        );
+        context.scene.lock().unwrap().push(SceneElement::Rect {
+            x: rect.x(),
+            y: rect.y(),
+            width: rect.width(),
+            height: rect.height(),
+            radius: 0.,
+            color,
+        });
+
+        Ok(())
+    }
+}
+
+impl SplitPane {
+    pub fn leading(name: &'static str, children: Vec<Box<dyn Component>>) -> SplitPane {
+        SplitPane {
+            children,
+            name,
+            leading: true,
+        }
+    }
+
+    pub fn trailing(name: &'static str, children: Vec<Box<dyn Component>>) -> SplitPane {
+        SplitPane {
+            children,
+            name,
+            leading: false,
+        }
+    }
+}
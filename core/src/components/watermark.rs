@@ -15,7 +15,7 @@ pub struct Watermark {
 }
 
 impl Component for Watermark {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "Watermark"
     }
 
@@ -32,7 +32,7 @@ impl Component for Watermark {
             .color(parse_hex_to_cosmic_color(&config.color))
             .family(Family::Name(&config.font_family));
 
-        context.font_renderer.lock().unwrap().draw_line(
+        let text = context.font_renderer.lock().unwrap().draw_line(
             0.,
             render_params.y,
             Metrics::new(20., 20.),
@@ -41,6 +41,7 @@ impl Component for Watermark {
             Some(Align::Center),
             pixmap,
         );
+        context.scene.lock().unwrap().push(text);
 
         Ok(())
     }
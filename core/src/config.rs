@@ -1,10 +1,15 @@
+use std::collections::HashMap;
+
 use derive_builder::Builder;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tiny_skia::{Color, GradientStop};
 
 use crate::{
-    snapshot::{ascii_snapshot::ASCIISnapshot, image_snapshot::ImageSnapshot},
+    snapshot::{
+        animation_snapshot::AnimationSnapshot, ascii_snapshot::ASCIISnapshot,
+        image_snapshot::ImageSnapshot,
+    },
     themes::get_theme,
     utils::color::RgbaColor,
 };
@@ -67,8 +72,8 @@ impl Point<DimensionValue> {
 
 #[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
 pub struct LinearGradientStop {
-    position: f32,
-    color: String,
+    pub position: f32,
+    pub color: String,
 }
 
 impl LinearGradientStop {
@@ -193,6 +198,150 @@ impl WindowBuilder {
 pub enum HighlightLine {
     Single(u32, String),
     Range(u32, u32, String),
+    /// The added or deleted lines (such as the changed lines of a diff), they are highlighted the
+    /// same as `Range`, and are marked with `+` or `-` instead of `>` in the ASCII snapshot
+    Changed(u32, u32, String, LineChange),
+}
+
+impl HighlightLine {
+    /// The start and end line numbers, the end may be less than the start
+    pub fn range(&self) -> (u32, u32) {
+        match self {
+            HighlightLine::Single(line_number, _) => (*line_number, *line_number),
+            HighlightLine::Range(start, end, _) | HighlightLine::Changed(start, end, _, _) => {
+                (*start, *end)
+            }
+        }
+    }
+
+    pub fn color(&self) -> &str {
+        match self {
+            HighlightLine::Single(_, color)
+            | HighlightLine::Range(_, _, color)
+            | HighlightLine::Changed(_, _, color, _) => color,
+        }
+    }
+
+    pub fn change(&self) -> Option<LineChange> {
+        match self {
+            HighlightLine::Changed(_, _, _, change) => Some(*change),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineChange {
+    Added,
+    Deleted,
+}
+
+impl LineChange {
+    pub fn marker(self) -> &'static str {
+        match self {
+            LineChange::Added => "+",
+            LineChange::Deleted => "-",
+        }
+    }
+}
+
+/// The chars of a line which are highlighted by `HighlightToken`
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenTarget {
+    /// The start and end columns of the token, the columns are counted by chars from 1, and the
+    /// end column is included, for example `"columns": [5, 9]`
+    Columns(u32, u32),
+    /// Highlight all matches of the regex in the line, for example `"pattern": "\\bfoo\\b"`
+    Pattern(String),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightTokenStyle {
+    /// Draw a rounded box around the chars
+    #[default]
+    Box,
+    /// Draw a line under the chars
+    Underline,
+    /// Paint the background of the chars
+    Background,
+}
+
+/// Highlight a part of a line instead of the whole line, such as an argument or an identifier
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct HighlightToken {
+    /// The line of the token, the line number is start from 1 and is counted in the displayed
+    /// code
+    pub line: u32,
+
+    #[serde(flatten)]
+    pub target: TokenTarget,
+
+    #[builder(setter(into), default = String::from("#ffcb6b"))]
+    #[serde(default = "default_highlight_token_color")]
+    pub color: String,
+
+    #[builder(default)]
+    #[serde(default)]
+    pub style: HighlightTokenStyle,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationPlacement {
+    /// Draw the callout in the right margin of the code, the callouts are stacked if they overlap
+    #[default]
+    Right,
+    /// Draw the callout below the line, the following lines are pushed down to make room for it
+    Below,
+}
+
+/// A note which is attached to a line or a token, the note is drawn as a callout bubble which is
+/// connected to the line or the token
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct Annotation {
+    /// The line of the annotation, the line number is start from 1 and is counted in the displayed
+    /// code
+    pub line: u32,
+
+    /// Attach the annotation to the token of the line instead of the whole line, the annotation is
+    /// attached to the first match if the pattern matches multiple times
+    #[builder(setter(into, strip_option), default = None)]
+    #[serde(default)]
+    pub token: Option<TokenTarget>,
+
+    /// The text of the callout, use `\n` to break the text into multiple lines
+    #[builder(setter(into), default = String::new())]
+    #[serde(default)]
+    pub text: String,
+
+    /// Prefix the callout with a numbered marker, such as ①, ② and ③, the numbered annotations
+    /// are numbered in order, so that the prose around the snapshot can refer to them
+    #[builder(default = false)]
+    #[serde(default)]
+    pub numbered: bool,
+
+    #[builder(default)]
+    #[serde(default)]
+    pub placement: AnnotationPlacement,
+
+    #[builder(setter(into), default = String::from("#82aaff"))]
+    #[serde(default = "default_annotation_color")]
+    pub color: String,
+}
+
+/// Hide a part of a line, the chars are blurred or pixelated in the image, and replaced with `█`
+/// in the ASCII snapshot
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct Redaction {
+    /// The line of the redacted chars, the line number is start from 1 and is counted in the
+    /// displayed code
+    pub line: u32,
+
+    #[serde(flatten)]
+    pub target: TokenTarget,
 }
 
 #[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
@@ -216,6 +365,21 @@ pub struct Code {
     #[serde(default)]
     pub highlight_lines: Vec<HighlightLine>,
 
+    /// Highlight the columns or the regex matches of lines, see `HighlightToken`
+    #[builder(setter(into), default = vec![])]
+    #[serde(default)]
+    pub highlight_tokens: Vec<HighlightToken>,
+
+    /// The chars which are hidden in the snapshot, see `Redaction`
+    #[builder(setter(into), default = vec![])]
+    #[serde(default)]
+    pub redactions: Vec<Redaction>,
+
+    /// The callouts which are attached to lines or tokens, see `Annotation`
+    #[builder(setter(into), default = vec![])]
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+
     /// The `language` will be used to determine the syntax highlighting to use for generating
     /// the snapshot.
     #[builder(setter(into, strip_option), default = None)]
@@ -223,6 +387,57 @@ pub struct Code {
 
     #[builder(setter(into, strip_option), default = None)]
     pub file_path: Option<String>,
+
+    /// The collapsed regions of the code, each of them is displayed as a fold marker such as
+    /// `⋯ 34 lines hidden`, and the line numbers after the fold are shifted by the hidden lines,
+    /// so that the line numbers are still the same as the original code
+    #[builder(setter(into), default = vec![])]
+    #[serde(default)]
+    pub folds: Vec<Fold>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct Fold {
+    /// The fold marker is drawn before this line, the line number is start from 1 and is counted
+    /// in the displayed code
+    pub line: u32,
+
+    /// How many lines are hidden by the fold
+    pub hidden_lines: u32,
+
+    /// The text of the fold marker, such as the hunk header of a diff, the default marker is
+    /// `⋯ n lines hidden`
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// A unified diff, such as the output of `git diff`, the added and deleted lines are marked in
+/// the gutter and highlighted with `diff_config` colors, and each hunk starts with its header
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct Diff {
+    #[builder(setter(into))]
+    pub diff: String,
+
+    /// The `language` will be used to determine the syntax highlighting of the changed code
+    #[builder(setter(into, strip_option), default = None)]
+    pub language: Option<String>,
+
+    /// The path of the changed file, if it's not provided, the path in the `+++` header of the
+    /// diff is used
+    #[builder(setter(into, strip_option), default = None)]
+    pub file_path: Option<String>,
+}
+
+/// A Markdown document, the prose is drawn in a proportional font with the basic formatting
+/// (headings, emphasis, inline code, links and lists), and each fenced code block is highlighted
+/// by its own language
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct Markdown {
+    #[builder(setter(into))]
+    pub markdown: String,
+
+    #[builder(setter(into, strip_option), default = None)]
+    pub file_path: Option<String>,
 }
 
 #[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema, Default)]
@@ -248,9 +463,260 @@ pub struct CommandOutputConfig {
 pub enum Content {
     Code(Code),
     CommandOutput(Vec<CommandLineContent>),
+    Diff(Diff),
+    Markdown(Markdown),
 }
 
-#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLayout {
+    /// The old and new lines are drawn in one editor pane, the deleted lines are followed by the
+    /// added lines
+    #[default]
+    Unified,
+    /// The old version is drawn in the left pane, and the new version is drawn in the right pane,
+    /// the changed lines are paired up in the same row
+    Split,
+}
+
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct DiffConfig {
+    #[builder(default)]
+    #[serde(default)]
+    pub layout: DiffLayout,
+
+    /// The background color of added lines
+    #[builder(setter(into), default = String::from("#2ecc7130"))]
+    #[serde(default = "default_added_line_color")]
+    pub added_line_color: String,
+
+    /// The background color of deleted lines
+    #[builder(setter(into), default = String::from("#ff6b6b30"))]
+    #[serde(default = "default_deleted_line_color")]
+    pub deleted_line_color: String,
+
+    /// The color of `+` markers in the gutter
+    #[builder(setter(into), default = String::from("#2ecc71"))]
+    #[serde(default = "default_added_marker_color")]
+    pub added_marker_color: String,
+
+    /// The color of `-` markers in the gutter
+    #[builder(setter(into), default = String::from("#ff6b6b"))]
+    #[serde(default = "default_deleted_marker_color")]
+    pub deleted_marker_color: String,
+
+    /// The background color of the changed chars of paired added lines in the split layout
+    #[builder(setter(into), default = String::from("#2ecc7160"))]
+    #[serde(default = "default_added_emphasis_color")]
+    pub added_emphasis_color: String,
+
+    /// The background color of the changed chars of paired deleted lines in the split layout
+    #[builder(setter(into), default = String::from("#ff6b6b60"))]
+    #[serde(default = "default_deleted_emphasis_color")]
+    pub deleted_emphasis_color: String,
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        DiffConfigBuilder::default().build().unwrap()
+    }
+}
+
+/// The built-in detectors of secrets, the matched chars are redacted as `Redaction`
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretDetector {
+    /// The well-known API keys (such as GitHub, Slack, Stripe and Google API keys), and the quoted
+    /// or env-style values assigned to the keys named like `api_key`, `secret`, `token` or
+    /// `password`
+    ApiKey,
+    /// JSON Web Tokens
+    Jwt,
+    /// The AWS access key IDs and secret access keys
+    AwsKey,
+    Email,
+    /// IPv4 and IPv6 addresses
+    Ip,
+}
+
+impl SecretDetector {
+    pub fn all() -> Vec<SecretDetector> {
+        vec![
+            SecretDetector::ApiKey,
+            SecretDetector::Jwt,
+            SecretDetector::AwsKey,
+            SecretDetector::Email,
+            SecretDetector::Ip,
+        ]
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionStyle {
+    #[default]
+    Blur,
+    Pixelate,
+}
+
+/// Options of redaction, the vector outputs (such as SVG and PDF) can't be blurred, the redacted
+/// chars are removed from them and covered by blocks
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct RedactConfig {
+    /// Redact the secrets found by the detectors in addition to the `redactions` of code, no
+    /// detector is enabled by default
+    #[builder(setter(into), default = vec![])]
+    #[serde(default)]
+    pub detectors: Vec<SecretDetector>,
+
+    #[builder(default)]
+    #[serde(default)]
+    pub style: RedactionStyle,
+}
+
+impl Default for RedactConfig {
+    fn default() -> Self {
+        RedactConfigBuilder::default().build().unwrap()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusStyle {
+    /// Reduce the opacity of the unfocused lines
+    #[default]
+    Dim,
+    /// Turn the unfocused lines gray, the `opacity` is still applied, set it to 1 to keep the
+    /// gray lines opaque
+    Desaturate,
+}
+
+/// Focus on the highlighted lines, the lines outside the `highlight_lines` are dimmed or
+/// desaturated, and the highlighted lines are still painted with their background colors
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct FocusConfig {
+    /// The focus mode is disabled by default, nothing is dimmed if no line is highlighted
+    #[builder(default = false)]
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[builder(default)]
+    #[serde(default)]
+    pub style: FocusStyle,
+
+    /// The opacity of the unfocused lines, range from 0 to 1
+    #[builder(default = 0.35)]
+    #[serde(default = "default_focus_opacity")]
+    pub opacity: f32,
+
+    /// Blur the unfocused lines by the radius in pixels, the vector outputs (such as SVG and PDF)
+    /// can't be blurred, so they are only dimmed. The unfocused lines are not blurred by default
+    #[builder(default = 0.)]
+    #[serde(default)]
+    pub blur: f32,
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        FocusConfigBuilder::default().build().unwrap()
+    }
+}
+
+/// Resolve the `language` of the code to a syntax, the language is matched by the name, the token
+/// (such as `rs`) or the alias of the syntax
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct LanguageConfig {
+    /// The aliases of the languages, for example `{ "tsx": "TypeScriptReact", "yml": "YAML" }`,
+    /// the alias is mapped onto the name or the token of a syntax, and takes precedence over the
+    /// built-in aliases
+    #[builder(setter(into), default = HashMap::new())]
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+
+    /// Fail with the close matches of the language instead of rendering the code as plain text
+    /// if the `language` doesn't match any syntax, or the language of code isn't detected (the
+    /// close matches of the file extension are suggested)
+    #[builder(default = false)]
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl Default for LanguageConfig {
+    fn default() -> Self {
+        LanguageConfigBuilder::default().build().unwrap()
+    }
+}
+
+/// The typography of Markdown content, the fenced code blocks are still drawn by `code_config`
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct MarkdownConfig {
+    /// The font of the prose, the sans-serif font of the system is used by default
+    #[builder(setter(into, strip_option), default = None)]
+    #[serde(default)]
+    pub font_family: Option<String>,
+
+    /// The font size of the paragraphs in pixels, the headings are scaled by their levels
+    #[builder(default = 14.)]
+    #[serde(default = "default_markdown_font_size")]
+    pub font_size: f32,
+
+    #[builder(default = 22.)]
+    #[serde(default = "default_markdown_line_height")]
+    pub line_height: f32,
+
+    /// The prose is wrapped at the max width in pixels, the fenced code blocks are wrapped by the
+    /// `max_width` of `code_config`
+    #[builder(default = 560.)]
+    #[serde(default = "default_markdown_max_width")]
+    pub max_width: f32,
+
+    /// The background color of the fenced code blocks
+    #[builder(setter(into), default = String::from("#00000033"))]
+    #[serde(default = "default_markdown_code_background")]
+    pub code_background: String,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        MarkdownConfigBuilder::default().build().unwrap()
+    }
+}
+
+/// The max width of the code, in columns or in pixels, for example `{ "columns": 80 }`
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MaxWidth {
+    Columns(usize),
+    Pixels(f32),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+    /// Never wrap the lines, the window is stretched by the longest line
+    None,
+    /// Wrap the lines at the max width
+    #[default]
+    Char,
+    /// Wrap the lines at the last word boundary before the max width, the long word which
+    /// doesn't fit in a row is still wrapped at the max width
+    Word,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightBackend {
+    /// Highlight the code by the Sublime Text grammars of syntect
+    #[default]
+    Syntect,
+    /// Highlight the code by the tree-sitter grammars, they are more accurate for the modern
+    /// syntax (such as async closures of Rust and TSX), the captures are mapped onto the scopes of
+    /// the theme. It requires the `tree-sitter` feature, and the languages which have no
+    /// tree-sitter grammar are still highlighted by syntect
+    TreeSitter,
+}
+
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
 pub struct CodeConfig {
     // #[builder(setter(into), default = String::from(""))]
     // #[serde(default)]
@@ -268,6 +734,156 @@ pub struct CodeConfig {
     #[builder(setter(into, strip_option), default = BreadcrumbsBuilder::default().build().unwrap())]
     #[serde(default)]
     pub breadcrumbs: Breadcrumbs,
+
+    /// Long lines stretch the window by default, if the `max_width` is provided, the lines which
+    /// are longer than the `max_width` are wrapped (see `wrap_mode`), so that the snapshot can fit
+    /// the fixed-width templates.
+    ///
+    /// The continuation rows of a wrapped line start with a wrap indicator, and have no line
+    /// number.
+    #[builder(setter(into, strip_option), default = None)]
+    #[serde(default)]
+    pub max_width: Option<MaxWidth>,
+
+    #[builder(setter(into), default = WrapMode::Char)]
+    #[serde(default)]
+    pub wrap_mode: WrapMode,
+
+    /// The font size of the code in pixels, the line numbers and the command line are scaled with
+    /// it, bigger type is useful for slides
+    #[builder(default = 12.5)]
+    #[serde(default = "default_font_size")]
+    pub font_size: f32,
+
+    /// The height of each code line in pixels, the highlight lines and line numbers are aligned to
+    /// it
+    #[builder(default = 18.)]
+    #[serde(default = "default_line_height")]
+    pub line_height: f32,
+
+    /// The extra space between chars in pixels, it can be negative to tighten the code
+    #[builder(default = 0.)]
+    #[serde(default)]
+    pub letter_spacing: f32,
+
+    #[builder(default)]
+    #[serde(default)]
+    pub highlight_backend: HighlightBackend,
+}
+
+impl Default for CodeConfig {
+    fn default() -> Self {
+        CodeConfigBuilder::default().build().unwrap()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevealMode {
+    #[default]
+    Char,
+    Line,
+}
+
+/// The animated snapshot reveals the code progressively, as if someone is typing it, the window
+/// frame, shadow and background stay fixed while the code is drawing
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct Animation {
+    /// Reveal the code char by char or line by line
+    #[builder(setter(into), default = RevealMode::Char)]
+    #[serde(default)]
+    pub reveal_mode: RevealMode,
+
+    /// How many chars (or lines) are revealed in each frame, whitespace is not counted in
+    /// `char` mode, so the typing looks smooth
+    #[builder(default = 1)]
+    #[serde(default = "default_animation_step")]
+    pub step: usize,
+
+    /// The duration of each frame in milliseconds
+    #[builder(default = 60)]
+    #[serde(default = "default_frame_delay")]
+    pub frame_delay: u16,
+
+    /// How long the complete code is displayed before the animation restarts, in milliseconds
+    #[builder(default = 3000)]
+    #[serde(default = "default_final_frame_delay")]
+    pub final_frame_delay: u16,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        AnimationBuilder::default().build().unwrap()
+    }
+}
+
+/// Options for encoding the snapshot to raster image formats
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct Encoding {
+    /// The quality of lossy formats (JPEG, WebP and AVIF), range from 1 to 100
+    #[builder(default = 90)]
+    #[serde(default = "default_quality")]
+    pub quality: u8,
+
+    /// Encode WebP losslessly, the `quality` is ignored for lossless WebP
+    #[builder(default = false)]
+    #[serde(default)]
+    pub lossless: bool,
+
+    /// JPEG doesn't support transparency, the transparent pixels (such as the margin of the
+    /// snapshot with a transparent background) are flattened onto the matte color
+    #[builder(setter(into), default = String::from("#ffffff"))]
+    #[serde(default = "default_matte_color")]
+    pub matte_color: String,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        EncodingBuilder::default().build().unwrap()
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ASCIIFrameStyle {
+    #[default]
+    Rounded,
+    Double,
+    Heavy,
+    Plain,
+    Markdown,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ANSIColorMode {
+    /// 24-bit colors, supported by most of the modern terminals
+    #[default]
+    TrueColor,
+    /// The 256-color palette, for the terminals which don't support 24-bit colors
+    Ansi256,
+}
+
+/// Options for the ASCII "snapshot"
+#[derive(Clone, Builder, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct ASCIIConfig {
+    /// The style of the frame, `plain` draws the frame with `+`, `-` and `|` for the environments
+    /// which don't support Unicode, and `markdown` wraps the code in a fenced code block instead
+    /// of drawing a frame
+    #[builder(setter(into), default = ASCIIFrameStyle::Rounded)]
+    #[serde(default)]
+    pub frame_style: ASCIIFrameStyle,
+
+    /// The colors used by the ANSI "snapshot", see `create_ansi_snapshot` for more detail
+    #[builder(setter(into), default = ANSIColorMode::TrueColor)]
+    #[serde(default)]
+    pub color_mode: ANSIColorMode,
+}
+
+impl Default for ASCIIConfig {
+    fn default() -> Self {
+        ASCIIConfigBuilder::default().build().unwrap()
+    }
 }
 
 /// Draw a watermark below the code, you can use this to add a logo or any other text
@@ -312,6 +928,31 @@ pub struct SnapshotConfig {
     #[builder(setter(into), default = CodeConfigBuilder::default().build().unwrap())]
     pub code_config: CodeConfig,
 
+    /// The colors of diff content
+    #[builder(setter(into), default = DiffConfigBuilder::default().build().unwrap())]
+    #[serde(default)]
+    pub diff_config: DiffConfig,
+
+    /// Hide the secrets in the code, see `RedactConfig`
+    #[builder(setter(into), default = RedactConfigBuilder::default().build().unwrap())]
+    #[serde(default)]
+    pub redact_config: RedactConfig,
+
+    /// Dim the lines outside the highlighted lines, see `FocusConfig`
+    #[builder(setter(into), default = FocusConfigBuilder::default().build().unwrap())]
+    #[serde(default)]
+    pub focus_config: FocusConfig,
+
+    /// The aliases of languages and the strict mode, see `LanguageConfig`
+    #[builder(setter(into), default = LanguageConfigBuilder::default().build().unwrap())]
+    #[serde(default)]
+    pub language_config: LanguageConfig,
+
+    /// The typography of Markdown content, see `MarkdownConfig`
+    #[builder(setter(into), default = MarkdownConfigBuilder::default().build().unwrap())]
+    #[serde(default)]
+    pub markdown_config: MarkdownConfig,
+
     #[builder(setter(into), default = None)]
     pub watermark: Option<Watermark>,
 
@@ -342,6 +983,16 @@ pub struct SnapshotConfig {
     #[builder(setter(into, strip_option), default = vec![])]
     pub fonts_folders: Vec<String>,
 
+    /// Load the syntax definitions (`.sublime-syntax` files and prebuilt `.packdump` files) from
+    /// the `syntaxes_folders`, they are merged into the default syntaxes, so that the languages
+    /// which are not supported by default (such as internal DSLs) can be highlighted.
+    ///
+    /// The syntax is chosen by the `language` or the extension of `file_path` of the code as the
+    /// default syntaxes.
+    #[builder(setter(into, strip_option), default = vec![])]
+    #[serde(default)]
+    pub syntaxes_folders: Vec<String>,
+
     /// CodeSnap use Syntect as the syntax highlighting engine, you can provide a custom theme
     /// for code highlighting and background.
     /// The theme is load from the `themes_folders`(if not provided, CodeSnap load the default
@@ -359,6 +1010,18 @@ pub struct SnapshotConfig {
 
     #[builder(setter(into, strip_option), default = None)]
     pub title: Option<String>,
+
+    #[builder(setter(into), default = AnimationBuilder::default().build().unwrap())]
+    #[serde(default)]
+    pub animation: Animation,
+
+    #[builder(setter(into), default = EncodingBuilder::default().build().unwrap())]
+    #[serde(default)]
+    pub encoding: Encoding,
+
+    #[builder(setter(into), default = ASCIIConfigBuilder::default().build().unwrap())]
+    #[serde(default)]
+    pub ascii_config: ASCIIConfig,
 }
 
 impl CodeSnap {
@@ -458,8 +1121,107 @@ impl SnapshotConfig {
     pub fn create_ascii_snapshot(&self) -> anyhow::Result<ASCIISnapshot> {
         ASCIISnapshot::from_config(self.clone())
     }
+
+    /// Create an ANSI "snapshot" from the config, the ANSI "snapshot" is an ASCII "snapshot" with
+    /// the code highlighted by ANSI escape sequences, the highlighting is the same as the image
+    /// snapshot, so it looks great in the terminal.
+    ///
+    /// The colors are disabled if the `NO_COLOR` environment variable is set, and the markdown
+    /// frame style is never colored, since the escape sequences break the markdown.
+    pub fn create_ansi_snapshot(&self) -> anyhow::Result<ASCIISnapshot> {
+        ASCIISnapshot::from_ansi_config(self.clone())
+    }
+
+    /// Create an animated "typing" snapshot from the config, the code is revealed char by char or
+    /// line by line (see `animation` config), and the frames can be encoded as GIF, APNG or
+    /// animated WebP.
+    ///
+    /// The animated snapshot only supports code content.
+    pub fn create_animation_snapshot(&self) -> anyhow::Result<AnimationSnapshot> {
+        AnimationSnapshot::from_config(self.clone())
+    }
 }
 
 fn default_scale_factor() -> u8 {
     3
 }
+
+fn default_animation_step() -> usize {
+    1
+}
+
+fn default_frame_delay() -> u16 {
+    60
+}
+
+fn default_final_frame_delay() -> u16 {
+    3000
+}
+
+fn default_font_size() -> f32 {
+    12.5
+}
+
+fn default_line_height() -> f32 {
+    18.
+}
+
+fn default_added_line_color() -> String {
+    String::from("#2ecc7130")
+}
+
+fn default_deleted_line_color() -> String {
+    String::from("#ff6b6b30")
+}
+
+fn default_added_marker_color() -> String {
+    String::from("#2ecc71")
+}
+
+fn default_deleted_marker_color() -> String {
+    String::from("#ff6b6b")
+}
+
+fn default_highlight_token_color() -> String {
+    String::from("#ffcb6b")
+}
+
+fn default_annotation_color() -> String {
+    String::from("#82aaff")
+}
+
+fn default_added_emphasis_color() -> String {
+    String::from("#2ecc7160")
+}
+
+fn default_deleted_emphasis_color() -> String {
+    String::from("#ff6b6b60")
+}
+
+fn default_focus_opacity() -> f32 {
+    0.35
+}
+
+fn default_quality() -> u8 {
+    90
+}
+
+fn default_matte_color() -> String {
+    String::from("#ffffff")
+}
+
+fn default_markdown_font_size() -> f32 {
+    14.
+}
+
+fn default_markdown_line_height() -> f32 {
+    22.
+}
+
+fn default_markdown_max_width() -> f32 {
+    560.
+}
+
+fn default_markdown_code_background() -> String {
+    String::from("#00000033")
+}
@@ -1,3 +1,8 @@
+pub mod animation_snapshot;
 pub mod ascii_snapshot;
+pub mod html_renderer;
 pub mod image_snapshot;
+pub mod pdf_renderer;
 pub mod snapshot_data;
+pub mod svg_renderer;
+pub mod terminal_renderer;
@@ -0,0 +1,558 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use image_webp::{ColorType, WebPEncoder};
+use tiny_skia::Pixmap;
+
+use crate::{
+    components::{
+        editor::code::Code,
+        interface::{component::Component, style::ComponentStyle},
+    },
+    config::{Animation, Content, RevealMode, SnapshotConfig},
+    utils::{
+        annotation::reserve_annotation_rows, code::prepare_code, syntax_provider::SyntaxProvider,
+        theme_provider::ThemeProvider,
+    },
+};
+
+use super::{image_snapshot::ImageSnapshot, snapshot_data::SnapshotData};
+
+// The speed of GIF color quantization, range from 1 (best quality) to 30 (fastest)
+const GIF_QUANTIZE_SPEED: i32 = 10;
+
+// Do not blend the frame with the previous canvas, and do not dispose the frame
+const WEBP_FRAME_FLAGS: u8 = 0b10;
+
+// The region of the frame which is changed from the previous frame, only the changed region is
+// encoded to keep the animation small, because most of the snapshot (frame, background, etc.)
+// stays the same between frames
+#[derive(Clone, Copy)]
+struct FrameRegion {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+pub struct AnimationSnapshot {
+    frames: Vec<Pixmap>,
+    // The duration of each frame in milliseconds
+    delays: Vec<u16>,
+}
+
+// Calculate how many chars of the code are visible in each frame, the first frame is an empty
+// editor, and the last frame is the complete code
+fn reveal_counts(code: &str, animation: &Animation) -> Vec<usize> {
+    let step = animation.step.max(1);
+    let chars = code.chars().collect::<Vec<char>>();
+    let mut counts = vec![0];
+    let mut revealed = 0;
+
+    for (index, char) in chars.iter().enumerate() {
+        let is_revealed = match animation.reveal_mode {
+            // Whitespace is revealed along with the next visible char
+            RevealMode::Char => !char.is_whitespace(),
+            RevealMode::Line => *char == '\n',
+        };
+
+        if is_revealed {
+            revealed += 1;
+
+            if revealed % step == 0 {
+                counts.push(match animation.reveal_mode {
+                    RevealMode::Char => index + 1,
+                    RevealMode::Line => index,
+                });
+            }
+        }
+    }
+
+    if counts.last() != Some(&chars.len()) {
+        counts.push(chars.len());
+    }
+
+    counts
+}
+
+fn changed_region(previous: Option<&Pixmap>, current: &Pixmap) -> FrameRegion {
+    let (width, height) = (current.width(), current.height());
+    let full_region = FrameRegion {
+        x: 0,
+        y: 0,
+        width,
+        height,
+    };
+    let Some(previous) = previous else {
+        return full_region;
+    };
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (width, height, 0, 0);
+
+    for (index, (a, b)) in previous.pixels().iter().zip(current.pixels()).enumerate() {
+        if a != b {
+            let (x, y) = (index as u32 % width, index as u32 / width);
+
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+
+    // Nothing changed, keep a single pixel frame to hold the delay
+    if min_x > max_x {
+        return FrameRegion {
+            width: 1,
+            height: 1,
+            ..full_region
+        };
+    }
+
+    // The offset of WebP frame should be even
+    let (min_x, min_y) = (min_x & !1, min_y & !1);
+
+    FrameRegion {
+        x: min_x,
+        y: min_y,
+        width: max_x - min_x + 1,
+        height: max_y - min_y + 1,
+    }
+}
+
+// The pixels of pixmap are premultiplied, the encoders expect straight RGBA
+fn to_rgba(pixmap: &Pixmap, region: FrameRegion) -> Vec<u8> {
+    let width = pixmap.width();
+    let pixels = pixmap.pixels();
+
+    (region.y..region.y + region.height)
+        .flat_map(|y| {
+            let start = (y * width + region.x) as usize;
+
+            pixels[start..start + region.width as usize].iter()
+        })
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+fn write_webp_chunk(buffer: &mut Vec<u8>, fourcc: &[u8], data: &[u8]) {
+    buffer.extend_from_slice(fourcc);
+    buffer.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(data);
+
+    // Chunks are aligned to 2 bytes
+    if data.len() % 2 == 1 {
+        buffer.push(0);
+    }
+}
+
+fn to_u24(value: u32) -> [u8; 3] {
+    let bytes = value.to_le_bytes();
+
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+impl AnimationSnapshot {
+    pub fn gif_data(&self) -> Result<SnapshotData, anyhow::Error> {
+        self.to_snapshot_data(self.to_gif()?)
+    }
+
+    pub fn apng_data(&self) -> Result<SnapshotData, anyhow::Error> {
+        self.to_snapshot_data(self.to_apng()?)
+    }
+
+    pub fn webp_data(&self) -> Result<SnapshotData, anyhow::Error> {
+        self.to_snapshot_data(self.to_webp()?)
+    }
+
+    fn to_snapshot_data(&self, data: Vec<u8>) -> Result<SnapshotData, anyhow::Error> {
+        let (width, height) = self.size();
+
+        Ok(SnapshotData::Image {
+            data,
+            width: width as usize,
+            height: height as usize,
+        })
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.frames
+            .first()
+            .map(|frame| (frame.width(), frame.height()))
+            .unwrap_or_default()
+    }
+
+    // Iterate the frames with the changed region and the delay
+    fn frames(&self) -> impl Iterator<Item = (&Pixmap, FrameRegion, u16)> {
+        self.frames
+            .iter()
+            .enumerate()
+            .zip(&self.delays)
+            .map(|((index, pixmap), delay)| {
+                let previous = index.checked_sub(1).map(|index| &self.frames[index]);
+
+                (pixmap, changed_region(previous, pixmap), *delay)
+            })
+    }
+}
+
+impl AnimationSnapshot {
+    pub fn to_gif(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let (width, height) = self.size();
+        let width = u16::try_from(width).context("The snapshot is too wide for GIF")?;
+        let height = u16::try_from(height).context("The snapshot is too high for GIF")?;
+        let mut data = vec![];
+        let mut encoder = gif::Encoder::new(&mut data, width, height, &[])?;
+
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        for (pixmap, region, delay) in self.frames() {
+            let mut frame = gif::Frame::from_rgba_speed(
+                region.width as u16,
+                region.height as u16,
+                &mut to_rgba(pixmap, region),
+                GIF_QUANTIZE_SPEED,
+            );
+
+            frame.left = region.x as u16;
+            frame.top = region.y as u16;
+            // The delay of GIF is in units of 10ms
+            frame.delay = delay / 10;
+            encoder.write_frame(&frame)?;
+        }
+
+        drop(encoder);
+
+        Ok(data)
+    }
+
+    pub fn to_apng(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let (width, height) = self.size();
+        let mut data = vec![];
+        let mut encoder = png::Encoder::new(&mut data, width, height);
+
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, 0)?;
+
+        let mut writer = encoder.write_header()?;
+
+        for (pixmap, region, delay) in self.frames() {
+            // Reset the position first, the dimension is checked against the current position
+            writer.set_frame_position(0, 0)?;
+            writer.set_frame_dimension(region.width, region.height)?;
+            writer.set_frame_position(region.x, region.y)?;
+            writer.set_frame_delay(delay, 1000)?;
+            writer.write_image_data(&to_rgba(pixmap, region))?;
+        }
+
+        writer.finish()?;
+
+        Ok(data)
+    }
+
+    // Each frame is encoded as a lossless WebP image, and wrapped by the `ANMF` chunk of the
+    // extended WebP container format
+    pub fn to_webp(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let (width, height) = self.size();
+        let mut chunks = vec![];
+        let mut vp8x = vec![];
+        let mut anim = vec![];
+
+        // The animation flag and the alpha flag
+        vp8x.push(0b0001_0010);
+        vp8x.extend_from_slice(&[0; 3]);
+        vp8x.extend_from_slice(&to_u24(width - 1));
+        vp8x.extend_from_slice(&to_u24(height - 1));
+        write_webp_chunk(&mut chunks, b"VP8X", &vp8x);
+
+        // Transparent background color, and loop forever
+        anim.extend_from_slice(&[0; 4]);
+        anim.extend_from_slice(&0u16.to_le_bytes());
+        write_webp_chunk(&mut chunks, b"ANIM", &anim);
+
+        for (pixmap, region, delay) in self.frames() {
+            let mut image = vec![];
+
+            WebPEncoder::new(&mut image).encode(
+                &to_rgba(pixmap, region),
+                region.width,
+                region.height,
+                ColorType::Rgba8,
+            )?;
+
+            let mut anmf = vec![];
+
+            anmf.extend_from_slice(&to_u24(region.x / 2));
+            anmf.extend_from_slice(&to_u24(region.y / 2));
+            anmf.extend_from_slice(&to_u24(region.width - 1));
+            anmf.extend_from_slice(&to_u24(region.height - 1));
+            anmf.extend_from_slice(&to_u24(delay as u32));
+            anmf.push(WEBP_FRAME_FLAGS);
+            // Skip the RIFF header of the simple WebP file, the rest is the `VP8L` chunk
+            anmf.extend_from_slice(&image[12..]);
+            write_webp_chunk(&mut chunks, b"ANMF", &anmf);
+        }
+
+        let mut data = vec![];
+
+        data.write_all(b"RIFF")?;
+        data.write_all(&(chunks.len() as u32 + 4).to_le_bytes())?;
+        data.write_all(b"WEBP")?;
+        data.write_all(&chunks)?;
+
+        Ok(data)
+    }
+
+    pub fn from_config(config: SnapshotConfig) -> anyhow::Result<Self> {
+        let code = match config.content {
+            Content::Code(ref code) => code.clone(),
+            _ => anyhow::bail!("The animated snapshot only supports code content"),
+        };
+        let theme_provider = ThemeProvider::from_config(&config)?;
+        let syntax_provider = SyntaxProvider::from_config(&config)?;
+        let window_padding = ImageSnapshot::window_padding(&config);
+        let counts = reveal_counts(&prepare_code(&code.content), &config.animation);
+        let context = ImageSnapshot::create_context(&config, &theme_provider);
+        let position = Arc::new(Mutex::new(None));
+
+        // The frames are only drawn on the pixmaps, the scene is not used
+        context.scene.lock().unwrap().pause();
+
+        // The window, line numbers and annotations stay the same while the code is revealing, so
+        // the frame is drawn once without any code, and only the code is drawn on each frame
+        let frame = ImageSnapshot::create_frame(
+            &config,
+            &theme_provider,
+            &window_padding,
+            ImageSnapshot::draw_customized_code_content(
+                &window_padding,
+                code.clone(),
+                &syntax_provider,
+                |code| code.reveal(Some(0)).report_position(position.clone()),
+            )?,
+        )
+        .draw_root(&context)?;
+        let render_params = position
+            .lock()
+            .unwrap()
+            .take()
+            .context("The code is not drawn in the frame")?;
+        let mut code = Code::new(reserve_annotation_rows(code), &syntax_provider)?;
+        let style = code.parsed_style(None, &context);
+        let mut frames = vec![];
+
+        for count in counts {
+            let mut pixmap = frame.clone();
+
+            code = code.reveal(Some(count));
+            code.draw_self(
+                &mut pixmap,
+                &context,
+                &render_params,
+                &style,
+                &ComponentStyle::default(),
+            )?;
+            frames.push(pixmap);
+        }
+
+        let mut delays = vec![config.animation.frame_delay; frames.len()];
+
+        if let Some(last_delay) = delays.last_mut() {
+            *last_delay = config.animation.final_frame_delay;
+        }
+
+        Ok(Self { frames, delays })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tiny_skia::Color;
+
+    use crate::config::AnimationBuilder;
+
+    use super::*;
+
+    fn counts(code: &str, reveal_mode: RevealMode, step: usize) -> Vec<usize> {
+        reveal_counts(
+            code,
+            &AnimationBuilder::default()
+                .reveal_mode(reveal_mode)
+                .step(step)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    // The frames are filled with white, and the pixel of each point is painted black
+    fn snapshot(points: &[Option<(u32, u32)>]) -> AnimationSnapshot {
+        let frames = points
+            .iter()
+            .map(|point| {
+                let mut pixmap = Pixmap::new(5, 3).unwrap();
+
+                pixmap.fill(Color::WHITE);
+
+                if let Some((x, y)) = point {
+                    pixmap.pixels_mut()[(y * 5 + x) as usize] =
+                        Color::BLACK.premultiply().to_color_u8();
+                }
+
+                pixmap
+            })
+            .collect::<Vec<_>>();
+
+        AnimationSnapshot {
+            delays: vec![60; frames.len()],
+            frames,
+        }
+    }
+
+    #[test]
+    fn reveals_chars() {
+        // The whitespace is revealed along with the next char
+        assert_eq!(counts("ab c", RevealMode::Char, 1), [0, 1, 2, 4]);
+        assert_eq!(counts("ab c", RevealMode::Char, 2), [0, 2, 4]);
+        assert_eq!(counts("ab c ", RevealMode::Char, 1), [0, 1, 2, 4, 5]);
+        assert_eq!(counts("a中b", RevealMode::Char, 1), [0, 1, 2, 3]);
+        assert_eq!(counts("", RevealMode::Char, 1), [0]);
+    }
+
+    #[test]
+    fn reveals_lines() {
+        assert_eq!(counts("a\nbc\nd", RevealMode::Line, 1), [0, 1, 4, 6]);
+        assert_eq!(counts("a\nbc\nd", RevealMode::Line, 2), [0, 4, 6]);
+        assert_eq!(counts("a\n", RevealMode::Line, 1), [0, 1, 2]);
+        // The step is at least 1
+        assert_eq!(counts("a\nb", RevealMode::Line, 0), [0, 1, 3]);
+    }
+
+    #[test]
+    fn finds_changed_regions() {
+        let snapshot = snapshot(&[None, Some((3, 1)), Some((3, 1))]);
+        let regions = snapshot
+            .frames()
+            .map(|(_, region, _)| (region.x, region.y, region.width, region.height))
+            .collect::<Vec<_>>();
+
+        // The offset is aligned to even, and the unchanged frame keeps a single pixel
+        assert_eq!(regions, [(0, 0, 5, 3), (2, 0, 2, 2), (0, 0, 1, 1)]);
+        assert_eq!(
+            to_rgba(
+                &snapshot.frames[1],
+                FrameRegion {
+                    x: 2,
+                    y: 1,
+                    width: 2,
+                    height: 1
+                }
+            ),
+            [255, 255, 255, 255, 0, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn writes_webp_chunks() {
+        let mut buffer = vec![];
+
+        write_webp_chunk(&mut buffer, b"ANIM", &[1, 2, 3]);
+        write_webp_chunk(&mut buffer, b"ANMF", &[4, 5]);
+
+        // The odd chunk is padded, but the padding is not counted in the chunk size
+        assert_eq!(
+            buffer,
+            [
+                b"ANIM".as_slice(),
+                &[3, 0, 0, 0, 1, 2, 3, 0],
+                b"ANMF",
+                &[2, 0, 0, 0, 4, 5]
+            ]
+            .concat()
+        );
+        assert_eq!(to_u24(0x0102_0304), [4, 3, 2]);
+    }
+
+    #[test]
+    fn encodes_gif() {
+        let data = snapshot(&[None, Some((3, 1))]).to_gif().unwrap();
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(data.as_slice())
+            .unwrap();
+        let mut frames = vec![];
+
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            frames.push((
+                frame.left,
+                frame.top,
+                frame.width,
+                frame.height,
+                frame.delay,
+            ));
+        }
+
+        assert_eq!((decoder.width(), decoder.height()), (5, 3));
+        assert_eq!(frames, [(0, 0, 5, 3, 6), (2, 0, 2, 2, 6)]);
+    }
+
+    #[test]
+    fn encodes_apng() {
+        let data = snapshot(&[None, Some((3, 1)), None]).to_apng().unwrap();
+        let mut reader = png::Decoder::new(data.as_slice()).read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let mut frames = vec![];
+
+        while let Ok(output) = reader.next_frame(&mut buffer) {
+            let control = reader.info().frame_control().unwrap();
+
+            frames.push((
+                control.x_offset,
+                control.y_offset,
+                output.width,
+                output.height,
+            ));
+        }
+
+        assert_eq!(reader.info().animation_control().unwrap().num_frames, 3);
+        assert_eq!(frames, [(0, 0, 5, 3), (2, 0, 2, 2), (2, 0, 2, 2)]);
+    }
+
+    #[test]
+    fn encodes_webp() {
+        let data = snapshot(&[None, Some((3, 1)), None]).to_webp().unwrap();
+        let mut chunks = vec![];
+        let mut offset = 12;
+
+        assert_eq!(&data[..4], b"RIFF");
+        assert_eq!(&data[8..12], b"WEBP");
+        assert_eq!(
+            u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize,
+            data.len() - 8
+        );
+
+        // The chunks should fill the container exactly
+        while offset < data.len() {
+            let size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().unwrap());
+
+            chunks.push((&data[offset..offset + 4], &data[offset + 8..offset + 14]));
+            offset += 8 + size as usize + size as usize % 2;
+        }
+
+        assert_eq!(offset, data.len());
+        assert_eq!(
+            chunks.iter().map(|(fourcc, _)| *fourcc).collect::<Vec<_>>(),
+            [b"VP8X", b"ANIM", b"ANMF", b"ANMF", b"ANMF"]
+        );
+        // The flags of `VP8X`, and the offset (halved) of each frame
+        assert_eq!(chunks[0].1, [0b0001_0010, 0, 0, 0, 4, 0]);
+        assert_eq!(chunks[2].1, [0, 0, 0, 0, 0, 0]);
+        assert_eq!(chunks[3].1, [1, 0, 0, 0, 0, 0]);
+        assert_eq!(chunks[4].1, [1, 0, 0, 0, 0, 0]);
+    }
+}
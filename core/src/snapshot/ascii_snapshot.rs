@@ -1,96 +1,615 @@
-use std::cmp::max;
+use std::{ops::Range, path::Path};
+
+use cansi::v3::categorise_text;
+use syntect::{easy::HighlightLines, highlighting::Color, util::LinesWithEndings};
 
 use crate::{
-    config::{Code, Content, SnapshotConfig},
-    utils::code::{calc_max_line_number_length, calc_wh, prepare_code},
+    config::{
+        ANSIColorMode, ASCIIFrameStyle, Code, CodeBuilder, CommandLineContent, Content, Diff,
+        DiffConfig, DiffLayout, HighlightLine, LineChange, RedactConfig, SnapshotConfig,
+    },
+    utils::{
+        ansi::{escape_style, foreground, is_color_disabled, paint, parse_hex_color, RESET},
+        code::{calc_display_width, prepare_code},
+        diff::{DiffPane, ParsedDiff},
+        fold::{fold_marker, folds_by_line, original_line_numbers},
+        redact::{redact_text, redaction_ranges, secret_ranges},
+        syntax_provider::SyntaxProvider,
+        theme_provider::ThemeProvider,
+    },
 };
 
 use super::snapshot_data::SnapshotData;
 
 const SPACE_BOTH_SIDE: usize = 2;
 
+// The fence of markdown code block should be longer than any backtick sequence in the code
+const MIN_FENCE_LENGTH: usize = 3;
+
+const HIGHLIGHTED_LINE_MARKER: &str = ">";
+const SPLIT_DIVIDER: &str = "│";
+const MARKDOWN_LANGUAGE: &str = "markdown";
+
 pub struct ASCIISnapshot {
-    code: Code,
+    content: Content,
     has_breadcrumbs: bool,
+    frame_style: ASCIIFrameStyle,
+    title: Option<String>,
+    watermark: Option<String>,
+    prompt: String,
+    diff_config: DiffConfig,
+    redact_config: RedactConfig,
+    // The code is colorized by ANSI escape sequences if it's provided
+    ansi: Option<ANSIColors>,
+}
+
+struct ANSIColors {
+    theme_provider: ThemeProvider,
+    syntax_provider: SyntaxProvider,
+    color_mode: ANSIColorMode,
+    line_number_color: Color,
+    prompt_color: Color,
+    command_color: Color,
+}
+
+enum Row {
+    // The escape sequences of `colorized_text` are invisible, so the width of the row is measured
+    // by the plain `text`
+    Text {
+        text: String,
+        colorized_text: String,
+    },
+    Separator,
+}
+
+impl Row {
+    fn plain(text: String) -> Row {
+        Row::Text {
+            colorized_text: text.clone(),
+            text,
+        }
+    }
+}
+
+struct FrameChars {
+    top_left: &'static str,
+    top_right: &'static str,
+    bottom_left: &'static str,
+    bottom_right: &'static str,
+    horizontal: &'static str,
+    vertical: &'static str,
+}
+
+impl FrameChars {
+    fn from_style(style: ASCIIFrameStyle) -> FrameChars {
+        let (top_left, top_right, bottom_left, bottom_right, horizontal, vertical) = match style {
+            ASCIIFrameStyle::Double => ("╔", "╗", "╚", "╝", "═", "║"),
+            ASCIIFrameStyle::Heavy => ("┏", "┓", "┗", "┛", "━", "┃"),
+            ASCIIFrameStyle::Plain => ("+", "+", "+", "+", "-", "|"),
+            _ => ("╭", "╮", "╰", "╯", "─", "│"),
+        };
+
+        FrameChars {
+            top_left,
+            top_right,
+            bottom_left,
+            bottom_right,
+            horizontal,
+            vertical,
+        }
+    }
 }
 
-fn optional(component: String, is_view: bool) -> String {
-    if is_view {
-        component
-    } else {
-        "".to_string()
+fn strip_ansi(text: &str) -> String {
+    categorise_text(text)
+        .into_iter()
+        .map(|category| category.text)
+        .collect()
+}
+
+// Redact the visible chars of the text which contains ANSI escape sequences, the ranges are the
+// byte ranges of the stripped text, and the escape sequences are kept
+fn redact_ansi(text: &str, ranges: &[Range<usize>]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut raw_offset = 0;
+    let mut offset = 0;
+
+    for category in categorise_text(text) {
+        let ranges = ranges
+            .iter()
+            .map(|range| range.start.saturating_sub(offset)..range.end.saturating_sub(offset))
+            .collect::<Vec<_>>();
+
+        result.push_str(&text[raw_offset..category.start]);
+        result.push_str(&redact_text(category.text, &ranges));
+        raw_offset = category.end;
+        offset += category.text.len();
     }
+
+    result.push_str(&text[raw_offset..]);
+    result
+}
+
+// Place the label in the middle of the border, the label is surrounded by spaces
+fn center_label(label: Option<&String>, fill: &str, width: usize) -> String {
+    let Some(label) = label else {
+        return fill.repeat(width);
+    };
+    let rest = width.saturating_sub(calc_display_width(label) + SPACE_BOTH_SIDE);
+
+    format!(
+        "{} {label} {}",
+        fill.repeat(rest / 2),
+        fill.repeat(rest - rest / 2)
+    )
+}
+
+// The last matched highlight wins, the same as the image snapshot which draws the highlights in
+// order
+fn find_highlight_line(
+    highlight_lines: &[HighlightLine],
+    line_number: u32,
+) -> Option<&HighlightLine> {
+    highlight_lines.iter().rev().find(|highlight_line| {
+        let (start, end) = highlight_line.range();
+
+        (start.min(end)..=start.max(end)).contains(&line_number)
+    })
+}
+
+fn highlight_marker(highlight_line: &HighlightLine) -> &'static str {
+    highlight_line
+        .change()
+        .map(LineChange::marker)
+        .unwrap_or(HIGHLIGHTED_LINE_MARKER)
+}
+
+// The language of the fenced code block, fallback to the extension of the file path
+fn markdown_language(code: &Code) -> String {
+    code.language
+        .clone()
+        .or_else(|| {
+            code.file_path.as_ref().and_then(|file_path| {
+                Path::new(file_path)
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .map(|extension| extension.to_string())
+            })
+        })
+        .unwrap_or_default()
 }
 
 impl ASCIISnapshot {
     pub fn raw_data(&self) -> Result<SnapshotData, anyhow::Error> {
-        Ok(SnapshotData::Text(self.generate_snapshot()))
+        Ok(SnapshotData::Text(self.generate_snapshot()?))
     }
 }
 
 impl ASCIISnapshot {
     pub fn from_config(config: SnapshotConfig) -> anyhow::Result<Self> {
-        match config.content {
-            Content::Code(ref raw_code) => Ok(ASCIISnapshot {
-                code: raw_code.clone(),
-                has_breadcrumbs: config.code_config.breadcrumbs.enable,
+        Ok(ASCIISnapshot {
+            content: config.content,
+            has_breadcrumbs: config.code_config.breadcrumbs.enable,
+            frame_style: config.ascii_config.frame_style,
+            title: config.title.filter(|title| !title.is_empty()),
+            watermark: config
+                .watermark
+                .map(|watermark| watermark.content)
+                .filter(|content| !content.is_empty()),
+            prompt: config.command_output_config.prompt,
+            diff_config: config.diff_config,
+            redact_config: config.redact_config,
+            ansi: None,
+        })
+    }
+
+    pub fn from_ansi_config(config: SnapshotConfig) -> anyhow::Result<Self> {
+        let snapshot = ASCIISnapshot::from_config(config.clone())?;
+
+        if is_color_disabled() || snapshot.frame_style == ASCIIFrameStyle::Markdown {
+            return Ok(snapshot);
+        }
+
+        Ok(ASCIISnapshot {
+            ansi: Some(ANSIColors {
+                theme_provider: ThemeProvider::from_config(&config)?,
+                syntax_provider: SyntaxProvider::from_config(&config)?,
+                color_mode: config.ascii_config.color_mode,
+                line_number_color: parse_hex_color(&config.line_number_color),
+                prompt_color: parse_hex_color(&config.command_output_config.prompt_color),
+                command_color: parse_hex_color(&config.command_output_config.command_color),
             }),
-            _ => Err(anyhow::anyhow!("The code content is not raw")),
+            ..snapshot
+        })
+    }
+
+    fn generate_snapshot(&self) -> anyhow::Result<String> {
+        Ok(match self.frame_style {
+            ASCIIFrameStyle::Markdown => self.generate_markdown_snapshot()?,
+            _ => self.generate_framed_snapshot()?,
+        })
+    }
+
+    // Colorize the text if the snapshot is colored, otherwise return the text as it is
+    fn paint(&self, text: &str, color: impl Fn(&ANSIColors) -> Color) -> String {
+        match self.ansi {
+            Some(ref ansi) => paint(text, &foreground(color(ansi), ansi.color_mode)),
+            None => text.to_string(),
         }
     }
 
-    fn generate_snapshot(&self) -> String {
-        let code = prepare_code(&self.code.content);
-        let (width, height) = calc_wh(&code, 1., 1.);
-        let calc_line_number_width = |start_line_number: u32| {
-            calc_max_line_number_length(height as usize, start_line_number)
-        };
-        let len = self
-            .code
-            .clone()
-            .file_path
-            .and_then(|x| Some(x.len()))
-            .unwrap_or(0);
-        let frame_width = max(width as usize, len + SPACE_BOTH_SIDE);
-        let frame_width = match self.code.start_line_number {
-            Some(start_line_number) => {
-                frame_width + SPACE_BOTH_SIDE + calc_line_number_width(start_line_number)
+    // The file path displayed in the breadcrumbs
+    fn file_path(&self) -> Option<String> {
+        match self.content {
+            Content::Code(ref code) => code.file_path.clone(),
+            Content::CommandOutput(_) => None,
+            Content::Diff(ref diff) => diff
+                .file_path
+                .clone()
+                .or_else(|| ParsedDiff::from(&diff.diff).ok()?.file_path),
+            Content::Markdown(ref markdown) => markdown.file_path.clone(),
+        }
+    }
+
+    fn rows(&self) -> anyhow::Result<Vec<Row>> {
+        match self.content {
+            Content::Code(ref code) => {
+                let lines = prepare_code(&code.content).lines().count();
+                let line_numbers = code.start_line_number.map(|start_line_number| {
+                    let line_numbers = original_line_numbers(start_line_number, lines, &code.folds);
+                    let width = line_numbers
+                        .last()
+                        .map(|line_number| line_number.to_string().len())
+                        .unwrap_or(0);
+
+                    line_numbers
+                        .iter()
+                        .map(|line_number| format!("{:1$}", line_number, width))
+                        .collect()
+                });
+
+                self.code_rows(code, line_numbers)
             }
-            None => frame_width,
+            Content::CommandOutput(ref command_line_content) => {
+                Ok(self.command_output_rows(command_line_content))
+            }
+            Content::Diff(ref diff) => {
+                let parsed_diff = ParsedDiff::from(&diff.diff)?;
+
+                match self.diff_config.layout {
+                    DiffLayout::Unified => self.unified_diff_rows(diff, &parsed_diff),
+                    DiffLayout::Split => self.split_diff_rows(diff, &parsed_diff),
+                }
+            }
+            // The text can't be formatted, the Markdown is displayed as the source
+            Content::Markdown(ref markdown) => {
+                let code = CodeBuilder::default()
+                    .content(markdown.markdown.clone())
+                    .language(MARKDOWN_LANGUAGE)
+                    .build()?;
+
+                self.code_rows(&code, None)
+            }
+        }
+    }
+
+    // The gutter of unified diff has two columns of line numbers
+    fn unified_diff_rows(&self, diff: &Diff, parsed_diff: &ParsedDiff) -> anyhow::Result<Vec<Row>> {
+        let code = parsed_diff.to_code(diff, &self.diff_config);
+
+        self.code_rows(&code, Some(parsed_diff.line_number_columns()))
+    }
+
+    // The rows of old and new panes are joined by the divider, the rows of the old pane are padded
+    // to the same width
+    fn split_diff_rows(&self, diff: &Diff, parsed_diff: &ParsedDiff) -> anyhow::Result<Vec<Row>> {
+        let (old_pane, new_pane) = parsed_diff.to_split_panes(diff, &self.diff_config);
+        let pane_rows =
+            |pane: &DiffPane| self.code_rows(&pane.code, Some(pane.line_number_column()));
+        let mut old_rows = pane_rows(&old_pane)?;
+        let mut new_rows = pane_rows(&new_pane)?;
+        // The trailing filler lines of a pane are empty, they are lost when the code is split into
+        // lines, so the shorter pane is padded to keep the rows of both panes
+        let row_count = old_rows.len().max(new_rows.len());
+
+        old_rows.resize_with(row_count, || Row::plain(String::new()));
+        new_rows.resize_with(row_count, || Row::plain(String::new()));
+        let row_width = |row: &Row| match row {
+            Row::Text { text, .. } => calc_display_width(text),
+            Row::Separator => 0,
+        };
+        let old_width = old_rows.iter().map(row_width).max().unwrap_or(0);
+        let divider = self.paint(SPLIT_DIVIDER, |ansi| ansi.line_number_color);
+
+        Ok(old_rows
+            .into_iter()
+            .zip(new_rows)
+            .map(|rows| match rows {
+                (
+                    Row::Text {
+                        text: old_text,
+                        colorized_text: old_colorized_text,
+                    },
+                    Row::Text {
+                        text: new_text,
+                        colorized_text: new_colorized_text,
+                    },
+                ) => {
+                    let padding = " ".repeat(old_width - calc_display_width(&old_text));
+
+                    Row::Text {
+                        text: format!("{old_text}{padding} {SPLIT_DIVIDER} {new_text}"),
+                        colorized_text: format!(
+                            "{old_colorized_text}{padding} {divider} {new_colorized_text}"
+                        ),
+                    }
+                }
+                _ => Row::Separator,
+            })
+            .collect())
+    }
+
+    // Highlight the code by the same syntax and theme as the image snapshot, each line is
+    // colorized by ANSI escape sequences
+    fn colorize_code(
+        &self,
+        code: &Code,
+        content: &str,
+        ansi: &ANSIColors,
+    ) -> anyhow::Result<Vec<String>> {
+        let syntax_provider = &ansi.syntax_provider;
+        let syntax =
+            syntax_provider.guess_syntax(code.language.clone(), code.file_path.clone(), content)?;
+        let mut highlight_lines = HighlightLines::new(&syntax, &ansi.theme_provider.theme);
+
+        LinesWithEndings::from(content)
+            .map(|line| {
+                Ok(highlight_lines
+                    .highlight_line(line, &syntax_provider.syntax_set)?
+                    .into_iter()
+                    .map(|(style, text)| {
+                        paint(
+                            text.trim_end_matches('\n'),
+                            &escape_style(style, ansi.color_mode),
+                        )
+                    })
+                    .collect::<String>())
+            })
+            .collect()
+    }
+
+    // The gutter contains the markers of highlighted lines and the line numbers, returns the
+    // plain gutter and the colorized gutter
+    fn gutter(&self, code: &Code, index: usize, line_number: Option<&str>) -> (String, String) {
+        let mut text = String::new();
+        let mut colorized_text = String::new();
+
+        if !code.highlight_lines.is_empty() {
+            let highlight_line = find_highlight_line(&code.highlight_lines, index as u32 + 1);
+            let marker = highlight_line.map(highlight_marker).unwrap_or(" ");
+            let colorized_marker = match highlight_line {
+                Some(highlight_line) => {
+                    self.paint(marker, |_| parse_hex_color(highlight_line.color()))
+                }
+                None => marker.to_string(),
+            };
+
+            text.push_str(&format!("{marker} "));
+            colorized_text.push_str(&format!("{colorized_marker} "));
+        }
+
+        if let Some(line_number) = line_number {
+            colorized_text.push_str(&format!(
+                "{} ",
+                self.paint(line_number, |ansi| ansi.line_number_color)
+            ));
+            text.push_str(&format!("{line_number} "));
+        }
+
+        (text, colorized_text)
+    }
+
+    // The line numbers are formatted by the caller, since the diff has two columns of line numbers
+    fn code_rows(
+        &self,
+        code: &Code,
+        line_numbers: Option<Vec<String>>,
+    ) -> anyhow::Result<Vec<Row>> {
+        // The redacted chars are replaced before colorizing, so that the secrets are never printed
+        let content = redact_text(
+            &prepare_code(&code.content),
+            &redaction_ranges(code, &self.redact_config.detectors)?,
+        );
+        let lines = content.lines().collect::<Vec<&str>>();
+        let colorized_lines = match self.ansi {
+            Some(ref ansi) => self.colorize_code(code, &content, ansi)?,
+            None => lines.iter().map(|line| line.to_string()).collect(),
         };
-        let frame_width_with_space = frame_width + SPACE_BOTH_SIDE;
-        let line = "─".repeat(frame_width_with_space);
-        let breadcurmbs_line = format!("│{}│\n", line);
-        let frame_width_with_content = frame_width;
-        let top_frame = format!("╭{}╮\n", line);
-        let bottom_frame = format!("╰{}╯", line);
-        let code = code
-            .lines()
+        let folds = folds_by_line(lines.len(), &code.folds);
+
+        Ok(lines
+            .iter()
+            .zip(colorized_lines)
             .enumerate()
-            .map(|(i, line)| {
-                format!(
-                    "│ {:1$} │\n",
-                    match self.code.start_line_number {
-                        Some(ref start_line_number) => format!(
-                            "{:1$} {line}",
-                            *start_line_number as usize + i,
-                            calc_line_number_width(*start_line_number),
+            .flat_map(|(index, (line, colorized_line))| {
+                let line_number = line_numbers
+                    .as_ref()
+                    .and_then(|line_numbers| line_numbers.get(index))
+                    .map(String::as_str);
+                let (gutter, colorized_gutter) = self.gutter(code, index, line_number);
+                // The fold markers are aligned with the code, and have no marker and line number
+                let indent = " ".repeat(calc_display_width(&gutter));
+                let fold_rows = folds[index].iter().map(|fold| {
+                    let marker = fold_marker(fold);
+
+                    Row::Text {
+                        text: format!("{indent}{marker}"),
+                        colorized_text: format!(
+                            "{indent}{}",
+                            self.paint(&marker, |ansi| ansi.line_number_color)
                         ),
-                        None => line.to_string(),
-                    },
-                    frame_width_with_content
-                )
+                    }
+                });
+
+                fold_rows
+                    .chain([Row::Text {
+                        text: format!("{gutter}{line}"),
+                        colorized_text: format!("{colorized_gutter}{colorized_line}"),
+                    }])
+                    .collect::<Vec<Row>>()
+            })
+            .collect())
+    }
+
+    // Each command is displayed with a prompt line, and followed by the output of the command
+    fn command_output_rows(&self, command_line_content: &[CommandLineContent]) -> Vec<Row> {
+        command_line_content
+            .iter()
+            .enumerate()
+            .flat_map(|(index, command_line)| {
+                let detectors = &self.redact_config.detectors;
+                let separator = (index > 0).then(|| Row::plain(String::new()));
+                let full_command = redact_text(
+                    &command_line.full_command,
+                    &secret_ranges(&command_line.full_command, detectors),
+                );
+                let prompt_line = Row::Text {
+                    text: format!("{} {}", self.prompt, full_command),
+                    colorized_text: format!(
+                        "{} {}",
+                        self.paint(&self.prompt, |ansi| ansi.prompt_color),
+                        self.paint(&full_command, |ansi| ansi.command_color)
+                    ),
+                };
+                // The output of the command may contain ANSI escape sequences, they are kept in
+                // the colored snapshot, and removed in the plain snapshot
+                let output_lines = command_line.content.lines().map(|line| {
+                    let text = strip_ansi(line);
+                    let ranges = secret_ranges(&text, detectors);
+
+                    match self.ansi {
+                        Some(_) => Row::Text {
+                            text: redact_text(&text, &ranges),
+                            colorized_text: format!("{}{RESET}", redact_ansi(line, &ranges)),
+                        },
+                        None => Row::plain(redact_text(&text, &ranges)),
+                    }
+                });
+
+                separator
+                    .into_iter()
+                    .chain([prompt_line])
+                    .chain(output_lines)
+                    .collect::<Vec<Row>>()
             })
+            .collect()
+    }
+
+    fn generate_markdown_snapshot(&self) -> anyhow::Result<String> {
+        let (language, file_path) = match self.content {
+            Content::Code(ref code) => (markdown_language(code), self.file_path()),
+            Content::CommandOutput(_) => (String::from("console"), None),
+            Content::Diff(_) => (String::from("diff"), self.file_path()),
+            Content::Markdown(_) => (String::from(MARKDOWN_LANGUAGE), self.file_path()),
+        };
+        // The colors are never used in markdown, see `from_ansi_config`
+        let lines = self
+            .rows()?
+            .into_iter()
+            .filter_map(|row| match row {
+                Row::Text { text, .. } => Some(text),
+                Row::Separator => None,
+            })
+            .collect::<Vec<String>>();
+        let longest_backticks = lines
+            .iter()
+            .flat_map(|line| line.split(|char| char != '`'))
+            .map(|backticks| backticks.len())
+            .max()
+            .unwrap_or(0);
+        let fence = "`".repeat(MIN_FENCE_LENGTH.max(longest_backticks + 1));
+        let code = lines
+            .iter()
+            .map(|line| format!("{line}\n"))
             .collect::<String>();
-        let text_line = |text: &str| format!("│ {:1$} │\n", text, frame_width_with_content);
-        let breadcrumbs = optional(
-            format!(
-                "{}{breadcurmbs_line}",
-                text_line(&self.code.file_path.clone().unwrap_or(String::from("")))
-            ),
-            self.has_breadcrumbs,
+        let mut sections = vec![];
+
+        if let Some(ref title) = self.title {
+            sections.push(format!("**{title}**"));
+        }
+
+        if let Some(file_path) = file_path.filter(|_| self.has_breadcrumbs) {
+            sections.push(format!("`{file_path}`"));
+        }
+
+        sections.push(format!("{fence}{language}\n{code}{fence}"));
+
+        if let Some(ref watermark) = self.watermark {
+            sections.push(format!("_{watermark}_"));
+        }
+
+        Ok(sections.join("\n\n"))
+    }
+
+    fn generate_framed_snapshot(&self) -> anyhow::Result<String> {
+        let frame_chars = FrameChars::from_style(self.frame_style);
+        let (horizontal, vertical) = (frame_chars.horizontal, frame_chars.vertical);
+        let mut rows = vec![];
+
+        if self.has_breadcrumbs && !matches!(self.content, Content::CommandOutput(_)) {
+            rows.push(Row::plain(self.file_path().unwrap_or_default()));
+            rows.push(Row::Separator);
+        }
+
+        rows.extend(self.rows()?);
+
+        let content_width = rows
+            .iter()
+            .map(|row| match row {
+                Row::Text { text, .. } => calc_display_width(text),
+                Row::Separator => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        // Keep at least one horizontal char on each side of the title and the watermark
+        let label_width = |label: &Option<String>| {
+            label
+                .as_ref()
+                .map(|label| calc_display_width(label))
+                .unwrap_or(0)
+        };
+        let frame_width = (content_width + SPACE_BOTH_SIDE)
+            .max(label_width(&self.title) + SPACE_BOTH_SIDE * 2)
+            .max(label_width(&self.watermark) + SPACE_BOTH_SIDE * 2);
+        let content_width = frame_width - SPACE_BOTH_SIDE;
+        let top_frame = format!(
+            "{}{}{}\n",
+            frame_chars.top_left,
+            center_label(self.title.as_ref(), horizontal, frame_width),
+            frame_chars.top_right
         );
+        let bottom_frame = format!(
+            "{}{}{}",
+            frame_chars.bottom_left,
+            center_label(self.watermark.as_ref(), horizontal, frame_width),
+            frame_chars.bottom_right
+        );
+        let body = rows
+            .iter()
+            .map(|row| match row {
+                Row::Text {
+                    text,
+                    colorized_text,
+                } => format!(
+                    "{vertical} {colorized_text}{} {vertical}\n",
+                    " ".repeat(content_width - calc_display_width(text))
+                ),
+                Row::Separator => {
+                    format!("{vertical}{}{vertical}\n", horizontal.repeat(frame_width))
+                }
+            })
+            .collect::<String>();
 
-        format!("{top_frame}{breadcrumbs}{code}{bottom_frame}")
+        Ok(format!("{top_frame}{body}{bottom_frame}"))
     }
 }
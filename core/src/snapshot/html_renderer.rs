@@ -0,0 +1,357 @@
+use std::{collections::HashMap, fmt::Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use cosmic_text::{fontdb, ttf_parser};
+use tiny_skia::Color;
+
+use crate::components::interface::scene::{Scene, SceneElement, SceneFont, SceneGlyph, ScenePaint};
+
+use super::{pdf_renderer::extract_face, svg_renderer::escape_xml};
+
+// The ascent and descent (in em) used when the metrics of the font is not available
+const DEFAULT_FONT_METRICS: (f32, f32) = (0.8, 0.2);
+
+const STYLE: &str = ".codesnap{position:relative;overflow:hidden}.codesnap div{position:absolute}.codesnap .line{white-space:pre;line-height:1}";
+
+pub struct HTMLRenderer<'a> {
+    scene: &'a Scene,
+    self_contained: bool,
+    body: String,
+    // The fonts used by the text, the value is the class name of the font
+    fonts: HashMap<fontdb::ID, (String, &'a SceneFont)>,
+    font_metrics: HashMap<fontdb::ID, (f32, f32)>,
+}
+
+fn format_color(color: &Color) -> String {
+    let color_u8 = color.to_color_u8();
+
+    if color.is_opaque() {
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            color_u8.red(),
+            color_u8.green(),
+            color_u8.blue()
+        )
+    } else {
+        format!(
+            "rgba({},{},{},{})",
+            color_u8.red(),
+            color_u8.green(),
+            color_u8.blue(),
+            round(color.alpha())
+        )
+    }
+}
+
+fn round(value: f32) -> f32 {
+    (value * 100.).round() / 100.
+}
+
+fn format_box(x: f32, y: f32, width: f32, height: f32) -> String {
+    format!(
+        "left:{}px;top:{}px;width:{}px;height:{}px",
+        round(x),
+        round(y),
+        round(width),
+        round(height)
+    )
+}
+
+// Read the ascent and descent of the font in em, the browser places the baseline by these
+// metrics, so they are used to line up the HTML text with the baseline of the snapshot
+fn read_font_metrics(font: &SceneFont) -> Option<(f32, f32)> {
+    let data = font.font.as_ref()?.data();
+    let face = ttf_parser::Face::parse(data, font.index).ok()?;
+    let units_per_em = face.units_per_em() as f32;
+
+    Some((
+        face.ascender() as f32 / units_per_em,
+        -face.descender() as f32 / units_per_em,
+    ))
+}
+
+// Glyphs are drawn in the same span if they have the same font and color
+fn is_same_span(a: &SceneGlyph, b: &SceneGlyph) -> bool {
+    a.font.id == b.font.id && a.color == b.color && a.oblique == b.oblique
+}
+
+impl<'a> HTMLRenderer<'a> {
+    pub fn new(scene: &'a Scene) -> HTMLRenderer<'a> {
+        HTMLRenderer {
+            scene,
+            self_contained: false,
+            body: String::new(),
+            fonts: HashMap::new(),
+            font_metrics: HashMap::new(),
+        }
+    }
+
+    // Inline the fonts as base64 `@font-face`, so that the HTML looks the same on the machine
+    // which doesn't install the fonts
+    pub fn self_contained(mut self, self_contained: bool) -> Self {
+        self.self_contained = self_contained;
+        self
+    }
+
+    pub fn render(mut self) -> String {
+        let scene = self.scene;
+
+        for element in &scene.elements {
+            self.render_element(element);
+        }
+
+        let mut fonts = self.fonts.values().collect::<Vec<_>>();
+
+        fonts.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let font_styles = fonts
+            .iter()
+            .map(|(class_name, font)| self.render_font_style(class_name, font))
+            .collect::<String>();
+
+        format!(
+            r#"<!DOCTYPE html><html><head><meta charset="utf-8"><style>{}{}</style></head><body><div class="codesnap" style="width:{}px;height:{}px">{}</div></body></html>"#,
+            STYLE,
+            font_styles,
+            round(self.scene.width),
+            round(self.scene.height),
+            self.body
+        )
+    }
+
+    fn render_font_style(&self, class_name: &str, font: &SceneFont) -> String {
+        let family = format!("'{}'", font.family.replace('\'', "\\'"));
+        let font_style = if font.italic { "italic" } else { "normal" };
+        let font_face = match font.font.as_ref() {
+            Some(font_data) if self.self_contained => extract_face(font_data.data(), font.index)
+                .map(|data| {
+                    format!(
+                        "@font-face{{font-family:{};src:url(data:font/ttf;base64,{});font-weight:{};font-style:{}}}",
+                        family,
+                        STANDARD.encode(data),
+                        font.weight,
+                        font_style
+                    )
+                })
+                .unwrap_or_default(),
+            _ => String::new(),
+        };
+
+        format!(
+            ".codesnap .{}{{font-family:{},monospace;font-weight:{};font-style:{}}}{}",
+            class_name, family, font.weight, font_style, font_face
+        )
+    }
+
+    fn render_element(&mut self, element: &'a SceneElement) {
+        match element {
+            SceneElement::Background {
+                width,
+                height,
+                paint,
+            } => {
+                let _ = write!(
+                    self.body,
+                    r#"<div style="{};background:{}"></div>"#,
+                    format_box(0., 0., *width, *height),
+                    self.render_paint(*width, *height, paint)
+                );
+            }
+            SceneElement::Rect {
+                x,
+                y,
+                width,
+                height,
+                radius,
+                color,
+            } => {
+                let _ = write!(
+                    self.body,
+                    r#"<div style="{};border-radius:{}px;background:{}"></div>"#,
+                    format_box(*x, *y, *width, *height),
+                    round(*radius),
+                    format_color(color)
+                );
+            }
+            SceneElement::StrokeRect {
+                x,
+                y,
+                width,
+                height,
+                radius,
+                stroke_width,
+                color,
+            } => {
+                // The border of CSS is drawn inside the box, so the box is expanded by half of
+                // the stroke width
+                let _ = write!(
+                    self.body,
+                    r#"<div style="{};box-sizing:border-box;border-radius:{}px;border:{}px solid {}"></div>"#,
+                    format_box(
+                        x - stroke_width / 2.,
+                        y - stroke_width / 2.,
+                        width + stroke_width,
+                        height + stroke_width
+                    ),
+                    round(radius + stroke_width / 2.),
+                    round(*stroke_width),
+                    format_color(color)
+                );
+            }
+            SceneElement::Shadow {
+                x,
+                y,
+                width,
+                height,
+                blur,
+                color,
+            } => {
+                let _ = write!(
+                    self.body,
+                    r#"<div style="{};background:{};filter:blur({}px)"></div>"#,
+                    format_box(*x, *y, *width, *height),
+                    format_color(color),
+                    round(*blur)
+                );
+            }
+            SceneElement::Circle {
+                cx,
+                cy,
+                radius,
+                color,
+            } => {
+                let _ = write!(
+                    self.body,
+                    r#"<div style="{};border-radius:50%;background:{}"></div>"#,
+                    format_box(cx - radius, cy - radius, radius * 2., radius * 2.),
+                    format_color(color)
+                );
+            }
+            SceneElement::Text(glyphs) => self.render_text(glyphs),
+        }
+    }
+
+    // CSS linear-gradient is described by an angle, and the gradient line always passes through
+    // the center of the box, so the stops are projected onto the gradient line of CSS
+    fn render_paint(&self, width: f32, height: f32, paint: &ScenePaint) -> String {
+        let (start, end, stops) = match paint {
+            ScenePaint::Solid(color) => return format_color(color),
+            ScenePaint::LinearGradient { start, end, stops } => (start, end, stops),
+        };
+        let (dx, dy) = (end.x - start.x, end.y - start.y);
+        let length = (dx * dx + dy * dy).sqrt();
+
+        if length == 0. {
+            return stops
+                .first()
+                .map(|(_, color)| format_color(color))
+                .unwrap_or("transparent".to_string());
+        }
+
+        let (ux, uy) = (dx / length, dy / length);
+        let angle = ux.atan2(-uy);
+        let line_length = (width * ux).abs() + (height * uy).abs();
+        let stops = stops
+            .iter()
+            .map(|(position, color)| {
+                let x = start.x + dx * position - width / 2.;
+                let y = start.y + dy * position - height / 2.;
+
+                format!(
+                    "{} {}%",
+                    format_color(color),
+                    round(((x * ux + y * uy) / line_length + 0.5) * 100.)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        format!(
+            "linear-gradient({}deg,{})",
+            round(angle.to_degrees()),
+            stops
+        )
+    }
+
+    fn font_class_name(&mut self, font: &'a SceneFont) -> String {
+        let next_class_name = format!("f{}", self.fonts.len() + 1);
+
+        self.fonts
+            .entry(font.id)
+            .or_insert((next_class_name, font))
+            .0
+            .clone()
+    }
+
+    fn font_metrics(&mut self, font: &SceneFont) -> (f32, f32) {
+        *self
+            .font_metrics
+            .entry(font.id)
+            .or_insert_with(|| read_font_metrics(font).unwrap_or(DEFAULT_FONT_METRICS))
+    }
+
+    fn render_text(&mut self, glyphs: &'a [SceneGlyph]) {
+        let mut lines: Vec<Vec<&SceneGlyph>> = vec![];
+
+        for glyph in glyphs {
+            match lines.last_mut() {
+                Some(line) if line[0].y == glyph.y => line.push(glyph),
+                _ => lines.push(vec![glyph]),
+            }
+        }
+
+        for line in lines {
+            if line.iter().all(|glyph| glyph.text.trim().is_empty()) {
+                continue;
+            }
+
+            let first_glyph = line[0];
+            let font_size = first_glyph.font_size;
+            let (ascent, descent) = self.font_metrics(&first_glyph.font);
+            // The line height equals to the font size, the baseline is placed at the half-leading
+            // plus the ascent from the top of the line box
+            let top = first_glyph.y - font_size * (1. + ascent - descent) / 2.;
+            let mut spans: Vec<Vec<&SceneGlyph>> = vec![];
+
+            for glyph in line {
+                match spans.last_mut() {
+                    Some(span) if is_same_span(span[0], glyph) => span.push(glyph),
+                    _ => spans.push(vec![glyph]),
+                }
+            }
+
+            let spans = spans
+                .iter()
+                .map(|span| {
+                    let text = span
+                        .iter()
+                        .map(|glyph| glyph.text.as_str())
+                        .collect::<String>();
+
+                    // The browser slants the upright face when the italic face is missing
+                    let font_style = match span[0].oblique {
+                        true => ";font-style:italic",
+                        false => "",
+                    };
+
+                    format!(
+                        r#"<span class="{}" style="color:{}{}">{}</span>"#,
+                        self.font_class_name(&span[0].font),
+                        format_color(&span[0].color),
+                        font_style,
+                        escape_xml(&text)
+                    )
+                })
+                .collect::<String>();
+
+            let _ = write!(
+                self.body,
+                r#"<div class="line" style="left:{}px;top:{}px;font-size:{}px">{}</div>"#,
+                round(first_glyph.x),
+                round(top),
+                round(font_size),
+                spans
+            );
+        }
+    }
+}
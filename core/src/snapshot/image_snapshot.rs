@@ -8,37 +8,66 @@ use crate::{
         command_line::{
             command_line_header::CommandLineHeader, command_line_output::CommandLineOutput,
         },
-        interface::{component::Component, style::Style},
+        interface::{component::Component, scene::Scene, style::Style},
         layout::{column::Column, row::Row},
+        markdown::{
+            code_fence::CodeFence,
+            prose::{Prose, ProseKind},
+        },
+    },
+    config::{
+        self, CodeBuilder, CommandLineContent, DiffLayout, SnapshotConfig, DEFAULT_WINDOW_MARGIN,
+    },
+    utils::{
+        annotation::reserve_annotation_rows,
+        color::RgbaColor,
+        diff::{align_wrapped_rows, ParsedDiff},
+        markdown::{parse_markdown, MarkdownBlock},
+        syntax_provider::SyntaxProvider,
+        text::FontRenderer,
+        theme_provider::ThemeProvider,
     },
-    config::{self, CommandLineContent, SnapshotConfig, DEFAULT_WINDOW_MARGIN},
-    utils::{color::RgbaColor, text::FontRenderer, theme_provider::ThemeProvider},
 };
 use tiny_skia::{Color, Pixmap};
 
 use crate::{
     components::{
+        annotations::Annotations,
         background::Background,
         breadcrumbs::Breadcrumbs,
         code_block::CodeBlock,
         container::Container,
         editor::{code::Code, mac_title_bar::MacTitleBar, title::Title},
-        highlight_code_block::HighlightCodeBlock,
+        highlight_code_block::{HighlightCodeBlock, HighlightExtent},
         interface::component::ComponentContext,
         line_number::LineNumber,
         rect::Rect,
+        split_pane::SplitPane,
         watermark::Watermark,
     },
     edges::padding::Padding,
 };
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 
-use super::snapshot_data::SnapshotData;
+use super::{
+    html_renderer::HTMLRenderer,
+    pdf_renderer::PDFRenderer,
+    snapshot_data::{ImageFormat, SnapshotData},
+    svg_renderer::SVGRenderer,
+    terminal_renderer::{TerminalProtocol, TerminalRenderer},
+};
 
 const DEFAULT_WINDOW_MIN_WIDTH: f32 = 350.;
+// The space between the blocks of Markdown, relative to the font size of prose
+const MARKDOWN_BLOCK_SPACING_SCALE: f32 = 0.8;
+
+// The drawer draws the render content inside the snapshot frame
+pub type Drawer = Box<dyn Fn(Vec<Box<dyn Component>>) -> anyhow::Result<(Pixmap, Scene)>>;
 
 pub struct ImageSnapshot {
     pixmap: Pixmap,
+    scene: Scene,
+    encoding: config::Encoding,
 }
 
 impl ImageSnapshot {
@@ -50,6 +79,27 @@ impl ImageSnapshot {
         Ok(SnapshotData::from_pixmap(&self.pixmap, true)?)
     }
 
+    pub fn jpeg_data(&self) -> Result<SnapshotData, anyhow::Error> {
+        self.image_data(ImageFormat::Jpeg, &self.encoding)
+    }
+
+    pub fn webp_data(&self) -> Result<SnapshotData, anyhow::Error> {
+        self.image_data(ImageFormat::WebP, &self.encoding)
+    }
+
+    pub fn avif_data(&self) -> Result<SnapshotData, anyhow::Error> {
+        self.image_data(ImageFormat::Avif, &self.encoding)
+    }
+
+    // Encode the snapshot with the given encoding options instead of the options from config
+    pub fn image_data(
+        &self,
+        format: ImageFormat,
+        encoding: &config::Encoding,
+    ) -> Result<SnapshotData, anyhow::Error> {
+        SnapshotData::from_pixmap_with_encoding(&self.pixmap, format, encoding)
+    }
+
     pub fn svg_data(&self) -> Result<SnapshotData, anyhow::Error> {
         Ok(SnapshotData::Text(self.to_svg()?))
     }
@@ -57,14 +107,31 @@ impl ImageSnapshot {
     pub fn html_data(&self) -> Result<SnapshotData, anyhow::Error> {
         Ok(SnapshotData::Text(self.to_html()?))
     }
+
+    pub fn self_contained_html_data(&self) -> Result<SnapshotData, anyhow::Error> {
+        Ok(SnapshotData::Text(self.to_self_contained_html()?))
+    }
+
+    pub fn pdf_data(&self) -> Result<SnapshotData, anyhow::Error> {
+        Ok(SnapshotData::Binary(self.to_pdf()?))
+    }
 }
 
 impl ImageSnapshot {
+    /// Render the snapshot as HTML, the window, background and decorations are built with
+    /// CSS, and the code is rendered as `<span>`s colored by the theme, so that it can be
+    /// selected and copied from the page.
+    ///
+    /// The fonts are referenced by family name, use `to_self_contained_html` if the HTML
+    /// should look the same on the machine which doesn't install the fonts.
     pub fn to_html(&self) -> Result<String, anyhow::Error> {
-        Ok(format!(
-            r#"<img src="data:image/png;base64,{}" />"#,
-            self.to_base64()?
-        ))
+        Ok(HTMLRenderer::new(&self.scene).render())
+    }
+
+    /// Same as `to_html`, but the fonts are inlined as base64 `@font-face`, so the HTML is a
+    /// single portable file.
+    pub fn to_self_contained_html(&self) -> Result<String, anyhow::Error> {
+        Ok(HTMLRenderer::new(&self.scene).self_contained(true).render())
     }
 
     pub fn to_base64(&self) -> Result<String, anyhow::Error> {
@@ -73,115 +140,336 @@ impl ImageSnapshot {
         Ok(STANDARD.encode(png_data))
     }
 
-    /// CodeSnap use tiny_skia to generate the image snapshot, and the format of generated image
-    /// is PNG, if you want a SVG code snapshot, you can use this method to convert the PNG to SVG
+    /// Render the snapshot as a real vector SVG, the SVG is replayed from the same layout pass as
+    /// the PNG, so every shape and glyph is placed at the same position as the PNG.
     ///
-    /// WARNING: This method is not really convert the PNG to SVG, it encode PNG to Base64 and
-    /// format it to SVG, so the SVG file is still a image file, not a real SVG file. Base64
-    /// usually takes about 33% more space than the original data, so the SVG file size might be larger.
+    /// The code is rendered as `<text>` elements, so it stays sharp when zoomed, and it can be
+    /// searched and selected. The fonts are referenced by family name, make sure the fonts are
+    /// available where the SVG is displayed.
     pub fn to_svg(&self) -> Result<String, anyhow::Error> {
-        let parsed_svg_content = format!(
-            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"><image href="data:image/png;base64,{}"/></svg>"#,
-            self.to_base64()?.as_str()
-        );
+        Ok(SVGRenderer::new(&self.scene).render())
+    }
 
-        Ok(parsed_svg_content)
+    /// Render the snapshot as a single page PDF, the page is replayed from the same layout pass
+    /// as the PNG.
+    ///
+    /// The code is written as real text with the fonts embedded, so it can be selected and
+    /// searched in any PDF viewer without depending on the fonts installed on the system.
+    pub fn to_pdf(&self) -> Result<Vec<u8>, anyhow::Error> {
+        PDFRenderer::new(&self.scene).render()
+    }
+
+    /// Render the snapshot as escape sequences of the terminal image protocol, print the result
+    /// to display the snapshot in the terminal directly, it's useful on the machine without
+    /// display server (such as SSH sessions), where the snapshot cannot be copied to clipboard.
+    ///
+    /// Use `TerminalProtocol::detect` to detect the protocol supported by current terminal.
+    pub fn to_terminal(&self, protocol: TerminalProtocol) -> Result<String, anyhow::Error> {
+        TerminalRenderer::new(&self.pixmap).render(protocol)
+    }
+
+    pub fn create_context(
+        config: &SnapshotConfig,
+        theme_provider: &ThemeProvider,
+    ) -> ComponentContext {
+        // The style parse process is recursive, there may some components style to be reculculated
+        // many times, so we cache the style to avoid reculculate
+        // The key is the component name, which defined in the Component trait
+        let style_map: Mutex<HashMap<String, Style<f32>>> = Mutex::new(HashMap::new());
+        let font_renderer = Mutex::new(FontRenderer::new(
+            config.scale_factor as f32,
+            config.fonts_folders.clone(),
+        ));
+
+        ComponentContext {
+            scale_factor: config.scale_factor as f32,
+            take_snapshot_params: Arc::new(config.clone()),
+            theme_provider: theme_provider.clone(),
+            font_renderer,
+            style_map,
+            scene: Mutex::new(Scene::default()),
+        }
+    }
+
+    // The frame of snapshot, includes the background, the window with title bar, and the
+    // watermark, the render content is drawn inside the window
+    pub fn create_frame(
+        config: &SnapshotConfig,
+        theme_provider: &ThemeProvider,
+        window_padding: &Padding,
+        render_content: Vec<Box<dyn Component>>,
+    ) -> Container {
+        let editor_background_color = theme_provider.theme_background();
+        let background_padding = Padding::from(config.window.margin.clone());
+        let border_rgba_color: RgbaColor = config.window.border.color.as_str().into();
+
+        // If vertical background padding is less than 82., should hidden watermark component
+        // If watermark text is equal to "", the watermark component is hidden
+        let watermark = if background_padding.bottom >= DEFAULT_WINDOW_MARGIN {
+            config.watermark.clone()
+        } else {
+            None
+        };
+
+        let mut parsed_render_content: Vec<Box<dyn Component>> =
+            vec![Box::new(Row::from_children(vec![
+                Box::new(MacTitleBar::new(config.window.mac_window_bar)),
+                Box::new(Title::from_content(config.title.clone())),
+            ]))];
+
+        parsed_render_content.extend(render_content);
+
+        let shadow_color: RgbaColor = config.window.shadow.color.as_str().into();
+
+        Container::from_children(vec![Box::new(Background::new(
+            background_padding,
+            vec![
+                Box::new(
+                    Rect::create_with_border(
+                        12.,
+                        editor_background_color.into(),
+                        DEFAULT_WINDOW_MIN_WIDTH,
+                        window_padding.clone(),
+                        config.window.border.width,
+                        border_rgba_color.into(),
+                        parsed_render_content,
+                    )
+                    .shadow(
+                        0.,
+                        21.,
+                        config.window.shadow.radius,
+                        Color::from(shadow_color),
+                    ),
+                ),
+                Box::new(Watermark::new(watermark)),
+            ],
+        ))])
     }
 
     pub fn create_drawer_with_frame(
         config: SnapshotConfig,
         theme_provider: ThemeProvider,
         window_padding: Padding,
-    ) -> Box<dyn Fn(Vec<Box<dyn Component>>) -> anyhow::Result<Pixmap>> {
+    ) -> Drawer {
         Box::new(move |render_content| {
-            // The style parse process is recursive, there may some components style to be reculculated
-            // many times, so we cache the style to avoid reculculate
-            // The key is the component name, which defined in the Component trait
-            let style_map: Mutex<HashMap<&'static str, Style<f32>>> = Mutex::new(HashMap::new());
-            let editor_background_color = theme_provider.theme_background();
-            let font_renderer = Mutex::new(FontRenderer::new(
-                config.scale_factor as f32,
-                config.fonts_folders.clone(),
-            ));
-            let context = ComponentContext {
-                scale_factor: config.scale_factor as f32,
-                take_snapshot_params: Arc::new(config.clone()),
-                theme_provider: theme_provider.clone(),
-                font_renderer,
-                style_map,
-            };
-            let background_padding = Padding::from(config.window.margin.clone());
-            let border_rgba_color: RgbaColor = config.window.border.color.as_str().into();
-
-            // If vertical background padding is less than 82., should hidden watermark component
-            // If watermark text is equal to "", the watermark component is hidden
-            let watermark = if background_padding.bottom >= DEFAULT_WINDOW_MARGIN {
-                config.watermark.clone()
-            } else {
-                None
-            };
-
-            let mut parsed_render_content: Vec<Box<dyn Component>> =
-                vec![Box::new(Row::from_children(vec![
-                    Box::new(MacTitleBar::new(config.window.mac_window_bar)),
-                    Box::new(Title::from_content(config.title.clone())),
-                ]))];
-
-            parsed_render_content.extend(render_content);
-
-            let shadow_color: RgbaColor = config.window.shadow.color.as_str().into();
+            let context = Self::create_context(&config, &theme_provider);
 
             // Draw the image snapshot frame template
-            let pixmap = Container::from_children(vec![Box::new(Background::new(
-                background_padding,
-                vec![
-                    Box::new(
-                        Rect::create_with_border(
-                            12.,
-                            editor_background_color.into(),
-                            DEFAULT_WINDOW_MIN_WIDTH,
-                            window_padding.clone(),
-                            config.window.border.width,
-                            border_rgba_color.into(),
-                            parsed_render_content,
-                        )
-                        .shadow(
-                            0.,
-                            21.,
-                            config.window.shadow.radius,
-                            Color::from(shadow_color),
-                        ),
-                    ),
-                    Box::new(Watermark::new(watermark)),
-                ],
-            ))])
-            .draw_root(&context)?;
+            let pixmap =
+                Self::create_frame(&config, &theme_provider, &window_padding, render_content)
+                    .draw_root(&context)?;
 
-            Ok(pixmap)
+            Ok((pixmap, context.scene.into_inner().unwrap()))
         })
     }
 
     pub fn draw_code_content(
         window_padding: &Padding,
         code_content: config::Code,
+        syntax_provider: &SyntaxProvider,
+    ) -> anyhow::Result<Vec<Box<dyn Component>>> {
+        Self::draw_customized_code_content(window_padding, code_content, syntax_provider, |code| {
+            code
+        })
+    }
+
+    // Same as `draw_code_content`, but the code component is customized, such as the code of
+    // animation, which is revealed progressively
+    pub fn draw_customized_code_content(
+        window_padding: &Padding,
+        code_content: config::Code,
+        syntax_provider: &SyntaxProvider,
+        customize: impl FnOnce(Code) -> Code,
     ) -> anyhow::Result<Vec<Box<dyn Component>>> {
-        let code_lines = code_content.content.lines().collect::<Vec<&str>>();
+        let code_content = reserve_annotation_rows(code_content);
+        let code: Box<dyn Component> =
+            Box::new(customize(Code::new(code_content.clone(), syntax_provider)?));
+        let code: Box<dyn Component> = match code_content.annotations.is_empty() {
+            true => code,
+            false => Box::new(Annotations::new(&code_content, code)?),
+        };
         let view: Vec<Box<dyn Component>> = vec![
             Box::new(Breadcrumbs::from(code_content.file_path.clone())),
             Box::new(CodeBlock::from_children(vec![
                 Box::new(HighlightCodeBlock::from(
-                    code_content.highlight_lines.clone(),
-                    code_lines.len(),
+                    &code_content,
                     window_padding.clone(),
                 )),
                 Box::new(LineNumber::new(code_content.clone())),
-                Box::new(Code::new(code_content.clone())?),
+                code,
+            ])),
+        ];
+
+        Ok(view)
+    }
+
+    // The diff is drawn as code, but the gutter has the old and new line numbers, and the markers
+    // of changed lines
+    pub fn draw_diff_content(
+        window_padding: &Padding,
+        diff: config::Diff,
+        config: &SnapshotConfig,
+        syntax_provider: &SyntaxProvider,
+    ) -> anyhow::Result<Vec<Box<dyn Component>>> {
+        let parsed_diff = ParsedDiff::from(&diff.diff)?;
+
+        if config.diff_config.layout == DiffLayout::Split {
+            return Self::draw_split_diff_content(
+                window_padding,
+                &diff,
+                &parsed_diff,
+                config,
+                syntax_provider,
+            );
+        }
+
+        let code_content = parsed_diff.to_code(&diff, &config.diff_config);
+        let view: Vec<Box<dyn Component>> = vec![
+            Box::new(Breadcrumbs::from(code_content.file_path.clone())),
+            Box::new(CodeBlock::from_children(vec![
+                Box::new(HighlightCodeBlock::from(
+                    &code_content,
+                    window_padding.clone(),
+                )),
+                Box::new(LineNumber::from_diff(&parsed_diff, &code_content)),
+                Box::new(Code::new(code_content.clone(), syntax_provider)?),
             ])),
         ];
 
         Ok(view)
     }
 
+    // The old and new versions are drawn in two panes side by side, each pane is drawn as code
+    // with its own line numbers, the components of each pane are named separately, since the
+    // styles of components are cached by names
+    fn draw_split_diff_content(
+        window_padding: &Padding,
+        diff: &config::Diff,
+        parsed_diff: &ParsedDiff,
+        config: &SnapshotConfig,
+        syntax_provider: &SyntaxProvider,
+    ) -> anyhow::Result<Vec<Box<dyn Component>>> {
+        let diff_config = &config.diff_config;
+        let (mut old_pane, mut new_pane) = parsed_diff.to_split_panes(diff, diff_config);
+
+        // The panes are aligned before the drawing context is created, so the wrapped rows are
+        // measured by a separate font renderer
+        let mut font_renderer =
+            FontRenderer::new(config.scale_factor as f32, config.fonts_folders.clone());
+
+        align_wrapped_rows(
+            (&mut old_pane, &mut new_pane),
+            &config.code_config,
+            &mut font_renderer,
+        );
+
+        let view: Vec<Box<dyn Component>> = vec![
+            Box::new(Breadcrumbs::from(old_pane.code.file_path.clone())),
+            Box::new(Row::from_children(vec![
+                Box::new(SplitPane::leading(
+                    "OldDiffPane",
+                    vec![
+                        Box::new(
+                            HighlightCodeBlock::from(&old_pane.code, window_padding.clone())
+                                .extent(HighlightExtent::LeadingPane("OldDiffPane"))
+                                .named("OldDiffHighlightCodeBlock"),
+                        ),
+                        Box::new(LineNumber::from_diff_pane(&old_pane).named("OldDiffLineNumber")),
+                        Box::new(
+                            Code::new(old_pane.code.clone(), syntax_provider)?
+                                .emphasize(old_pane.emphasis, &diff_config.deleted_emphasis_color)
+                                .named("OldDiffCode"),
+                        ),
+                    ],
+                )),
+                Box::new(SplitPane::trailing(
+                    "NewDiffPane",
+                    vec![
+                        Box::new(
+                            HighlightCodeBlock::from(&new_pane.code, window_padding.clone())
+                                .extent(HighlightExtent::TrailingPane("OldDiffPane"))
+                                .named("NewDiffHighlightCodeBlock"),
+                        ),
+                        Box::new(LineNumber::from_diff_pane(&new_pane).named("NewDiffLineNumber")),
+                        Box::new(
+                            Code::new(new_pane.code.clone(), syntax_provider)?
+                                .emphasize(new_pane.emphasis, &diff_config.added_emphasis_color)
+                                .named("NewDiffCode"),
+                        ),
+                    ],
+                )),
+            ])),
+        ];
+
+        Ok(view)
+    }
+
+    // The prose of Markdown is drawn block by block, and each fenced code block is drawn as code
+    // which is highlighted by its own language
+    pub fn draw_markdown_content(
+        window_padding: &Padding,
+        markdown: config::Markdown,
+        config: &SnapshotConfig,
+        syntax_provider: &SyntaxProvider,
+    ) -> anyhow::Result<Vec<Box<dyn Component>>> {
+        let blocks = parse_markdown(&markdown.markdown);
+        let block_spacing = config.markdown_config.font_size * MARKDOWN_BLOCK_SPACING_SCALE;
+        let mut children: Vec<Box<dyn Component>> = vec![];
+
+        for (index, block) in blocks.iter().enumerate() {
+            // The items of a list are not separated
+            let spacing = match (block, blocks.get(index + 1)) {
+                (_, None) => 0.,
+                (MarkdownBlock::ListItem { .. }, Some(MarkdownBlock::ListItem { .. })) => 0.,
+                _ => block_spacing,
+            };
+            let child: Box<dyn Component> = match block.clone() {
+                MarkdownBlock::Heading { level, spans } => {
+                    Box::new(Prose::new(ProseKind::Heading(level), spans).spacing(spacing))
+                }
+                MarkdownBlock::Paragraph(spans) => {
+                    Box::new(Prose::new(ProseKind::Paragraph, spans).spacing(spacing))
+                }
+                MarkdownBlock::ListItem {
+                    depth,
+                    marker,
+                    spans,
+                } => Box::new(
+                    Prose::new(ProseKind::ListItem { depth, marker }, spans).spacing(spacing),
+                ),
+                MarkdownBlock::CodeBlock { language, code } => {
+                    let mut code_content = CodeBuilder::default().content(code).build()?;
+
+                    code_content.language = language;
+
+                    // The styles of components are cached by names, each fenced code block is
+                    // named by its index, so that it's cached separately. The padded block is
+                    // wrapped by a column, since the padding of a child is counted again by the
+                    // position of its next sibling
+                    Box::new(Column::from_children(vec![Box::new(
+                        CodeFence::new(
+                            Box::new(
+                                Code::new(code_content, syntax_provider)?
+                                    .named(format!("MarkdownCode{}", index)),
+                            ),
+                            window_padding.clone(),
+                        )
+                        .spacing(spacing),
+                    )]))
+                }
+            };
+
+            children.push(child);
+        }
+
+        let view: Vec<Box<dyn Component>> = vec![
+            Box::new(Breadcrumbs::from(markdown.file_path.clone())),
+            Box::new(Column::from_children(children)),
+        ];
+
+        Ok(view)
+    }
+
     pub fn command_line_content(
         command_line_content: Vec<CommandLineContent>,
     ) -> Vec<Box<dyn Component>> {
@@ -197,31 +485,54 @@ impl ImageSnapshot {
             .collect::<Vec<Box<dyn Component>>>()
     }
 
-    pub fn from_config(config: SnapshotConfig) -> anyhow::Result<Self> {
-        let theme_provider = ThemeProvider::from_config(&config)?;
-        let window_padding = Padding {
+    pub fn window_padding(config: &SnapshotConfig) -> Padding {
+        Padding {
             top: if config.window.mac_window_bar {
                 14.
             } else {
                 12.
             },
             ..Padding::from_value(14.)
-        };
+        }
+    }
+
+    pub fn from_config(config: SnapshotConfig) -> anyhow::Result<Self> {
+        let theme_provider = ThemeProvider::from_config(&config)?;
+        let syntax_provider = SyntaxProvider::from_config(&config)?;
+        let window_padding = Self::window_padding(&config);
 
         let drawer = Self::create_drawer_with_frame(
             config.clone(),
             theme_provider.clone(),
             window_padding.clone(),
         );
-        let pixmap = match config.content {
-            crate::config::Content::Code(code) => {
-                drawer(Self::draw_code_content(&window_padding, code)?)
-            }
+        let (pixmap, scene) = match config.content.clone() {
+            crate::config::Content::Code(code) => drawer(Self::draw_code_content(
+                &window_padding,
+                code,
+                &syntax_provider,
+            )?),
             crate::config::Content::CommandOutput(command_line_content) => {
                 drawer(Self::command_line_content(command_line_content))
             }
+            crate::config::Content::Diff(diff) => drawer(Self::draw_diff_content(
+                &window_padding,
+                diff,
+                &config,
+                &syntax_provider,
+            )?),
+            crate::config::Content::Markdown(markdown) => drawer(Self::draw_markdown_content(
+                &window_padding,
+                markdown,
+                &config,
+                &syntax_provider,
+            )?),
         }?;
 
-        Ok(Self { pixmap })
+        Ok(Self {
+            pixmap,
+            scene,
+            encoding: config.encoding,
+        })
     }
 }
@@ -0,0 +1,780 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    io::Write as _,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use cosmic_text::{fontdb, ttf_parser};
+use flate2::{write::ZlibEncoder, Compression};
+use rgb::FromSlice;
+use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+use crate::{
+    components::interface::scene::{Scene, SceneElement, SceneFont, SceneGlyph, ScenePaint},
+    utils::blur::{apply, ImageRefMut},
+};
+
+// The control point factor to approximate a quarter circle with a cubic bezier curve
+const KAPPA: f32 = 0.552_284_8;
+
+// PDF has no blur operator, so the shadow is rasterized as an image, the image should be large
+// enough to contain the whole blurred shadow
+const SHADOW_EXTENT: f32 = 3.;
+
+// The PDF spec limits the number of entries in a single `beginbfchar` block to 100
+const BF_CHAR_CHUNK_SIZE: usize = 100;
+
+// The skew of the slanted glyphs, which is tan(14°), the same angle as the raster glyphs are
+// slanted by cosmic-text
+const OBLIQUE_SKEW: f32 = 0.249;
+
+// A minimal PDF object writer, the objects are written in order, and the offsets of the objects
+// are recorded to generate the cross-reference table
+struct PDFDocument {
+    buffer: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl PDFDocument {
+    fn new() -> PDFDocument {
+        PDFDocument {
+            buffer: b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n".to_vec(),
+            offsets: vec![],
+        }
+    }
+
+    // Reserve an object id, the object can be written later
+    fn reserve(&mut self) -> usize {
+        self.offsets.push(0);
+        self.offsets.len()
+    }
+
+    fn write_object(&mut self, id: usize, content: &[u8]) {
+        self.offsets[id - 1] = self.buffer.len();
+        self.buffer
+            .extend_from_slice(format!("{} 0 obj\n", id).as_bytes());
+        self.buffer.extend_from_slice(content);
+        self.buffer.extend_from_slice(b"\nendobj\n");
+    }
+
+    fn write_dict(&mut self, id: usize, dict: &str) {
+        self.write_object(id, format!("<< {} >>", dict).as_bytes());
+    }
+
+    fn write_stream(&mut self, id: usize, dict: &str, data: &[u8]) -> anyhow::Result<()> {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+
+        encoder.write_all(data)?;
+
+        let data = encoder.finish()?;
+        let mut content = format!(
+            "<< {} /Filter /FlateDecode /Length {} >>\nstream\n",
+            dict,
+            data.len()
+        )
+        .into_bytes();
+
+        content.extend_from_slice(&data);
+        content.extend_from_slice(b"\nendstream");
+        self.write_object(id, &content);
+
+        Ok(())
+    }
+
+    fn finish(mut self, root: usize) -> Vec<u8> {
+        let xref_offset = self.buffer.len();
+        let size = self.offsets.len() + 1;
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", size);
+
+        for offset in &self.offsets {
+            let _ = writeln!(xref, "{:010} 00000 n ", offset);
+        }
+
+        let _ = write!(
+            xref,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            size, root, xref_offset
+        );
+        self.buffer.extend_from_slice(xref.as_bytes());
+        self.buffer
+    }
+}
+
+struct PDFFont {
+    name: String,
+    font: Arc<SceneFont>,
+    // The glyph id maps to the advance width (in 1/1000 em) and the original text of the glyph
+    glyphs: BTreeMap<u16, (f32, String)>,
+}
+
+pub struct PDFRenderer<'a> {
+    scene: &'a Scene,
+    document: PDFDocument,
+    content: String,
+    fonts: HashMap<fontdb::ID, PDFFont>,
+    // The key is the alpha value multiplied by 1000
+    graphics_states: BTreeMap<u32, String>,
+    shadings: Vec<(String, usize)>,
+    images: Vec<(String, usize)>,
+}
+
+// PDF does not support scientific notation, and the precision of 3 decimal places is enough
+fn num(value: f32) -> String {
+    let formatted = format!("{:.3}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+
+    match trimmed {
+        "-0" | "" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+fn format_rgb(color: &Color) -> String {
+    format!(
+        "{} {} {}",
+        num(color.red()),
+        num(color.green()),
+        num(color.blue())
+    )
+}
+
+// The name object of PDF only allows regular characters
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .filter(|char| char.is_ascii_alphanumeric() || *char == '-' || *char == '_')
+        .collect()
+}
+
+fn rounded_rect_path(x: f32, y: f32, w: f32, h: f32, radius: f32) -> String {
+    let r = radius.min(w / 2.).min(h / 2.).max(0.);
+
+    if r == 0. {
+        return format!("{} {} {} {} re\n", num(x), num(y), num(w), num(h));
+    }
+
+    let k = r * KAPPA;
+    let points = [
+        format!("{} {} m", num(x + r), num(y)),
+        format!("{} {} l", num(x + w - r), num(y)),
+        format!(
+            "{} {} {} {} {} {} c",
+            num(x + w - r + k),
+            num(y),
+            num(x + w),
+            num(y + r - k),
+            num(x + w),
+            num(y + r)
+        ),
+        format!("{} {} l", num(x + w), num(y + h - r)),
+        format!(
+            "{} {} {} {} {} {} c",
+            num(x + w),
+            num(y + h - r + k),
+            num(x + w - r + k),
+            num(y + h),
+            num(x + w - r),
+            num(y + h)
+        ),
+        format!("{} {} l", num(x + r), num(y + h)),
+        format!(
+            "{} {} {} {} {} {} c",
+            num(x + r - k),
+            num(y + h),
+            num(x),
+            num(y + h - r + k),
+            num(x),
+            num(y + h - r)
+        ),
+        format!("{} {} l", num(x), num(y + r)),
+        format!(
+            "{} {} {} {} {} {} c",
+            num(x),
+            num(y + r - k),
+            num(x + r - k),
+            num(y),
+            num(x + r),
+            num(y)
+        ),
+        "h".to_string(),
+    ];
+
+    format!("{}\n", points.join("\n"))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+// PDF (and web fonts) can only embed a single font face, if the font data is a font collection
+// (TTC), the face should be extracted as a standalone font by rebuilding the table directory
+pub fn extract_face(data: &[u8], index: u32) -> Option<Vec<u8>> {
+    if data.get(0..4)? != b"ttcf" {
+        return Some(data.to_vec());
+    }
+
+    let face_offset = read_u32(data, 12 + 4 * index as usize)? as usize;
+    let num_tables = read_u16(data, face_offset + 4)? as usize;
+    let records_offset = face_offset + 12;
+    let mut table_offset = 12 + num_tables * 16;
+    let mut header = data.get(face_offset..records_offset)?.to_vec();
+    let mut tables = vec![];
+
+    for table_index in 0..num_tables {
+        let record_offset = records_offset + table_index * 16;
+        let record = data.get(record_offset..record_offset + 8)?;
+        let offset = read_u32(data, record_offset + 8)? as usize;
+        let length = read_u32(data, record_offset + 12)? as usize;
+        let mut table = data.get(offset..offset + length)?.to_vec();
+
+        header.extend_from_slice(record);
+        header.extend_from_slice(&(table_offset as u32).to_be_bytes());
+        header.extend_from_slice(&(length as u32).to_be_bytes());
+
+        // Tables are aligned to 4 bytes
+        table.resize(length.div_ceil(4) * 4, 0);
+        table_offset += table.len();
+        tables.extend(table);
+    }
+
+    header.extend(tables);
+
+    Some(header)
+}
+
+fn to_unicode_cmap(glyphs: &BTreeMap<u16, (f32, String)>) -> String {
+    let chars = glyphs
+        .iter()
+        .filter(|(_, (_, text))| !text.is_empty())
+        .map(|(glyph_id, (_, text))| {
+            let unicode = text
+                .encode_utf16()
+                .map(|unit| format!("{:04X}", unit))
+                .collect::<String>();
+
+            format!("<{:04X}> <{}>", glyph_id, unicode)
+        })
+        .collect::<Vec<String>>();
+    let bf_chars = chars
+        .chunks(BF_CHAR_CHUNK_SIZE)
+        .map(|chunk| {
+            format!(
+                "{} beginbfchar\n{}\nendbfchar\n",
+                chunk.len(),
+                chunk.join("\n")
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n\
+         {}endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend",
+        bf_chars
+    )
+}
+
+impl<'a> PDFRenderer<'a> {
+    pub fn new(scene: &'a Scene) -> PDFRenderer<'a> {
+        PDFRenderer {
+            scene,
+            document: PDFDocument::new(),
+            content: String::new(),
+            fonts: HashMap::new(),
+            graphics_states: BTreeMap::new(),
+            shadings: vec![],
+            images: vec![],
+        }
+    }
+
+    pub fn render(mut self) -> anyhow::Result<Vec<u8>> {
+        let catalog_id = self.document.reserve();
+        let pages_id = self.document.reserve();
+        let page_id = self.document.reserve();
+        let content_id = self.document.reserve();
+
+        for element in &self.scene.elements {
+            self.render_element(element)?;
+        }
+
+        let mut fonts = self.fonts.drain().map(|(_, font)| font).collect::<Vec<_>>();
+
+        fonts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let font_resources = fonts
+            .iter()
+            .map(|font| Ok(format!("/{} {} 0 R", font.name, self.write_font(font)?)))
+            .collect::<anyhow::Result<Vec<String>>>()?
+            .join(" ");
+        let graphics_state_resources = self
+            .graphics_states
+            .iter()
+            .map(|(alpha, name)| {
+                format!(
+                    "/{} << /Type /ExtGState /ca {} /CA {} >>",
+                    name,
+                    num(*alpha as f32 / 1000.),
+                    num(*alpha as f32 / 1000.)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        let format_resources = |resources: &Vec<(String, usize)>| {
+            resources
+                .iter()
+                .map(|(name, id)| format!("/{} {} 0 R", name, id))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+        let width = num(self.scene.width);
+        let height = num(self.scene.height);
+        // PDF origin is at the bottom left corner, flip the y axis to make the coordinates of the
+        // scene can be used directly
+        let content = format!("1 0 0 -1 0 {} cm\n{}", height, self.content);
+
+        self.document
+            .write_stream(content_id, "", content.as_bytes())?;
+        self.document.write_dict(
+            page_id,
+            &format!(
+                "/Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << /Font << {} >> /ExtGState << {} >> /Shading << {} >> /XObject << {} >> >> /Contents {} 0 R",
+                pages_id,
+                width,
+                height,
+                font_resources,
+                graphics_state_resources,
+                format_resources(&self.shadings),
+                format_resources(&self.images),
+                content_id
+            ),
+        );
+        self.document.write_dict(
+            pages_id,
+            &format!("/Type /Pages /Kids [{} 0 R] /Count 1", page_id),
+        );
+        self.document.write_dict(
+            catalog_id,
+            &format!("/Type /Catalog /Pages {} 0 R", pages_id),
+        );
+
+        Ok(self.document.finish(catalog_id))
+    }
+
+    fn render_element(&mut self, element: &SceneElement) -> anyhow::Result<()> {
+        match element {
+            SceneElement::Background {
+                width,
+                height,
+                paint,
+            } => self.render_background(*width, *height, paint),
+            SceneElement::Rect {
+                x,
+                y,
+                width,
+                height,
+                radius,
+                color,
+            } => {
+                self.fill_path(&rounded_rect_path(*x, *y, *width, *height, *radius), color);
+
+                Ok(())
+            }
+            SceneElement::StrokeRect {
+                x,
+                y,
+                width,
+                height,
+                radius,
+                stroke_width,
+                color,
+            } => {
+                self.stroke_path(
+                    &rounded_rect_path(*x, *y, *width, *height, *radius),
+                    *stroke_width,
+                    color,
+                );
+
+                Ok(())
+            }
+            SceneElement::Circle {
+                cx,
+                cy,
+                radius,
+                color,
+            } => {
+                self.fill_path(
+                    &rounded_rect_path(cx - radius, cy - radius, radius * 2., radius * 2., *radius),
+                    color,
+                );
+
+                Ok(())
+            }
+            SceneElement::Shadow {
+                x,
+                y,
+                width,
+                height,
+                blur,
+                color,
+            } => self.render_shadow(*x, *y, *width, *height, *blur, color),
+            SceneElement::Text(glyphs) => {
+                self.render_text(glyphs);
+
+                Ok(())
+            }
+        }
+    }
+
+    // The graphics state is used to set the alpha of the fill color
+    fn graphics_state(&mut self, alpha: f32) -> String {
+        let key = (alpha * 1000.).round() as u32;
+        let next_name = format!("GS{}", self.graphics_states.len() + 1);
+
+        self.graphics_states.entry(key).or_insert(next_name).clone()
+    }
+
+    fn set_fill_color(&mut self, color: &Color) {
+        if !color.is_opaque() {
+            let graphics_state = self.graphics_state(color.alpha());
+
+            let _ = writeln!(self.content, "/{} gs", graphics_state);
+        }
+
+        let _ = writeln!(self.content, "{} rg", format_rgb(color));
+    }
+
+    fn fill_path(&mut self, path: &str, color: &Color) {
+        self.content.push_str("q\n");
+        self.set_fill_color(color);
+        self.content.push_str(path);
+        self.content.push_str("f\nQ\n");
+    }
+
+    fn stroke_path(&mut self, path: &str, width: f32, color: &Color) {
+        self.content.push_str("q\n");
+
+        if !color.is_opaque() {
+            let graphics_state = self.graphics_state(color.alpha());
+
+            let _ = writeln!(self.content, "/{} gs", graphics_state);
+        }
+
+        let _ = writeln!(self.content, "{} RG\n{} w", format_rgb(color), num(width));
+        self.content.push_str(path);
+        self.content.push_str("S\nQ\n");
+    }
+
+    fn render_background(
+        &mut self,
+        width: f32,
+        height: f32,
+        paint: &ScenePaint,
+    ) -> anyhow::Result<()> {
+        let (start, end, stops) = match paint {
+            ScenePaint::Solid(color) => {
+                self.fill_path(&rounded_rect_path(0., 0., width, height, 0.), color);
+
+                return Ok(());
+            }
+            ScenePaint::LinearGradient { start, end, stops } => (start, end, stops),
+        };
+        let mut stops = stops.clone();
+
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Make sure the gradient function covers the whole domain
+        if let (Some(first), Some(last)) = (stops.first().cloned(), stops.last().cloned()) {
+            if first.0 > 0. {
+                stops.insert(0, (0., first.1));
+            }
+
+            if last.0 < 1. {
+                stops.push((1., last.1));
+            }
+        }
+
+        let functions = stops
+            .windows(2)
+            .map(|pair| {
+                format!(
+                    "<< /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >>",
+                    format_rgb(&pair[0].1),
+                    format_rgb(&pair[1].1)
+                )
+            })
+            .collect::<Vec<String>>();
+        let bounds = stops[1..stops.len().saturating_sub(1)]
+            .iter()
+            .map(|(position, _)| num(*position))
+            .collect::<Vec<String>>();
+        let encode = vec!["0 1"; functions.len()];
+        let function = format!(
+            "<< /FunctionType 3 /Domain [0 1] /Functions [{}] /Bounds [{}] /Encode [{}] >>",
+            functions.join(" "),
+            bounds.join(" "),
+            encode.join(" ")
+        );
+        let shading_id = self.document.reserve();
+        let shading_name = format!("Sh{}", self.shadings.len() + 1);
+
+        self.document.write_dict(
+            shading_id,
+            &format!(
+                "/ShadingType 2 /ColorSpace /DeviceRGB /Coords [{} {} {} {}] /Function {} /Extend [true true]",
+                num(start.x),
+                num(start.y),
+                num(end.x),
+                num(end.y),
+                function
+            ),
+        );
+        let _ = write!(
+            self.content,
+            "q\n{}W n\n/{} sh\nQ\n",
+            rounded_rect_path(0., 0., width, height, 0.),
+            shading_name
+        );
+        self.shadings.push((shading_name, shading_id));
+
+        Ok(())
+    }
+
+    fn render_shadow(
+        &mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        blur: f32,
+        color: &Color,
+    ) -> anyhow::Result<()> {
+        let scale_factor = self.scene.scale_factor;
+        let extent = blur * SHADOW_EXTENT;
+        let image_width = ((width + extent * 2.) * scale_factor).ceil() as u32;
+        let image_height = ((height + extent * 2.) * scale_factor).ceil() as u32;
+        let mut pixmap =
+            Pixmap::new(image_width, image_height).context("Cannot create shadow pixmap")?;
+        let mut paint = Paint::default();
+
+        paint.set_color(Color::BLACK);
+        pixmap.fill_rect(
+            Rect::from_xywh(extent, extent, width, height).context("Invalid shadow size")?,
+            &paint,
+            Transform::from_scale(scale_factor, scale_factor),
+            None,
+        );
+        apply(
+            (blur * scale_factor) as f64,
+            (blur * scale_factor) as f64,
+            ImageRefMut::new(image_width, image_height, pixmap.data_mut().as_rgba_mut()),
+        );
+
+        // The color of shadow is solid, the blurred alpha is stored in the soft mask
+        let color_u8 = color.to_color_u8();
+        let rgb = [color_u8.red(), color_u8.green(), color_u8.blue()]
+            .repeat((image_width * image_height) as usize);
+        let alpha = pixmap
+            .pixels()
+            .iter()
+            .map(|pixel| (pixel.alpha() as f32 * color.alpha()).round() as u8)
+            .collect::<Vec<u8>>();
+        let mask_id = self.document.reserve();
+        let image_id = self.document.reserve();
+        let image_name = format!("Im{}", self.images.len() + 1);
+        let image_dict = format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} /BitsPerComponent 8",
+            image_width, image_height
+        );
+
+        self.document.write_stream(
+            mask_id,
+            &format!("{} /ColorSpace /DeviceGray", image_dict),
+            &alpha,
+        )?;
+        self.document.write_stream(
+            image_id,
+            &format!(
+                "{} /ColorSpace /DeviceRGB /SMask {} 0 R",
+                image_dict, mask_id
+            ),
+            &rgb,
+        )?;
+
+        let image_x = x - extent;
+        let image_y = y - extent;
+        let image_w = image_width as f32 / scale_factor;
+        let image_h = image_height as f32 / scale_factor;
+
+        // The image is drawn in the unit square, and the y axis is flipped, so the image should be
+        // flipped back
+        let _ = write!(
+            self.content,
+            "q\n{} 0 0 {} {} {} cm\n/{} Do\nQ\n",
+            num(image_w),
+            num(-image_h),
+            num(image_x),
+            num(image_y + image_h),
+            image_name
+        );
+        self.images.push((image_name, image_id));
+
+        Ok(())
+    }
+
+    fn render_text(&mut self, glyphs: &[SceneGlyph]) {
+        let mut current_font = None;
+        let mut current_color = None;
+
+        self.content.push_str("q\nBT\n");
+
+        for glyph in glyphs {
+            // The font can't be embedded, skip the glyph
+            if glyph.font.font.is_none() {
+                continue;
+            }
+
+            let next_name = format!("F{}", self.fonts.len() + 1);
+            let font = self.fonts.entry(glyph.font.id).or_insert_with(|| PDFFont {
+                name: next_name,
+                font: glyph.font.clone(),
+                glyphs: BTreeMap::new(),
+            });
+            let advance = if glyph.font_size > 0. {
+                glyph.width / glyph.font_size * 1000.
+            } else {
+                0.
+            };
+
+            font.glyphs
+                .entry(glyph.glyph_id)
+                .or_insert((advance, glyph.text.clone()));
+
+            let font_state = (font.name.clone(), num(glyph.font_size));
+
+            if current_font.as_ref() != Some(&font_state) {
+                let _ = writeln!(self.content, "/{} {} Tf", font_state.0, font_state.1);
+                current_font = Some(font_state);
+            }
+
+            if current_color != Some(glyph.color) {
+                self.set_fill_color(&glyph.color);
+                current_color = Some(glyph.color);
+            }
+
+            let skew = match glyph.oblique {
+                true => OBLIQUE_SKEW,
+                false => 0.,
+            };
+
+            let _ = writeln!(
+                self.content,
+                "1 0 {} -1 {} {} Tm\n<{:04X}> Tj",
+                num(skew),
+                num(glyph.x),
+                num(glyph.y),
+                glyph.glyph_id
+            );
+        }
+
+        self.content.push_str("ET\nQ\n");
+    }
+
+    fn write_font(&mut self, pdf_font: &PDFFont) -> anyhow::Result<usize> {
+        let scene_font = &pdf_font.font;
+        let font = scene_font
+            .font
+            .as_ref()
+            .context(format!("Cannot load font {}", scene_font.family))?;
+        let data = extract_face(font.data(), scene_font.index)
+            .context(format!("Cannot read font {}", scene_font.family))?;
+        let face = ttf_parser::Face::parse(&data, 0)
+            .map_err(|_| anyhow::anyhow!("Cannot parse font {}", scene_font.family))?;
+        let units_per_em = face.units_per_em() as f32;
+        let to_pdf_unit = |value: f32| num(value * 1000. / units_per_em);
+        let bbox = face.global_bounding_box();
+        let is_cff = face.tables().cff.is_some();
+        let base_font = match sanitize_name(&scene_font.post_script_name) {
+            name if name.is_empty() => pdf_font.name.clone(),
+            name => name,
+        };
+        // Symbolic, and the glyphs are addressed by glyph id
+        let flags =
+            4 | if face.is_monospaced() { 1 } else { 0 } | if scene_font.italic { 64 } else { 0 };
+        let widths = pdf_font
+            .glyphs
+            .iter()
+            .map(|(glyph_id, (advance, _))| format!("{} [{}]", glyph_id, num(*advance)))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let font_id = self.document.reserve();
+        let cid_font_id = self.document.reserve();
+        let descriptor_id = self.document.reserve();
+        let font_file_id = self.document.reserve();
+        let to_unicode_id = self.document.reserve();
+
+        self.document.write_dict(
+            font_id,
+            &format!(
+                "/Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H /DescendantFonts [{} 0 R] /ToUnicode {} 0 R",
+                base_font, cid_font_id, to_unicode_id
+            ),
+        );
+        self.document.write_dict(
+            cid_font_id,
+            &format!(
+                "/Type /Font /Subtype /{} /BaseFont /{} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> /FontDescriptor {} 0 R /W [{}]{}",
+                if is_cff { "CIDFontType0" } else { "CIDFontType2" },
+                base_font,
+                descriptor_id,
+                widths,
+                if is_cff { "" } else { " /CIDToGIDMap /Identity" }
+            ),
+        );
+        self.document.write_dict(
+            descriptor_id,
+            &format!(
+                "/Type /FontDescriptor /FontName /{} /Flags {} /FontBBox [{} {} {} {}] /ItalicAngle {} /Ascent {} /Descent {} /CapHeight {} /StemV 80 /{} {} 0 R",
+                base_font,
+                flags,
+                to_pdf_unit(bbox.x_min as f32),
+                to_pdf_unit(bbox.y_min as f32),
+                to_pdf_unit(bbox.x_max as f32),
+                to_pdf_unit(bbox.y_max as f32),
+                num(face.italic_angle().unwrap_or(0.)),
+                to_pdf_unit(face.ascender() as f32),
+                to_pdf_unit(face.descender() as f32),
+                to_pdf_unit(face.capital_height().unwrap_or(face.ascender()) as f32),
+                if is_cff { "FontFile3" } else { "FontFile2" },
+                font_file_id
+            ),
+        );
+        self.document.write_stream(
+            font_file_id,
+            &if is_cff {
+                "/Subtype /OpenType".to_string()
+            } else {
+                format!("/Length1 {}", data.len())
+            },
+            &data,
+        )?;
+        self.document.write_stream(
+            to_unicode_id,
+            "",
+            to_unicode_cmap(&pdf_font.glyphs).as_bytes(),
+        )?;
+
+        Ok(font_id)
+    }
+}
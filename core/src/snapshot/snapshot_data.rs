@@ -1,11 +1,135 @@
 #[cfg(feature = "copy")]
 use arboard::ImageData;
-use tiny_skia::Pixmap;
+use image_webp::WebPEncoder;
+use jpeg_encoder::Encoder as JpegEncoder;
+use tiny_skia::{Color, Pixmap};
 
 #[cfg(feature = "copy")]
 use crate::utils::clipboard::Clipboard;
-use crate::utils::path::parse_file_name;
-use std::fs::write;
+use crate::{
+    config::Encoding,
+    utils::{color::RgbaColor, path::parse_file_name},
+};
+use std::{
+    fs::write,
+    io::{stdout, Write},
+};
+
+// The speed of AVIF encoding, range from 1 (slowest) to 10 (fastest), the snapshot is mostly flat
+// colors, a faster speed is good enough
+#[cfg(feature = "avif")]
+const AVIF_ENCODE_SPEED: u8 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    WebP,
+    Avif,
+}
+
+// The pixels of pixmap are premultiplied, the encoders expect straight RGBA
+fn to_rgba(pixmap: &Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+// Flatten the premultiplied pixels onto the matte color, since JPEG has no alpha channel
+fn to_rgb_with_matte(pixmap: &Pixmap, matte_color: Color) -> Vec<u8> {
+    let matte_color = matte_color.to_color_u8();
+
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let blend = |channel: u8, matte: u8| {
+                channel + ((matte as u16 * (255 - pixel.alpha() as u16) + 127) / 255) as u8
+            };
+
+            [
+                blend(pixel.red(), matte_color.red()),
+                blend(pixel.green(), matte_color.green()),
+                blend(pixel.blue(), matte_color.blue()),
+            ]
+        })
+        .collect()
+}
+
+fn encode_jpeg(pixmap: &Pixmap, encoding: &Encoding) -> anyhow::Result<Vec<u8>> {
+    let matte_color: RgbaColor = encoding.matte_color.as_str().into();
+    let mut data = vec![];
+
+    JpegEncoder::new(&mut data, encoding.quality.clamp(1, 100)).encode(
+        &to_rgb_with_matte(pixmap, matte_color.into()),
+        u16::try_from(pixmap.width())?,
+        u16::try_from(pixmap.height())?,
+        jpeg_encoder::ColorType::Rgb,
+    )?;
+
+    Ok(data)
+}
+
+fn encode_webp(pixmap: &Pixmap, encoding: &Encoding) -> anyhow::Result<Vec<u8>> {
+    let rgba = to_rgba(pixmap);
+
+    if encoding.lossless {
+        let mut data = vec![];
+
+        WebPEncoder::new(&mut data).encode(
+            &rgba,
+            pixmap.width(),
+            pixmap.height(),
+            image_webp::ColorType::Rgba8,
+        )?;
+
+        return Ok(data);
+    }
+
+    #[cfg(feature = "webp")]
+    {
+        Ok(
+            webp::Encoder::from_rgba(&rgba, pixmap.width(), pixmap.height())
+                .encode(encoding.quality.clamp(1, 100) as f32)
+                .to_vec(),
+        )
+    }
+
+    #[cfg(not(feature = "webp"))]
+    anyhow::bail!(
+        "Lossy WebP requires the `webp` feature, enable `lossless` to encode lossless WebP"
+    )
+}
+
+#[cfg(feature = "avif")]
+fn encode_avif(pixmap: &Pixmap, encoding: &Encoding) -> anyhow::Result<Vec<u8>> {
+    use rgb::FromSlice;
+
+    let rgba = to_rgba(pixmap);
+    let image = ravif::Img::new(
+        rgba.as_rgba(),
+        pixmap.width() as usize,
+        pixmap.height() as usize,
+    );
+    let encoded = ravif::Encoder::new()
+        .with_quality(encoding.quality.clamp(1, 100) as f32)
+        .with_alpha_quality(encoding.quality.clamp(1, 100) as f32)
+        .with_speed(AVIF_ENCODE_SPEED)
+        .encode_rgba(image)?;
+
+    Ok(encoded.avif_file)
+}
+
+#[cfg(not(feature = "avif"))]
+fn encode_avif(_pixmap: &Pixmap, _encoding: &Encoding) -> anyhow::Result<Vec<u8>> {
+    anyhow::bail!("AVIF requires the `avif` feature")
+}
 
 pub enum SnapshotData {
     Image {
@@ -14,6 +138,8 @@ pub enum SnapshotData {
         height: usize,
     },
     Text(String),
+    // The documents which are not images, such as PDF, the data has no pixel dimensions
+    Binary(Vec<u8>),
 }
 
 impl SnapshotData {
@@ -29,17 +155,47 @@ impl SnapshotData {
         })
     }
 
+    // Encode the pixmap to the image format, the quality of lossy formats, the lossless mode of
+    // WebP and the matte color of JPEG are controlled by the encoding options
+    pub fn from_pixmap_with_encoding(
+        pixmap: &Pixmap,
+        format: ImageFormat,
+        encoding: &Encoding,
+    ) -> Result<Self, anyhow::Error> {
+        Ok(SnapshotData::Image {
+            width: pixmap.width() as usize,
+            height: pixmap.height() as usize,
+            data: match format {
+                ImageFormat::Png => pixmap.encode_png()?,
+                ImageFormat::Jpeg => encode_jpeg(pixmap, encoding)?,
+                ImageFormat::WebP => encode_webp(pixmap, encoding)?,
+                ImageFormat::Avif => encode_avif(pixmap, encoding)?,
+            },
+        })
+    }
+
     pub fn save(&self, save_path: &str) -> anyhow::Result<()> {
         let path = parse_file_name(save_path)?;
 
-        Ok(match self {
-            SnapshotData::Text(data) => write(path, data)?,
-            SnapshotData::Image {
-                data,
-                width: _,
-                height: _,
-            } => write(path, data)?,
-        })
+        Ok(write(path, self.as_bytes())?)
+    }
+
+    // Write the snapshot to stdout, so that the snapshot can be piped to other programs
+    pub fn write_to_stdout(&self) -> anyhow::Result<()> {
+        let mut stdout = stdout().lock();
+
+        stdout.write_all(self.as_bytes())?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            SnapshotData::Text(data) => data.as_bytes(),
+            SnapshotData::Image { data, .. } => data,
+            SnapshotData::Binary(data) => data,
+        }
     }
 
     #[cfg(feature = "copy")]
@@ -61,6 +217,7 @@ impl SnapshotData {
 
                 clipboard.set_image(image_data)?;
             }
+            SnapshotData::Binary(_) => anyhow::bail!("The binary snapshot cannot be copied"),
         };
 
         Ok(())
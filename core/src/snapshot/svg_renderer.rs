@@ -0,0 +1,324 @@
+use std::fmt::Write;
+
+use tiny_skia::Color;
+
+use crate::components::interface::scene::{Scene, SceneElement, SceneGlyph, ScenePaint};
+
+// The blur of the shadow will out of the host element, the filter region should be large enough
+// to contain the whole shadow, otherwise the shadow will be clipped
+const SHADOW_FILTER_EXTENT: f32 = 3.;
+
+pub struct SVGRenderer<'a> {
+    scene: &'a Scene,
+    defs: String,
+    body: String,
+    id_count: usize,
+}
+
+fn format_color(color: &Color) -> String {
+    let color = color.to_color_u8();
+
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        color.red(),
+        color.green(),
+        color.blue()
+    )
+}
+
+// SVG use a separate attribute to describe the opacity of color, the attribute is omitted if the
+// color is opaque to keep the output small
+fn format_opacity(name: &str, color: &Color) -> String {
+    if color.is_opaque() {
+        String::new()
+    } else {
+        format!(r#" {}="{}""#, name, round(color.alpha()))
+    }
+}
+
+fn round(value: f32) -> f32 {
+    (value * 100.).round() / 100.
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.chars().fold(String::new(), |mut escaped, char| {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(char),
+        };
+        escaped
+    })
+}
+
+// Glyphs are drawn in the same text element if they on the same baseline and have the same style,
+// this can reduce the size of SVG and make the text selectable as a whole line
+fn is_same_text_run(a: &SceneGlyph, b: &SceneGlyph) -> bool {
+    a.y == b.y
+        && a.font.id == b.font.id
+        && a.font_size == b.font_size
+        && a.color == b.color
+        && a.oblique == b.oblique
+}
+
+impl<'a> SVGRenderer<'a> {
+    pub fn new(scene: &'a Scene) -> SVGRenderer<'a> {
+        SVGRenderer {
+            scene,
+            defs: String::new(),
+            body: String::new(),
+            id_count: 0,
+        }
+    }
+
+    pub fn render(mut self) -> String {
+        for element in &self.scene.elements {
+            self.render_element(element);
+        }
+
+        let defs = if self.defs.is_empty() {
+            String::new()
+        } else {
+            format!("<defs>{}</defs>", self.defs)
+        };
+
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">{}{}</svg>"#,
+            round(self.scene.width * self.scene.scale_factor),
+            round(self.scene.height * self.scene.scale_factor),
+            round(self.scene.width),
+            round(self.scene.height),
+            defs,
+            self.body
+        )
+    }
+
+    fn create_id(&mut self, prefix: &str) -> String {
+        self.id_count += 1;
+
+        format!("{}{}", prefix, self.id_count)
+    }
+
+    fn render_element(&mut self, element: &SceneElement) {
+        match element {
+            SceneElement::Background {
+                width,
+                height,
+                paint,
+            } => {
+                let fill = self.render_paint(paint);
+
+                let _ = write!(
+                    self.body,
+                    r#"<rect width="{}" height="{}" {}/>"#,
+                    round(*width),
+                    round(*height),
+                    fill
+                );
+            }
+            SceneElement::Rect {
+                x,
+                y,
+                width,
+                height,
+                radius,
+                color,
+            } => {
+                let radius = if *radius > 0. {
+                    format!(r#" rx="{}""#, round(*radius))
+                } else {
+                    String::new()
+                };
+
+                let _ = write!(
+                    self.body,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}"{} fill="{}"{}/>"#,
+                    round(*x),
+                    round(*y),
+                    round(*width),
+                    round(*height),
+                    radius,
+                    format_color(color),
+                    format_opacity("fill-opacity", color)
+                );
+            }
+            SceneElement::StrokeRect {
+                x,
+                y,
+                width,
+                height,
+                radius,
+                stroke_width,
+                color,
+            } => {
+                let _ = write!(
+                    self.body,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" fill="none" stroke="{}" stroke-width="{}"{}/>"#,
+                    round(*x),
+                    round(*y),
+                    round(*width),
+                    round(*height),
+                    round(*radius),
+                    format_color(color),
+                    round(*stroke_width),
+                    format_opacity("stroke-opacity", color)
+                );
+            }
+            SceneElement::Shadow {
+                x,
+                y,
+                width,
+                height,
+                blur,
+                color,
+            } => {
+                let id = self.create_id("shadow");
+                let extent = blur * SHADOW_FILTER_EXTENT;
+
+                let _ = write!(
+                    self.defs,
+                    r#"<filter id="{}" filterUnits="userSpaceOnUse" x="{}" y="{}" width="{}" height="{}"><feGaussianBlur stdDeviation="{}"/></filter>"#,
+                    id,
+                    round(x - extent),
+                    round(y - extent),
+                    round(width + extent * 2.),
+                    round(height + extent * 2.),
+                    round(*blur)
+                );
+                let _ = write!(
+                    self.body,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"{} filter="url(#{})"/>"#,
+                    round(*x),
+                    round(*y),
+                    round(*width),
+                    round(*height),
+                    format_color(color),
+                    format_opacity("fill-opacity", color),
+                    id
+                );
+            }
+            SceneElement::Circle {
+                cx,
+                cy,
+                radius,
+                color,
+            } => {
+                let _ = write!(
+                    self.body,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}"{}/>"#,
+                    round(*cx),
+                    round(*cy),
+                    round(*radius),
+                    format_color(color),
+                    format_opacity("fill-opacity", color)
+                );
+            }
+            SceneElement::Text(glyphs) => self.render_text(glyphs),
+        }
+    }
+
+    fn render_paint(&mut self, paint: &ScenePaint) -> String {
+        match paint {
+            ScenePaint::Solid(color) => format!(
+                r#"fill="{}"{}"#,
+                format_color(color),
+                format_opacity("fill-opacity", color)
+            ),
+            ScenePaint::LinearGradient { start, end, stops } => {
+                let id = self.create_id("gradient");
+                let stops = stops
+                    .iter()
+                    .map(|(offset, color)| {
+                        format!(
+                            r#"<stop offset="{}" stop-color="{}"{}/>"#,
+                            round(*offset),
+                            format_color(color),
+                            format_opacity("stop-opacity", color)
+                        )
+                    })
+                    .collect::<String>();
+
+                let _ = write!(
+                    self.defs,
+                    r#"<linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">{}</linearGradient>"#,
+                    id,
+                    round(start.x),
+                    round(start.y),
+                    round(end.x),
+                    round(end.y),
+                    stops
+                );
+
+                format!(r#"fill="url(#{})""#, id)
+            }
+        }
+    }
+
+    fn render_text(&mut self, glyphs: &[SceneGlyph]) {
+        let mut runs: Vec<Vec<&SceneGlyph>> = vec![];
+
+        for glyph in glyphs {
+            match runs.last_mut() {
+                Some(run) if is_same_text_run(run[0], glyph) => run.push(glyph),
+                _ => runs.push(vec![glyph]),
+            }
+        }
+
+        for run in runs {
+            let first_glyph = run[0];
+            // Each char in SVG text can be placed by the corresponding value of x attribute,
+            // the glyph which contains multiple chars (such as ligatures) will be split evenly
+            let mut xs = vec![];
+            let mut text = String::new();
+
+            for glyph in &run {
+                let chars_count = glyph.text.chars().count().max(1);
+
+                for index in 0..chars_count {
+                    xs.push(round(
+                        glyph.x + glyph.width * index as f32 / chars_count as f32,
+                    ));
+                }
+                text.push_str(&glyph.text);
+            }
+
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            let xs = xs
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>()
+                .join(" ");
+            let font = &first_glyph.font;
+            let font_style = if font.italic || first_glyph.oblique {
+                r#" font-style="italic""#
+            } else {
+                ""
+            };
+            let font_weight = if font.weight != 400 {
+                format!(r#" font-weight="{}""#, font.weight)
+            } else {
+                String::new()
+            };
+
+            let _ = write!(
+                self.body,
+                r#"<text x="{}" y="{}" font-family="{}, monospace" font-size="{}"{}{} fill="{}"{} xml:space="preserve">{}</text>"#,
+                xs,
+                round(first_glyph.y),
+                escape_xml(&format!("'{}'", font.family)),
+                round(first_glyph.font_size),
+                font_weight,
+                font_style,
+                format_color(&first_glyph.color),
+                format_opacity("fill-opacity", &first_glyph.color),
+                escape_xml(&text)
+            );
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::BTreeMap, env, fmt::Write, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use color_quant::NeuQuant;
+use tiny_skia::Pixmap;
+
+// The kitty graphics protocol requires the payload to be split into chunks of at most 4096 bytes
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+// Sixel supports up to 256 colors in the palette
+const SIXEL_PALETTE_SIZE: usize = 256;
+
+// The sample factor of NeuQuant, range from 1 (best quality) to 30 (fastest)
+const SIXEL_QUANTIZE_SAMPLE_FACTOR: i32 = 10;
+
+// Sixel has no alpha channel, pixels which are more transparent than the threshold are left
+// unpainted, so the terminal background shows through
+const SIXEL_ALPHA_THRESHOLD: u8 = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TerminalProtocol {
+    Kitty,
+    ITerm2,
+    Sixel,
+}
+
+impl FromStr for TerminalProtocol {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "kitty" => Ok(TerminalProtocol::Kitty),
+            "iterm" | "iterm2" => Ok(TerminalProtocol::ITerm2),
+            "sixel" => Ok(TerminalProtocol::Sixel),
+            _ => Err(anyhow::anyhow!("Unknown terminal protocol: {}", value)),
+        }
+    }
+}
+
+impl TerminalProtocol {
+    // Detect the image protocol supported by current terminal through environment variables,
+    // sixel is used as a fallback since it's supported by most of the other terminals
+    pub fn detect() -> TerminalProtocol {
+        let term = env::var("TERM").unwrap_or_default();
+        let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+        // iTerm2 sets `LC_TERMINAL`, which is forwarded by SSH in most cases
+        let lc_terminal = env::var("LC_TERMINAL").unwrap_or_default();
+
+        if env::var("KITTY_WINDOW_ID").is_ok()
+            || term == "xterm-kitty"
+            || term == "xterm-ghostty"
+            || term_program == "ghostty"
+        {
+            TerminalProtocol::Kitty
+        } else if term_program == "iTerm.app"
+            || term_program == "WezTerm"
+            || lc_terminal == "iTerm2"
+        {
+            TerminalProtocol::ITerm2
+        } else {
+            TerminalProtocol::Sixel
+        }
+    }
+}
+
+pub struct TerminalRenderer<'a> {
+    pixmap: &'a Pixmap,
+}
+
+impl<'a> TerminalRenderer<'a> {
+    pub fn new(pixmap: &'a Pixmap) -> TerminalRenderer<'a> {
+        TerminalRenderer { pixmap }
+    }
+
+    // Render the pixmap as escape sequences, print the result to the terminal to display the image
+    pub fn render(&self, protocol: TerminalProtocol) -> anyhow::Result<String> {
+        match protocol {
+            TerminalProtocol::Kitty => self.render_kitty(),
+            TerminalProtocol::ITerm2 => self.render_iterm2(),
+            TerminalProtocol::Sixel => Ok(self.render_sixel()),
+        }
+    }
+
+    fn render_kitty(&self) -> anyhow::Result<String> {
+        let data = STANDARD.encode(self.pixmap.encode_png()?);
+        let chunks = data.as_bytes().chunks(KITTY_CHUNK_SIZE).collect::<Vec<_>>();
+        let mut output = String::new();
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            // The control data of the image is only sent with the first chunk
+            let control = if index == 0 { "a=T,f=100," } else { "" };
+            let has_more = if index == chunks.len() - 1 { 0 } else { 1 };
+
+            let _ = write!(
+                output,
+                "\x1b_G{}m={};{}\x1b\\",
+                control,
+                has_more,
+                String::from_utf8_lossy(chunk)
+            );
+        }
+
+        Ok(output)
+    }
+
+    fn render_iterm2(&self) -> anyhow::Result<String> {
+        let data = self.pixmap.encode_png()?;
+
+        Ok(format!(
+            "\x1b]1337;File=inline=1;size={};preserveAspectRatio=1:{}\x07",
+            data.len(),
+            STANDARD.encode(&data)
+        ))
+    }
+
+    fn render_sixel(&self) -> String {
+        let width = self.pixmap.width() as usize;
+        let height = self.pixmap.height() as usize;
+        let rgba = self
+            .pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = pixel.demultiply();
+
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect::<Vec<u8>>();
+        let quantizer = NeuQuant::new(SIXEL_QUANTIZE_SAMPLE_FACTOR, SIXEL_PALETTE_SIZE, &rgba);
+        let indexes = rgba
+            .chunks(4)
+            .map(|pixel| {
+                if pixel[3] < SIXEL_ALPHA_THRESHOLD {
+                    None
+                } else {
+                    Some(quantizer.index_of(pixel))
+                }
+            })
+            .collect::<Vec<Option<usize>>>();
+        // Use transparent background (P2 = 1), and set the pixel aspect ratio to 1:1
+        let mut output = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+
+        for (index, color) in quantizer.color_map_rgb().chunks(3).enumerate() {
+            // The color components of sixel are in percent
+            let _ = write!(
+                output,
+                "#{};2;{};{};{}",
+                index,
+                color[0] as u32 * 100 / 255,
+                color[1] as u32 * 100 / 255,
+                color[2] as u32 * 100 / 255
+            );
+        }
+
+        // Each sixel char represents 6 vertical pixels, the image is drawn band by band, and each
+        // band is drawn color by color
+        for band_y in (0..height).step_by(6) {
+            let mut bands: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+
+            for y in band_y..(band_y + 6).min(height) {
+                for x in 0..width {
+                    if let Some(color_index) = indexes[y * width + x] {
+                        bands.entry(color_index).or_insert_with(|| vec![0; width])[x] |=
+                            1 << (y - band_y);
+                    }
+                }
+            }
+
+            for (color_index, sixels) in bands {
+                let _ = write!(output, "#{}", color_index);
+                write_sixels(&mut output, &sixels);
+                // Return to the start of the band to draw the next color
+                output.push('$');
+            }
+
+            output.push('-');
+        }
+
+        output.push_str("\x1b\\");
+        output
+    }
+}
+
+// Write the sixels with run-length encoding
+fn write_sixels(output: &mut String, sixels: &[u8]) {
+    let mut index = 0;
+
+    while index < sixels.len() {
+        let sixel = sixels[index];
+        let count = sixels[index..]
+            .iter()
+            .take_while(|value| **value == sixel)
+            .count();
+        let char = (sixel + 63) as char;
+
+        if count > 3 {
+            let _ = write!(output, "!{}{}", count, char);
+        } else {
+            for _ in 0..count {
+                output.push(char);
+            }
+        }
+
+        index += count;
+    }
+}
@@ -1,12 +1,23 @@
+pub mod annotation;
+pub mod ansi;
 pub mod blur;
 #[cfg(feature = "copy")]
 pub mod clipboard;
 pub mod code;
 pub mod color;
+pub mod diff;
+pub mod focus;
+pub mod fold;
 pub mod helpers;
 pub mod highlight;
+pub mod markdown;
 pub mod path;
+pub mod redact;
 pub mod syntax_provider;
 pub mod text;
 pub mod theme;
 pub mod theme_provider;
+pub mod token;
+#[cfg(feature = "tree-sitter")]
+pub mod tree_sitter_provider;
+pub mod wrap;
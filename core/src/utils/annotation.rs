@@ -0,0 +1,63 @@
+use crate::config::{Annotation, AnnotationPlacement, Code, Fold};
+
+// The circled numbers are only available from ① to ⑳, the larger numbers are wrapped by
+// parentheses instead
+pub fn annotation_marker(number: usize) -> String {
+    match number {
+        1..=20 => char::from_u32(0x2460 + number as u32 - 1)
+            .map(String::from)
+            .unwrap_or_default(),
+        _ => format!("({})", number),
+    }
+}
+
+// The texts of callouts, the numbered annotations are prefixed with their markers, and the
+// callout of numbered annotation without text is only the marker
+pub fn callout_texts(annotations: &[Annotation]) -> Vec<String> {
+    let mut number = 0;
+
+    annotations
+        .iter()
+        .map(|annotation| {
+            if !annotation.numbered {
+                return annotation.text.clone();
+            }
+
+            number += 1;
+
+            match annotation.text.is_empty() {
+                true => annotation_marker(number),
+                false => format!("{} {}", annotation_marker(number), annotation.text),
+            }
+        })
+        .collect()
+}
+
+// The callout below the line takes a row for each line of text, and an extra row above them for
+// the bracket which connects the callout to the line
+pub fn below_callout_rows(text: &str) -> usize {
+    text.split('\n').count() + 1
+}
+
+// Reserve the rows for the callouts below the lines, the rows are inserted as empty fold markers
+// before the next line, so that the line numbers and the highlighted lines are pushed down along
+// with the code. There is no line after the last line, the callouts of the last line are drawn
+// under the code
+pub fn reserve_annotation_rows(mut code: Code) -> Code {
+    let rows = code
+        .annotations
+        .iter()
+        .zip(callout_texts(&code.annotations))
+        .filter(|(annotation, _)| annotation.placement == AnnotationPlacement::Below)
+        .flat_map(|(annotation, text)| {
+            (0..below_callout_rows(&text)).map(|_| Fold {
+                line: annotation.line + 1,
+                hidden_lines: 0,
+                label: Some(String::new()),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    code.folds.splice(0..0, rows);
+    code
+}
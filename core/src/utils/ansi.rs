@@ -0,0 +1,103 @@
+use std::env;
+
+use syntect::highlighting::{Color, FontStyle, Style};
+
+use crate::{config::ANSIColorMode, utils::color::RgbaColor};
+
+pub const RESET: &str = "\x1b[0m";
+
+// The levels of the 6x6x6 color cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// Respect the `NO_COLOR` convention (https://no-color.org), the colors are disabled if the
+// variable is present and not empty
+pub fn is_color_disabled() -> bool {
+    env::var("NO_COLOR").is_ok_and(|value| !value.is_empty())
+}
+
+fn nearest_cube_index(value: u8) -> usize {
+    CUBE_LEVELS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, level)| (**level as i32 - value as i32).abs())
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let square = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+
+    square(a.0, b.0) + square(a.1, b.1) + square(a.2, b.2)
+}
+
+// Find the closest color in the 256-color palette, both the color cube (16-231) and the
+// grayscale ramp (232-255) are considered, the first 16 colors are skipped since they are
+// usually customized by the terminal theme
+pub fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let (ri, gi, bi) = (
+        nearest_cube_index(r),
+        nearest_cube_index(g),
+        nearest_cube_index(b),
+    );
+    let cube_color = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_index = ((average.saturating_sub(3)) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray_index;
+    let gray_color = (gray_level, gray_level, gray_level);
+
+    if distance((r, g, b), gray_color) < distance((r, g, b), cube_color) {
+        232 + gray_index
+    } else {
+        cube_index as u8
+    }
+}
+
+pub fn parse_hex_color(hex: &str) -> Color {
+    let color: RgbaColor = hex.into();
+    let color = tiny_skia::Color::from(color).to_color_u8();
+
+    Color {
+        r: color.red(),
+        g: color.green(),
+        b: color.blue(),
+        a: color.alpha(),
+    }
+}
+
+pub fn foreground(color: Color, mode: ANSIColorMode) -> String {
+    match mode {
+        ANSIColorMode::TrueColor => format!("\x1b[38;2;{};{};{}m", color.r, color.g, color.b),
+        ANSIColorMode::Ansi256 => {
+            format!("\x1b[38;5;{}m", rgb_to_ansi256(color.r, color.g, color.b))
+        }
+    }
+}
+
+// Convert the syntect style to ANSI escape sequences, the background of the style is ignored so
+// that the code is drawn on the background of the terminal
+pub fn escape_style(style: Style, mode: ANSIColorMode) -> String {
+    let mut escape = foreground(style.foreground, mode);
+
+    if style.font_style.contains(FontStyle::BOLD) {
+        escape.push_str("\x1b[1m");
+    }
+
+    if style.font_style.contains(FontStyle::ITALIC) {
+        escape.push_str("\x1b[3m");
+    }
+
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        escape.push_str("\x1b[4m");
+    }
+
+    escape
+}
+
+pub fn paint(text: &str, escape: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+
+    format!("{}{}{}", escape, text, RESET)
+}
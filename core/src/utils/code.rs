@@ -1,10 +1,12 @@
-use cached::proc_macro::cached;
+use std::ops::Range;
+
 use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 const MIN_WIDTH: f32 = 100.;
-pub const CHAR_WIDTH: f32 = 9.05;
 
-fn min_width(width: f32) -> f32 {
+pub fn min_width(width: f32) -> f32 {
     if width < MIN_WIDTH {
         MIN_WIDTH
     } else {
@@ -19,29 +21,13 @@ pub fn calc_max_line_number_length(code_length: usize, start_line_number: u32) -
     (max_line_number - 1).to_string().len()
 }
 
-pub fn calc_wh(text: &str, char_wdith: f32, line_height: f32) -> (f32, f32) {
-    let trimmed_text = prepare_code(text);
-    let lines = trimmed_text.lines();
-    let max_length_line = lines.clone().into_iter().fold("", |max_length_line, cur| {
-        if cur.len() > max_length_line.len() {
-            cur
-        } else {
-            max_length_line
-        }
-    });
-    let width = max_length_line.len() as f32 * char_wdith;
-    let height = lines.collect::<Vec<&str>>().len() as f32 * line_height;
-
-    (width, height)
-}
-
-// Because the code block is input by users, we need to calculate the width and height
-// to make sure render the width and height of the "editor" shape correctly
-#[cached(key = "String", convert = r#"{ format!("{}", text) }"#)]
-pub fn calc_wh_with_min_width(text: &str, char_wdith: f32, line_height: f32) -> (f32, f32) {
-    let (width, height) = calc_wh(text, char_wdith, line_height);
-
-    (min_width(width), height)
+// Calculate how many columns the text occupies in monospace layout, the text is measured by
+// grapheme clusters, so that an emoji sequence or a letter with combining marks is counted as a
+// single char, and East Asian wide chars (such as CJK) occupy two columns
+pub fn calc_display_width(text: &str) -> usize {
+    text.graphemes(true)
+        .map(|grapheme| grapheme.width().min(2))
+        .sum()
 }
 
 // The tab character is incorrectly render using cosmic, need to replace all tab with space
@@ -82,3 +68,80 @@ fn trim_space(text: &str) -> String {
 pub fn prepare_code(code: &str) -> String {
     trim_space(&replace_tab_to_space(&code))
 }
+
+// Convert the byte ranges in the lines (the index of line and the range in the line) to the byte
+// ranges in the prepared code, since the tabs are replaced and the common indention is removed by
+// `prepare_code`, the part of range in the removed indention is dropped, so the range may be empty
+pub fn prepared_ranges(code: &str, ranges: &[(usize, Range<usize>)]) -> Vec<Range<usize>> {
+    let prepared_code = prepare_code(code);
+    let mut line_start = 0;
+    // The line and the start offset of each prepared line
+    let lines = code
+        .split('\n')
+        .zip(prepared_code.split('\n'))
+        .map(|(line, prepared_line)| {
+            let start = line_start;
+
+            line_start += prepared_line.len() + 1;
+            (line, prepared_line, start)
+        })
+        .collect::<Vec<_>>();
+
+    ranges
+        .iter()
+        .map(|(line, range)| {
+            let Some((line, prepared_line, line_start)) = lines.get(*line) else {
+                return 0..0;
+            };
+            let expanded = |offset: usize| offset + line[..offset].matches('\t').count();
+            let removed = line.len() + line.matches('\t').count() - prepared_line.len();
+            let (start, end) = (
+                expanded(range.start).saturating_sub(removed),
+                expanded(range.end).saturating_sub(removed),
+            );
+
+            line_start + start..line_start + end.max(start)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prepares_code() {
+        assert_eq!(prepare_code("    a\n      b\n    c"), "a\n  b\nc");
+        assert_eq!(prepare_code("\ta\n\t\tb"), "a\n  b");
+        assert_eq!(prepare_code("a\n  b"), "a\n  b");
+    }
+
+    #[test]
+    fn converts_ranges_to_prepared_code() {
+        let code = "    let a = 1;\n      a += 1;";
+        let prepared_code = prepare_code(code);
+        let ranges = prepared_ranges(code, &[(0, 8..9), (1, 6..7), (1, 2..9), (1, 0..4)]);
+
+        assert_eq!(ranges, [4..5, 13..14, 11..16, 11..11]);
+        assert_eq!(&prepared_code[4..5], "a");
+        assert_eq!(&prepared_code[11..16], "  a +");
+    }
+
+    #[test]
+    fn converts_ranges_after_tabs() {
+        // The tab is replaced with two spaces, so the offsets after it are shifted
+        let code = "a\tb\n\tc";
+        let ranges = prepared_ranges(code, &[(0, 2..3), (1, 1..2), (2, 0..1)]);
+
+        assert_eq!(ranges, [3..4, 7..8, 0..0]);
+        assert_eq!(&prepare_code(code)[3..4], "b");
+        assert_eq!(&prepare_code(code)[7..8], "c");
+    }
+
+    #[test]
+    fn calculates_display_width() {
+        assert_eq!(calc_display_width("abc"), 3);
+        assert_eq!(calc_display_width("中文"), 4);
+        assert_eq!(calc_display_width("e\u{301}👨‍👩‍👧"), 3);
+    }
+}
@@ -0,0 +1,698 @@
+use std::{ops::Range, sync::OnceLock};
+
+use anyhow::bail;
+use regex::Regex;
+
+use crate::config::{Code, CodeConfig, Diff, DiffConfig, Fold, HighlightLine, LineChange};
+
+use super::{
+    code::{prepare_code, prepared_ranges},
+    text::FontRenderer,
+    wrap::wrap_code,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Deleted,
+}
+
+impl DiffLineKind {
+    // The marker in the gutter, the context lines have a blank marker
+    pub fn marker(self) -> &'static str {
+        self.change().map(LineChange::marker).unwrap_or(" ")
+    }
+
+    pub fn change(self) -> Option<LineChange> {
+        match self {
+            DiffLineKind::Added => Some(LineChange::Added),
+            DiffLineKind::Deleted => Some(LineChange::Deleted),
+            DiffLineKind::Context => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_line_number: Option<u32>,
+    pub new_line_number: Option<u32>,
+    pub text: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ParsedDiff {
+    pub file_path: Option<String>,
+    pub lines: Vec<DiffLine>,
+    // The hunk headers (and the file paths of multi-file diffs) are drawn as fold markers before
+    // the first line of the hunk
+    pub folds: Vec<Fold>,
+}
+
+// A pane of the split diff, the old version of the file is drawn in the left pane, and the new
+// version is drawn in the right pane
+#[derive(Clone, Debug)]
+pub struct DiffPane {
+    pub code: Code,
+    // The line numbers of the lines, the filler lines which keep the rows of both panes aligned
+    // have no line number
+    pub line_numbers: Vec<Option<u32>>,
+    // The filler lines are drawn as context lines
+    pub kinds: Vec<DiffLineKind>,
+    // The byte ranges (in the prepared code) of the changed chars of the paired lines
+    pub emphasis: Vec<Range<usize>>,
+}
+
+// The path in `--- a/src/main.rs` or `+++ b/src/main.rs\t2024-01-01 00:00:00`, the path of
+// added or deleted file is `/dev/null`
+fn parse_file_path(path: &str) -> Option<String> {
+    let path = path.split('\t').next().unwrap_or(path).trim();
+
+    match path {
+        "/dev/null" => None,
+        _ => Some(
+            path.strip_prefix("a/")
+                .or(path.strip_prefix("b/"))
+                .unwrap_or(path)
+                .to_string(),
+        ),
+    }
+}
+
+// The hunk header is compiled once instead of each time a diff is parsed
+fn hunk_header() -> &'static Regex {
+    static HUNK_HEADER: OnceLock<Regex> = OnceLock::new();
+
+    HUNK_HEADER.get_or_init(|| Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").unwrap())
+}
+
+// Right align the line numbers to the widest one, the missing line numbers are blank
+fn align_line_numbers(line_numbers: &[Option<u32>]) -> Vec<String> {
+    let width = line_numbers
+        .iter()
+        .map(|line_number| line_number.unwrap_or(0).to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    line_numbers
+        .iter()
+        .map(|line_number| {
+            format!(
+                "{:>width$}",
+                line_number
+                    .map(|line_number| line_number.to_string())
+                    .unwrap_or_default()
+            )
+        })
+        .collect()
+}
+
+impl DiffPane {
+    // Each pane has one column of line numbers, the filler lines have no line number
+    pub fn line_number_column(&self) -> Vec<String> {
+        align_line_numbers(&self.line_numbers)
+    }
+}
+
+impl ParsedDiff {
+    // The unified diff has two columns of line numbers, the old line number of added line and the
+    // new line number of deleted line are empty. The markers are drawn by the snapshots, since
+    // they are colored differently
+    pub fn line_number_columns(&self) -> Vec<String> {
+        let old_line_numbers = align_line_numbers(
+            &self
+                .lines
+                .iter()
+                .map(|line| line.old_line_number)
+                .collect::<Vec<_>>(),
+        );
+        let new_line_numbers = align_line_numbers(
+            &self
+                .lines
+                .iter()
+                .map(|line| line.new_line_number)
+                .collect::<Vec<_>>(),
+        );
+
+        old_line_numbers
+            .into_iter()
+            .zip(new_line_numbers)
+            .map(|(old, new)| format!("{old} {new}"))
+            .collect()
+    }
+
+    pub fn from(diff: &str) -> anyhow::Result<ParsedDiff> {
+        let hunk_header = hunk_header();
+        let mut file_paths: Vec<String> = vec![];
+        let mut old_file_path = None;
+        let mut lines = vec![];
+        let mut folds = vec![];
+        let (mut old_line_number, mut new_line_number) = (0, 0);
+        // The number of old and new lines which are not consumed in current hunk
+        let (mut old_rest, mut new_rest) = (0u32, 0u32);
+
+        for line in diff.lines() {
+            if old_rest > 0 || new_rest > 0 {
+                let (kind, text) = match line.chars().next() {
+                    Some('+') => (DiffLineKind::Added, &line[1..]),
+                    Some('-') => (DiffLineKind::Deleted, &line[1..]),
+                    Some(' ') => (DiffLineKind::Context, &line[1..]),
+                    // Some tools trim the trailing whitespace of the diff, so the empty context
+                    // line loses its leading space
+                    None => (DiffLineKind::Context, ""),
+                    // No newline at end of file
+                    Some('\\') => continue,
+                    Some(_) => bail!("Invalid line in the hunk of diff: {}", line),
+                };
+                let old_line = kind != DiffLineKind::Added;
+                let new_line = kind != DiffLineKind::Deleted;
+
+                lines.push(DiffLine {
+                    kind,
+                    old_line_number: old_line.then_some(old_line_number),
+                    new_line_number: new_line.then_some(new_line_number),
+                    text: text.to_string(),
+                });
+
+                if old_line {
+                    old_line_number += 1;
+                    old_rest = old_rest.saturating_sub(1);
+                }
+
+                if new_line {
+                    new_line_number += 1;
+                    new_rest = new_rest.saturating_sub(1);
+                }
+
+                continue;
+            }
+
+            if let Some(captures) = hunk_header.captures(line) {
+                let parse_count = |index| {
+                    captures
+                        .get(index)
+                        .map(|count| count.as_str().parse::<u32>())
+                        .unwrap_or(Ok(1))
+                };
+
+                old_line_number = captures[1].parse()?;
+                old_rest = parse_count(2)?;
+                new_line_number = captures[3].parse()?;
+                new_rest = parse_count(4)?;
+                folds.push(Fold {
+                    line: lines.len() as u32 + 1,
+                    hidden_lines: 0,
+                    label: Some(line.to_string()),
+                });
+            } else if let Some(path) = line.strip_prefix("--- ") {
+                old_file_path = parse_file_path(path);
+            } else if let Some(path) = line.strip_prefix("+++ ") {
+                // The new path of a deleted file is `/dev/null`
+                let Some(file_path) = parse_file_path(path).or(old_file_path.take()) else {
+                    continue;
+                };
+
+                // The diff of multiple files, each file starts with its path
+                if !file_paths.is_empty() {
+                    folds.push(Fold {
+                        line: lines.len() as u32 + 1,
+                        hidden_lines: 0,
+                        label: Some(file_path.clone()),
+                    });
+                }
+
+                file_paths.push(file_path);
+            }
+        }
+
+        if lines.is_empty() {
+            bail!("No hunk is found in the diff");
+        }
+
+        Ok(ParsedDiff {
+            // The breadcrumbs only display the path of a single file
+            file_path: (file_paths.len() == 1).then(|| file_paths[0].clone()),
+            lines,
+            folds,
+        })
+    }
+
+    // Convert the diff to code, the changed lines are highlighted, and the hunk headers are drawn
+    // as fold markers, the line numbers are drawn by the `LineNumber` of diff
+    pub fn to_code(&self, diff: &Diff, diff_config: &DiffConfig) -> Code {
+        let highlight_lines = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let change = line.kind.change()?;
+                let color = match change {
+                    LineChange::Added => &diff_config.added_line_color,
+                    LineChange::Deleted => &diff_config.deleted_line_color,
+                };
+                let line_number = index as u32 + 1;
+
+                Some(HighlightLine::Changed(
+                    line_number,
+                    line_number,
+                    color.clone(),
+                    change,
+                ))
+            })
+            .collect();
+
+        Code {
+            content: self
+                .lines
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect::<Vec<&str>>()
+                .join("\n"),
+            start_line_number: None,
+            highlight_lines,
+            highlight_tokens: vec![],
+            redactions: vec![],
+            annotations: vec![],
+            language: diff.language.clone(),
+            file_path: diff.file_path.clone().or(self.file_path.clone()),
+            folds: self.folds.clone(),
+        }
+    }
+
+    // Pair up the lines of both versions, the context lines are drawn in the same row of both
+    // panes, and the deleted lines of a change are paired with the added lines after them, the
+    // missing lines of the shorter side are filled with empty lines
+    pub fn to_split_panes(&self, diff: &Diff, diff_config: &DiffConfig) -> (DiffPane, DiffPane) {
+        let is_fold_line = |index: usize| {
+            self.folds
+                .iter()
+                .any(|fold| fold.line as usize == index + 1)
+        };
+        // The lines of a change are not paired with the lines of next hunk or next file
+        let take_change = |start: usize, kind: DiffLineKind| {
+            start
+                + self.lines[start..]
+                    .iter()
+                    .enumerate()
+                    .take_while(|(offset, line)| {
+                        line.kind == kind && (*offset == 0 || !is_fold_line(start + offset))
+                    })
+                    .count()
+        };
+        let mut rows: Vec<(Option<&DiffLine>, Option<&DiffLine>)> = vec![];
+        // The row of each line of the unified diff
+        let mut line_rows = vec![0; self.lines.len()];
+        let mut index = 0;
+
+        while index < self.lines.len() {
+            if self.lines[index].kind == DiffLineKind::Context {
+                line_rows[index] = rows.len();
+                rows.push((Some(&self.lines[index]), Some(&self.lines[index])));
+                index += 1;
+                continue;
+            }
+
+            let deleted_end = take_change(index, DiffLineKind::Deleted);
+            let added_end = match deleted_end > index && is_fold_line(deleted_end) {
+                true => deleted_end,
+                false => take_change(deleted_end, DiffLineKind::Added),
+            };
+            let (deleted, added) = (index..deleted_end, deleted_end..added_end);
+
+            for offset in 0..deleted.len().max(added.len()) {
+                let old = (offset < deleted.len()).then(|| deleted.start + offset);
+                let new = (offset < added.len()).then(|| added.start + offset);
+
+                for line in old.iter().chain(new.iter()) {
+                    line_rows[*line] = rows.len();
+                }
+
+                rows.push((
+                    old.map(|line| &self.lines[line]),
+                    new.map(|line| &self.lines[line]),
+                ));
+            }
+
+            index = added_end;
+        }
+
+        let folds = self
+            .folds
+            .iter()
+            .map(|fold| Fold {
+                line: line_rows
+                    .get(fold.line as usize - 1)
+                    .map(|row| *row as u32 + 1)
+                    .unwrap_or(fold.line),
+                ..fold.clone()
+            })
+            .collect::<Vec<Fold>>();
+        let changed_ranges = rows
+            .iter()
+            .map(|row| match row {
+                (Some(old), Some(new)) if old.kind != DiffLineKind::Context => {
+                    changed_ranges(&old.text, &new.text)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let pane = |lines: Vec<Option<&DiffLine>>,
+                    emphasis: Vec<Option<Range<usize>>>,
+                    line_number: fn(&DiffLine) -> Option<u32>,
+                    line_color: &str| {
+            let content = lines
+                .iter()
+                .map(|line| line.map(|line| line.text.as_str()).unwrap_or_default())
+                .collect::<Vec<&str>>()
+                .join("\n");
+            let highlight_lines = lines
+                .iter()
+                .enumerate()
+                .filter_map(|(row, line)| {
+                    let change = line.and_then(|line| line.kind.change())?;
+                    let line_number = row as u32 + 1;
+
+                    Some(HighlightLine::Changed(
+                        line_number,
+                        line_number,
+                        line_color.to_string(),
+                        change,
+                    ))
+                })
+                .collect();
+
+            DiffPane {
+                emphasis: prepared_ranges(
+                    &content,
+                    &emphasis
+                        .into_iter()
+                        .enumerate()
+                        .filter_map(|(line, range)| Some((line, range?)))
+                        .collect::<Vec<_>>(),
+                )
+                .into_iter()
+                .filter(|range| !range.is_empty())
+                .collect(),
+                line_numbers: lines
+                    .iter()
+                    .map(|line| line.and_then(line_number))
+                    .collect(),
+                kinds: lines
+                    .iter()
+                    .map(|line| line.map(|line| line.kind).unwrap_or(DiffLineKind::Context))
+                    .collect(),
+                code: Code {
+                    content,
+                    start_line_number: None,
+                    highlight_lines,
+                    highlight_tokens: vec![],
+                    redactions: vec![],
+                    annotations: vec![],
+                    language: diff.language.clone(),
+                    file_path: diff.file_path.clone().or(self.file_path.clone()),
+                    folds: folds.clone(),
+                },
+            }
+        };
+
+        (
+            pane(
+                rows.iter().map(|(old, _)| *old).collect(),
+                changed_ranges
+                    .iter()
+                    .map(|ranges| ranges.clone().map(|(old, _)| old))
+                    .collect(),
+                |line| line.old_line_number,
+                &diff_config.deleted_line_color,
+            ),
+            pane(
+                rows.iter().map(|(_, new)| *new).collect(),
+                changed_ranges
+                    .iter()
+                    .map(|ranges| ranges.clone().map(|(_, new)| new))
+                    .collect(),
+                |line| line.new_line_number,
+                &diff_config.added_line_color,
+            ),
+        )
+    }
+}
+
+// The byte ranges of the changed chars of the paired lines, the common prefix and suffix are
+// unchanged, the lines which have nothing in common are not emphasized, since the whole line is
+// highlighted already
+fn changed_ranges(old: &str, new: &str) -> Option<(Range<usize>, Range<usize>)> {
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, old_char), new_char)| old_char != new_char)
+        .map(|((index, _), _)| index)
+        .unwrap_or(old.len().min(new.len()));
+    let suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(old_char, new_char)| old_char == new_char)
+        .map(|(char, _)| char.len_utf8())
+        .sum::<usize>();
+
+    if prefix == 0 && suffix == 0 {
+        return None;
+    }
+
+    Some((prefix..old.len() - suffix, prefix..new.len() - suffix))
+}
+
+// The wrapped lines take more rows, the rows of the other pane are filled with empty fold markers,
+// so that the paired lines are still in the same row
+pub fn align_wrapped_rows(
+    panes: (&mut DiffPane, &mut DiffPane),
+    code_config: &CodeConfig,
+    font_renderer: &mut FontRenderer,
+) {
+    let mut rows = |pane: &DiffPane| {
+        wrap_code(
+            &prepare_code(&pane.code.content),
+            code_config,
+            font_renderer,
+        )
+        .iter()
+        .map(|breaks| breaks.len())
+        .collect::<Vec<usize>>()
+    };
+    let (old_rows, new_rows) = (rows(panes.0), rows(panes.1));
+
+    for (line, (old_rows, new_rows)) in old_rows.iter().zip(&new_rows).enumerate() {
+        let (pane, missing_rows) = match old_rows.cmp(new_rows) {
+            std::cmp::Ordering::Less => (&mut *panes.0, new_rows - old_rows),
+            std::cmp::Ordering::Greater => (&mut *panes.1, old_rows - new_rows),
+            std::cmp::Ordering::Equal => continue,
+        };
+
+        // The fold markers are drawn before the next line (and before the hunk header of the next
+        // line), the last line doesn't need to be aligned
+        pane.code.folds.splice(
+            0..0,
+            (0..missing_rows).map(|_| Fold {
+                line: line as u32 + 2,
+                hidden_lines: 0,
+                label: Some(String::new()),
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CodeConfigBuilder, DiffBuilder, MaxWidth};
+
+    fn kinds(diff: &ParsedDiff) -> Vec<DiffLineKind> {
+        diff.lines.iter().map(|line| line.kind).collect()
+    }
+
+    fn texts(diff: &ParsedDiff) -> Vec<&str> {
+        diff.lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    fn split_panes(diff: &str) -> (DiffPane, DiffPane) {
+        ParsedDiff::from(diff).unwrap().to_split_panes(
+            &DiffBuilder::default().diff(diff).build().unwrap(),
+            &DiffConfig::default(),
+        )
+    }
+
+    #[test]
+    fn parses_hunks() {
+        let diff = ParsedDiff::from(
+            "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -3,3 +3,3 @@ fn main() {\n a\n-b\n+c\n d\n",
+        )
+        .unwrap();
+
+        assert_eq!(diff.file_path.as_deref(), Some("src/main.rs"));
+        assert_eq!(
+            kinds(&diff),
+            [
+                DiffLineKind::Context,
+                DiffLineKind::Deleted,
+                DiffLineKind::Added,
+                DiffLineKind::Context
+            ]
+        );
+        assert_eq!(
+            diff.lines
+                .iter()
+                .map(|line| (line.old_line_number, line.new_line_number))
+                .collect::<Vec<_>>(),
+            [
+                (Some(3), Some(3)),
+                (Some(4), None),
+                (None, Some(4)),
+                (Some(5), Some(5))
+            ]
+        );
+        assert_eq!(diff.folds.len(), 1);
+        assert_eq!(diff.folds[0].line, 1);
+        assert_eq!(
+            diff.folds[0].label.as_deref(),
+            Some("@@ -3,3 +3,3 @@ fn main() {")
+        );
+    }
+
+    #[test]
+    fn parses_hunk_headers_without_counts() {
+        let diff = ParsedDiff::from("@@ -7 +7 @@\n-a\n+b\nnot in the hunk\n").unwrap();
+
+        assert_eq!(texts(&diff), ["a", "b"]);
+        assert_eq!(diff.lines[0].old_line_number, Some(7));
+        assert_eq!(diff.lines[1].new_line_number, Some(7));
+    }
+
+    #[test]
+    fn skips_no_newline_markers() {
+        let diff = ParsedDiff::from(
+            "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n",
+        )
+        .unwrap();
+
+        assert_eq!(texts(&diff), ["a", "b", "c"]);
+        assert_eq!(diff.lines[2].new_line_number, Some(2));
+    }
+
+    #[test]
+    fn parses_crlf_diffs() {
+        let diff =
+            ParsedDiff::from("--- a/a.txt\r\n+++ b/a.txt\r\n@@ -1,2 +1,2 @@\r\n a\r\n-b\r\n+c\r\n")
+                .unwrap();
+
+        assert_eq!(diff.file_path.as_deref(), Some("a.txt"));
+        assert_eq!(texts(&diff), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn keeps_trimmed_empty_context_lines() {
+        let diff = ParsedDiff::from("@@ -1,3 +1,3 @@\n a\n\n-b\n+c\n").unwrap();
+
+        assert_eq!(texts(&diff), ["a", "", "b", "c"]);
+        assert_eq!(diff.lines[1].kind, DiffLineKind::Context);
+    }
+
+    #[test]
+    fn marks_files_of_multi_file_diffs() {
+        let diff = ParsedDiff::from(
+            "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n--- a/b.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-c\n",
+        )
+        .unwrap();
+
+        assert_eq!(diff.file_path, None);
+        assert_eq!(
+            diff.folds
+                .iter()
+                .map(|fold| (fold.line, fold.label.as_deref().unwrap()))
+                .collect::<Vec<_>>(),
+            [(1, "@@ -1 +1 @@"), (3, "b.txt"), (3, "@@ -1 +0,0 @@")]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_diffs() {
+        assert!(ParsedDiff::from("").is_err());
+        assert!(ParsedDiff::from("--- a/a.txt\n+++ b/a.txt\n").is_err());
+        assert!(ParsedDiff::from("@@ -1,2 +1,2 @@\n a\n*b\n").is_err());
+    }
+
+    #[test]
+    fn finds_changed_ranges() {
+        assert_eq!(
+            changed_ranges("let a = 1;", "let a = 22;"),
+            Some((8..9, 8..10))
+        );
+        // The ranges are counted in bytes
+        assert_eq!(changed_ranges("café", "cafe"), Some((3..5, 3..4)));
+        // The common prefix and suffix don't overlap
+        assert_eq!(changed_ranges("aa", "aaa"), Some((2..2, 2..3)));
+        assert_eq!(changed_ranges("abc", "abc"), Some((3..3, 3..3)));
+        assert_eq!(changed_ranges("", "abc"), None);
+        assert_eq!(changed_ranges("abc", "xyz"), None);
+    }
+
+    #[test]
+    fn pairs_lines_of_split_panes() {
+        let (old, new) = split_panes("@@ -1,3 +1,2 @@\n a\n-b\n-c\n+d\n");
+
+        assert_eq!(old.code.content, "a\nb\nc");
+        assert_eq!(new.code.content, "a\nd\n");
+        assert_eq!(old.line_numbers, [Some(1), Some(2), Some(3)]);
+        assert_eq!(new.line_numbers, [Some(1), Some(2), None]);
+        assert_eq!(
+            new.kinds,
+            [
+                DiffLineKind::Context,
+                DiffLineKind::Added,
+                DiffLineKind::Context
+            ]
+        );
+        assert_eq!(new.line_number_column(), ["1", "2", " "]);
+    }
+
+    #[test]
+    fn emphasizes_changed_chars_of_paired_lines() {
+        let (old, new) = split_panes("@@ -1 +1 @@\n-let a = 1;\n+let a = 2;\n");
+
+        assert_eq!(old.emphasis, [8..9]);
+        assert_eq!(new.emphasis, [8..9]);
+    }
+
+    #[test]
+    fn aligns_wrapped_rows() {
+        let (mut old, mut new) = split_panes("@@ -1,2 +1,2 @@\n-aaaaaaaaaa\n+b\n c\n");
+        let code_config = CodeConfigBuilder::default()
+            .max_width(MaxWidth::Columns(5))
+            .build()
+            .unwrap();
+
+        align_wrapped_rows(
+            (&mut old, &mut new),
+            &code_config,
+            &mut FontRenderer::new(1., vec![]),
+        );
+
+        // The old line takes 3 rows, the new pane is filled with 2 rows before the next line
+        assert!(old.code.folds.iter().all(|fold| fold.label.is_some()));
+        assert_eq!(
+            new.code
+                .folds
+                .iter()
+                .filter(|fold| fold.label.as_deref() == Some(""))
+                .map(|fold| fold.line)
+                .collect::<Vec<_>>(),
+            [2, 2]
+        );
+        assert!(old
+            .code
+            .folds
+            .iter()
+            .all(|fold| fold.label.as_deref() != Some("")));
+    }
+}
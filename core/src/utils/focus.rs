@@ -0,0 +1,47 @@
+use syntect::highlighting::Color;
+
+use crate::config::{FocusConfig, FocusStyle, HighlightLine};
+
+// Whether each line of the code is out of focus, the lines are all in focus if the focus mode is
+// disabled or no line is highlighted
+pub fn unfocused_lines(
+    highlight_lines: &[HighlightLine],
+    line_count: usize,
+    focus_config: &FocusConfig,
+) -> Vec<bool> {
+    if !focus_config.enabled || highlight_lines.is_empty() {
+        return vec![false; line_count];
+    }
+
+    (1..=line_count as u32)
+        .map(|line_number| {
+            !highlight_lines.iter().any(|highlight_line| {
+                let (start, end) = highlight_line.range();
+
+                (start.min(end)..=start.max(end)).contains(&line_number)
+            })
+        })
+        .collect()
+}
+
+// Dim or desaturate the color of the unfocused chars
+pub fn unfocus_color(color: Color, focus_config: &FocusConfig) -> Color {
+    let Color { r, g, b, a } = color;
+    let a = (a as f32 * focus_config.opacity.clamp(0., 1.)).round() as u8;
+
+    match focus_config.style {
+        FocusStyle::Dim => Color { r, g, b, a },
+        FocusStyle::Desaturate => {
+            // The perceived luminance of the color, so that the bright colors are still brighter
+            // than the dark ones after desaturating
+            let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).round() as u8;
+
+            Color {
+                r: luma,
+                g: luma,
+                b: luma,
+                a,
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::config::Fold;
+
+// The marker which is drawn in place of the hidden lines
+pub fn fold_marker(fold: &Fold) -> String {
+    if let Some(ref label) = fold.label {
+        return label.clone();
+    }
+
+    match fold.hidden_lines {
+        1 => String::from("⋯ 1 line hidden"),
+        hidden_lines => format!("⋯ {} lines hidden", hidden_lines),
+    }
+}
+
+// The folds which are drawn before each line, a fold which is out of the code is ignored
+pub fn folds_by_line(line_count: usize, folds: &[Fold]) -> Vec<Vec<&Fold>> {
+    (1..=line_count)
+        .map(|line| {
+            folds
+                .iter()
+                .filter(|fold| fold.line as usize == line)
+                .collect()
+        })
+        .collect()
+}
+
+// The line numbers of the original code, the lines after a fold are shifted by the hidden lines
+pub fn original_line_numbers(
+    start_line_number: u32,
+    line_count: usize,
+    folds: &[Fold],
+) -> Vec<u32> {
+    let mut hidden_lines = 0;
+
+    folds_by_line(line_count, folds)
+        .into_iter()
+        .enumerate()
+        .map(|(index, line_folds)| {
+            hidden_lines += line_folds.iter().map(|fold| fold.hidden_lines).sum::<u32>();
+
+            start_line_number + index as u32 + hidden_lines
+        })
+        .collect()
+}
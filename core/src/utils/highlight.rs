@@ -1,56 +1,111 @@
-use cosmic_text::{Attrs, Family, Style, Weight};
+use cosmic_text::{Attrs, Style, Weight};
 use syntect::{
-    easy::HighlightLines, highlighting::FontStyle, parsing::SyntaxSet, util::LinesWithEndings,
+    easy::HighlightLines,
+    highlighting::{FontStyle, Style as SyntectStyle},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
 };
+#[cfg(feature = "tree-sitter")]
+use syntect::{highlighting::Theme, parsing::SyntaxReference};
 
-use crate::components::interface::render_error::RenderError;
+use crate::{components::interface::render_error::RenderError, config::FocusConfig};
 
-pub struct Highlight {
+#[cfg(feature = "tree-sitter")]
+use super::tree_sitter_provider::highlight_lines;
+use super::{focus::unfocus_color, text::UNDERLINE_METADATA};
+
+pub struct Highlight<'a> {
     content: String,
-    font_family: String,
+    attrs: Attrs<'a>,
+    // Whether each line is out of focus, the colors of unfocused lines are adjusted by the
+    // `focus_config`
+    unfocused_lines: Vec<bool>,
+    focus_config: FocusConfig,
 }
 
 pub type HighlightResult<'a> = Vec<(&'a str, Attrs<'a>)>;
 
-impl Highlight {
-    pub fn new(content: String, font_family: String) -> Highlight {
+impl<'a> Highlight<'a> {
+    pub fn new(content: String, attrs: Attrs<'a>) -> Highlight<'a> {
         Highlight {
             content,
-            font_family,
+            attrs,
+            unfocused_lines: vec![],
+            focus_config: FocusConfig::default(),
         }
     }
 
+    pub fn unfocus(mut self, unfocused_lines: Vec<bool>, focus_config: FocusConfig) -> Self {
+        self.unfocused_lines = unfocused_lines;
+        self.focus_config = focus_config;
+        self
+    }
+
     // Parse Syntect Highlightlines to Cosmic Text span Attrs
     pub fn parse(
         &self,
         highlight: &mut HighlightLines,
         syntax_set: &SyntaxSet,
     ) -> Result<Vec<(&str, Attrs)>, RenderError> {
-        let attrs = Attrs::new().family(Family::Name(self.font_family.as_ref()));
-
         // Highlight the content line by line using highlight_line function
-        Ok(LinesWithEndings::from(&self.content)
-            .map(|line| {
-                highlight
-                    .highlight_line(line, &syntax_set)
-                    .unwrap()
+        let lines = LinesWithEndings::from(&self.content)
+            .map(|line| highlight.highlight_line(line, &syntax_set).unwrap())
+            .collect::<Vec<_>>();
+
+        Ok(self.to_spans(lines))
+    }
+
+    // Parse the captures of tree-sitter to Cosmic Text span Attrs, returns `None` if the language
+    // has no tree-sitter grammar
+    #[cfg(feature = "tree-sitter")]
+    pub fn parse_tree_sitter(
+        &self,
+        syntax: &SyntaxReference,
+        theme: &Theme,
+    ) -> Result<Option<HighlightResult<'_>>, RenderError> {
+        let lines = highlight_lines(&self.content, syntax, theme)?;
+
+        Ok(lines.map(|lines| self.to_spans(lines)))
+    }
+
+    fn to_spans<'b>(&self, lines: Vec<Vec<(SyntectStyle, &'b str)>>) -> Vec<(&'b str, Attrs<'a>)> {
+        let attrs = self.attrs.clone();
+
+        lines
+            .into_iter()
+            .enumerate()
+            .flat_map(|(index, regions)| {
+                let is_unfocused = self.unfocused_lines.get(index).copied().unwrap_or(false);
+
+                regions
                     .into_iter()
                     .map(|(style, str)| {
-                        let syntect::highlighting::Color { r, g, b, a } = style.foreground;
-                        let attrs_cloned = attrs.clone();
-                        let attrs = match style.font_style {
-                            FontStyle::BOLD => attrs_cloned.weight(Weight::BOLD),
-                            FontStyle::ITALIC => attrs_cloned.style(Style::Italic),
-                            FontStyle::UNDERLINE => attrs_cloned.style(Style::Normal),
-                            _ => attrs_cloned,
+                        let color = match is_unfocused {
+                            true => unfocus_color(style.foreground, &self.focus_config),
+                            false => style.foreground,
                         };
+                        let syntect::highlighting::Color { r, g, b, a } = color;
+                        // The font styles can be combined, such as the bold italic comments
+                        let mut attrs = attrs.clone();
+
+                        if style.font_style.contains(FontStyle::BOLD) {
+                            attrs = attrs.weight(Weight::BOLD);
+                        }
+
+                        if style.font_style.contains(FontStyle::ITALIC) {
+                            attrs = attrs.style(Style::Italic);
+                        }
+
+                        if style.font_style.contains(FontStyle::UNDERLINE) {
+                            let metadata = attrs.metadata | UNDERLINE_METADATA;
+
+                            attrs = attrs.metadata(metadata);
+                        }
 
                         (str, attrs.color(cosmic_text::Color::rgba(r, g, b, a)))
                     })
-                    .collect::<HighlightResult>()
+                    .collect::<Vec<_>>()
             })
-            .fold(vec![], |acc, cur| [acc, cur].concat())
-            .into_iter()
-            .collect::<HighlightResult>())
+            .collect()
     }
 }
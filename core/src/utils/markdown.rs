@@ -0,0 +1,334 @@
+use regex::Regex;
+
+// The bullets of unordered lists are drawn as the same marker whatever the source is
+const BULLET_MARKER: &str = "•";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InlineStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub strikethrough: bool,
+    pub link: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InlineSpan {
+    pub text: String,
+    pub style: InlineStyle,
+}
+
+// Only the basic blocks of Markdown are parsed, the other blocks (such as quotes and tables) are
+// drawn as paragraphs, and the thematic breaks only separate the paragraphs
+#[derive(Clone, Debug)]
+pub enum MarkdownBlock {
+    Heading {
+        level: usize,
+        spans: Vec<InlineSpan>,
+    },
+    Paragraph(Vec<InlineSpan>),
+    ListItem {
+        // The nesting level of the item, the top level is 0
+        depth: usize,
+        marker: String,
+        spans: Vec<InlineSpan>,
+    },
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+    },
+}
+
+// The text of the paragraph or list item is collected line by line, the inlines are parsed after
+// the whole block is collected, since the emphasis may span lines
+enum RawBlock {
+    Heading(usize, String),
+    Paragraph(String),
+    ListItem(usize, String, String),
+    CodeBlock(Option<String>, String),
+}
+
+impl RawBlock {
+    fn into_block(self) -> MarkdownBlock {
+        match self {
+            RawBlock::Heading(level, text) => MarkdownBlock::Heading {
+                level,
+                spans: parse_inlines(&text),
+            },
+            RawBlock::Paragraph(text) => MarkdownBlock::Paragraph(parse_inlines(&text)),
+            RawBlock::ListItem(depth, marker, text) => MarkdownBlock::ListItem {
+                depth,
+                marker,
+                spans: parse_inlines(&text),
+            },
+            RawBlock::CodeBlock(language, code) => MarkdownBlock::CodeBlock { language, code },
+        }
+    }
+}
+
+// The closing fence is made of the same char as the opening fence, and is at least as long as it
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let line = line.trim();
+    let fence_char = fence.chars().next().unwrap_or('`');
+
+    line.len() >= fence.len() && line.chars().all(|char| char == fence_char)
+}
+
+// The soft line breaks are joined by spaces, and the hard line breaks (two trailing spaces or a
+// trailing backslash) are kept
+fn continue_text(text: &mut String, line: &str) {
+    let is_hard_break = text.ends_with("  ") || text.ends_with('\\');
+    let trimmed_len = text.trim_end_matches(['\\', ' ']).len();
+
+    text.truncate(trimmed_len);
+    text.push(if is_hard_break { '\n' } else { ' ' });
+    text.push_str(line.trim());
+}
+
+pub fn parse_markdown(markdown: &str) -> Vec<MarkdownBlock> {
+    let fence = Regex::new(r"^ {0,3}(`{3,}|~{3,})[ \t]*([^`\s]*)").unwrap();
+    let heading = Regex::new(r"^ {0,3}(#{1,6})(?:[ \t]+(.*?))?(?:[ \t]+#+)?[ \t]*$").unwrap();
+    let thematic_break =
+        Regex::new(r"^ {0,3}(?:(?:\*[ \t]*){3,}|(?:-[ \t]*){3,}|(?:_[ \t]*){3,})$").unwrap();
+    let list_item = Regex::new(r"^([ \t]*)([-*+]|\d{1,9}[.)])[ \t]+(.*)$").unwrap();
+    let mut lines = markdown.lines();
+    let mut blocks: Vec<RawBlock> = vec![];
+    // The last paragraph or list item can be continued by the next line
+    let mut is_open = false;
+
+    while let Some(line) = lines.next() {
+        if let Some(captures) = fence.captures(line) {
+            let marker = captures[1].to_string();
+            let language = Some(captures[2].to_string()).filter(|language| !language.is_empty());
+            let code = lines
+                .by_ref()
+                .take_while(|line| !is_closing_fence(line, &marker))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            blocks.push(RawBlock::CodeBlock(language, code));
+            is_open = false;
+        } else if line.trim().is_empty() || thematic_break.is_match(line) {
+            is_open = false;
+        } else if let Some(captures) = heading.captures(line) {
+            let text = captures.get(2).map(|text| text.as_str()).unwrap_or("");
+
+            blocks.push(RawBlock::Heading(captures[1].len(), text.to_string()));
+            is_open = false;
+        } else if let Some(captures) = list_item.captures(line) {
+            let indent = captures[1].replace('\t', "    ").len();
+            let marker = match &captures[2] {
+                "-" | "*" | "+" => BULLET_MARKER.to_string(),
+                marker => marker.to_string(),
+            };
+
+            blocks.push(RawBlock::ListItem(
+                indent / 2,
+                marker,
+                captures[3].to_string(),
+            ));
+            is_open = true;
+        } else {
+            match blocks.last_mut() {
+                Some(RawBlock::Paragraph(text)) | Some(RawBlock::ListItem(_, _, text))
+                    if is_open =>
+                {
+                    continue_text(text, line)
+                }
+                _ => blocks.push(RawBlock::Paragraph(line.trim().to_string())),
+            }
+            is_open = true;
+        }
+    }
+
+    blocks.into_iter().map(RawBlock::into_block).collect()
+}
+
+// Append the char to the last span if they are in the same style
+fn push_char(spans: &mut Vec<InlineSpan>, char: char, style: InlineStyle) {
+    match spans.last_mut() {
+        Some(span) if span.style == style => span.text.push(char),
+        _ => spans.push(InlineSpan {
+            text: char.to_string(),
+            style,
+        }),
+    }
+}
+
+fn run_length(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .take_while(|char| **char == chars[start])
+        .count()
+}
+
+// The index of the closing backticks which are as long as the opening backticks
+fn find_closing_backticks(chars: &[char], start: usize, length: usize) -> Option<usize> {
+    let mut index = start;
+
+    while index < chars.len() {
+        let run = match chars[index] {
+            '`' => run_length(chars, index),
+            _ => 0,
+        };
+
+        if run == length {
+            return Some(index);
+        }
+
+        index += run.max(1);
+    }
+
+    None
+}
+
+// The `[text](url)` link, returns the end of the text and the end of the whole link
+fn find_link(chars: &[char], start: usize) -> Option<(usize, usize)> {
+    let text_end = (start + 1..chars.len()).find(|index| chars[*index] == ']')?;
+
+    if chars.get(text_end + 1) != Some(&'(') {
+        return None;
+    }
+
+    let url_end = (text_end + 2..chars.len()).find(|index| chars[*index] == ')')?;
+
+    Some((text_end, url_end + 1))
+}
+
+// Toggle the emphasis by the delimiter run, the run is a delimiter only if it's followed by a
+// non-whitespace char (when opening) or preceded by one (when closing), and the `_` in the middle
+// of words (such as `snake_case`) isn't a delimiter
+fn toggle_emphasis(
+    chars: &[char],
+    index: usize,
+    length: usize,
+    style: &mut InlineStyle,
+) -> Option<usize> {
+    let char = chars[index];
+    let length = match char {
+        '~' if length >= 2 => 2,
+        '~' => return None,
+        _ => length.min(3),
+    };
+    let previous = index.checked_sub(1).map(|index| chars[index]);
+    let next = chars.get(index + length).copied();
+    let is_intraword = |char: Option<char>| char.is_some_and(|char| char.is_alphanumeric());
+    let can_open =
+        next.is_some_and(|next| !next.is_whitespace()) && !(char == '_' && is_intraword(previous));
+    let can_close = previous.is_some_and(|previous| !previous.is_whitespace())
+        && !(char == '_' && is_intraword(next));
+    let flags = match (char, length) {
+        ('~', _) => (false, false, true),
+        (_, 1) => (false, true, false),
+        (_, 2) => (true, false, false),
+        _ => (true, true, false),
+    };
+    let is_active =
+        (!flags.0 || style.bold) && (!flags.1 || style.italic) && (!flags.2 || style.strikethrough);
+    let is_inactive =
+        !((flags.0 && style.bold) || (flags.1 && style.italic) || (flags.2 && style.strikethrough));
+    let delimiter = chars[index..index + length].iter().collect::<String>();
+    let has_closing = || {
+        chars[index + length..]
+            .iter()
+            .collect::<String>()
+            .contains(&delimiter)
+    };
+
+    if is_active && can_close {
+        style.bold &= !flags.0;
+        style.italic &= !flags.1;
+        style.strikethrough &= !flags.2;
+    } else if is_inactive && can_open && has_closing() {
+        style.bold |= flags.0;
+        style.italic |= flags.1;
+        style.strikethrough |= flags.2;
+    } else {
+        return None;
+    }
+
+    Some(length)
+}
+
+fn parse_spans(chars: &[char], base_style: InlineStyle) -> Vec<InlineSpan> {
+    let mut spans = vec![];
+    let mut style = base_style;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let char = chars[index];
+
+        match char {
+            '\\' if chars
+                .get(index + 1)
+                .is_some_and(|next| next.is_ascii_punctuation()) =>
+            {
+                push_char(&mut spans, chars[index + 1], style);
+                index += 2;
+            }
+            '`' => {
+                let length = run_length(chars, index);
+                let Some(end) = find_closing_backticks(chars, index + length, length) else {
+                    chars[index..index + length]
+                        .iter()
+                        .for_each(|char| push_char(&mut spans, *char, style));
+                    index += length;
+                    continue;
+                };
+                let code = chars[index + length..end].iter().collect::<String>();
+                let code = match code.len() > 2 && code.starts_with(' ') && code.ends_with(' ') {
+                    true => code[1..code.len() - 1].to_string(),
+                    false => code,
+                };
+
+                spans.push(InlineSpan {
+                    text: code,
+                    style: InlineStyle {
+                        code: true,
+                        ..style
+                    },
+                });
+                index = end + length;
+            }
+            '*' | '_' | '~' => {
+                let length = run_length(chars, index);
+
+                match toggle_emphasis(chars, index, length, &mut style) {
+                    Some(length) => index += length,
+                    None => {
+                        chars[index..index + length]
+                            .iter()
+                            .for_each(|char| push_char(&mut spans, *char, style));
+                        index += length;
+                    }
+                }
+            }
+            '[' => match find_link(chars, index) {
+                Some((text_end, end)) => {
+                    spans.extend(parse_spans(
+                        &chars[index + 1..text_end],
+                        InlineStyle {
+                            link: true,
+                            ..style
+                        },
+                    ));
+                    index = end;
+                }
+                None => {
+                    push_char(&mut spans, char, style);
+                    index += 1;
+                }
+            },
+            _ => {
+                push_char(&mut spans, char, style);
+                index += 1;
+            }
+        }
+    }
+
+    spans
+}
+
+pub fn parse_inlines(text: &str) -> Vec<InlineSpan> {
+    parse_spans(&text.chars().collect::<Vec<char>>(), InlineStyle::default())
+}
@@ -1,14 +1,138 @@
 #[cfg(feature = "auto-detect")]
 use hyperpolyglot_fork::detectors::classify;
-use syntect::parsing::{SyntaxReference, SyntaxSet};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::components::interface::render_error::RenderError;
+use anyhow::Context;
+use syntect::{
+    dumps::from_dump_file,
+    parsing::{SyntaxReference, SyntaxSet},
+};
+
+use crate::{components::interface::render_error::RenderError, config::SnapshotConfig};
+
+const PACKDUMP_EXTENSION: &str = "packdump";
+
+const MAX_SUGGESTIONS: usize = 3;
+
+// The common names of the languages which are neither the names nor the file extensions of the
+// default syntaxes, the user aliases of `LanguageConfig` take precedence over them
+const DEFAULT_ALIASES: &[(&str, &str)] = &[
+    ("csharp", "C#"),
+    ("dockerfile", "Dockerfile"),
+    ("golang", "Go"),
+    ("jsx", "JavaScript"),
+    ("objc", "Objective-C"),
+    ("python3", "Python"),
+    ("shell", "Bourne Again Shell (bash)"),
+    ("tsx", "TypeScriptReact"),
+    ("yml", "YAML"),
+];
+
+// Find the prebuilt syntax sets in the folder recursively, the same as the `.sublime-syntax` files
+// are loaded by syntect
+fn packdump_files(folder: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            files.extend(packdump_files(&path)?);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == PACKDUMP_EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+// The edit distance between the two strings, used to suggest the close matches of a misspelled
+// language
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut distances = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous = distances[0];
+
+        distances[0] = i + 1;
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous + usize::from(a_char != *b_char);
+
+            previous = distances[j + 1];
+            distances[j + 1] = substitution.min(distances[j] + 1).min(distances[j + 1] + 1);
+        }
+    }
+
+    distances[b.len()]
+}
+
+/// A syntax which can be used as the `language` of the code, the syntax can be chosen by its
+/// name, file extensions or aliases
+#[derive(Clone, Debug)]
+pub struct Language {
+    pub name: String,
+    pub extensions: Vec<String>,
+    pub aliases: Vec<String>,
+}
 
 pub struct SyntaxProvider {
     pub syntax_set: SyntaxSet,
+    aliases: HashMap<String, String>,
+    strict: bool,
 }
 
 impl SyntaxProvider {
+    // Resolve the language by the aliases first, then by the name and the token of the syntax
+    fn find_syntax_by_language(&self, language: &str) -> Option<&SyntaxReference> {
+        let language = self
+            .aliases
+            .get(&language.to_lowercase())
+            .map(String::as_str)
+            .unwrap_or(language);
+
+        self.syntax_set
+            .find_syntax_by_name(language)
+            .or_else(|| self.syntax_set.find_syntax_by_token(language))
+    }
+
+    // The names, extensions and aliases which are close to the misspelled language, the closest
+    // ones come first
+    fn suggestions(&self, language: &str) -> Vec<String> {
+        let language = language.to_lowercase();
+        let max_distance = language.chars().count() / 3 + 1;
+        let mut candidates = self
+            .languages()
+            .into_iter()
+            .flat_map(|language| {
+                [vec![language.name], language.extensions, language.aliases].concat()
+            })
+            .filter_map(|candidate| {
+                let lowercase_candidate = candidate.to_lowercase();
+                let distance = edit_distance(&language, &lowercase_candidate);
+
+                (distance <= max_distance || lowercase_candidate.starts_with(&language))
+                    .then_some((distance, candidate))
+            })
+            .collect::<Vec<_>>();
+
+        candidates.sort();
+        candidates.dedup_by(|(_, a), (_, b)| a.eq_ignore_ascii_case(b));
+        candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, candidate)| candidate)
+            .collect()
+    }
+
     pub fn guess_syntax(
         &self,
         language: Option<String>,
@@ -16,7 +140,15 @@ impl SyntaxProvider {
         code: &str,
     ) -> Result<SyntaxReference, RenderError> {
         let syntax = match &language {
-            Some(language) => self.syntax_set.find_syntax_by_token(&language),
+            Some(language) => match self.find_syntax_by_language(language) {
+                None if self.strict => {
+                    return Err(RenderError::NoSuchLanguage(
+                        language.clone(),
+                        self.suggestions(language),
+                    ))
+                }
+                syntax => syntax,
+            },
             None => match &code_file_path {
                 Some(file_path) => self
                     .syntax_set
@@ -32,14 +164,129 @@ impl SyntaxProvider {
                 .find_syntax_by_token(classify(code, &*vec![]))
         });
 
-        let syntax = syntax.unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+        match syntax {
+            Some(syntax) => Ok(syntax.to_owned()),
+            // The code is not detected by the file path or the first line, it would be rendered
+            // as plain text, the extension of file is suggested in strict mode
+            None if self.strict => {
+                let language = code_file_path.as_deref().map(|file_path| {
+                    Path::new(file_path)
+                        .extension()
+                        .and_then(|extension| extension.to_str())
+                        .unwrap_or(file_path)
+                        .to_string()
+                });
+                let suggestions = language
+                    .as_deref()
+                    .map(|language| self.suggestions(language))
+                    .unwrap_or_default();
+
+                Err(RenderError::NoSuchLanguage(
+                    language.unwrap_or("unknown".to_string()),
+                    suggestions,
+                ))
+            }
+            None => Ok(self.syntax_set.find_syntax_plain_text().to_owned()),
+        }
+    }
+
+    // All the visible syntaxes sorted by name, along with the aliases which are mapped onto them
+    pub fn languages(&self) -> Vec<Language> {
+        let mut languages = self
+            .syntax_set
+            .syntaxes()
+            .iter()
+            .filter(|syntax| !syntax.hidden)
+            .map(|syntax| Language {
+                name: syntax.name.clone(),
+                extensions: syntax.file_extensions.clone(),
+                aliases: vec![],
+            })
+            .collect::<Vec<_>>();
+
+        for (alias, language) in &self.aliases {
+            let Some(syntax) = self.find_syntax_by_language(language) else {
+                continue;
+            };
+
+            // The aliases which are the same as the extensions are not listed twice
+            if let Some(language) = languages.iter_mut().find(|item| item.name == syntax.name) {
+                if !language
+                    .extensions
+                    .iter()
+                    .any(|extension| extension.eq_ignore_ascii_case(alias))
+                {
+                    language.aliases.push(alias.clone());
+                }
+            }
+        }
 
-        Ok(syntax.to_owned())
+        languages.sort_by_key(|language| language.name.to_lowercase());
+        languages
+            .iter_mut()
+            .for_each(|language| language.aliases.sort());
+        languages
     }
 
     pub fn new() -> SyntaxProvider {
-        let syntax_set = two_face::syntax::extra_newlines();
+        SyntaxProvider::with_syntax_set(two_face::syntax::extra_newlines())
+    }
+
+    fn with_syntax_set(syntax_set: SyntaxSet) -> SyntaxProvider {
+        let aliases = DEFAULT_ALIASES
+            .iter()
+            .map(|(alias, language)| (alias.to_string(), language.to_string()))
+            .collect();
+
+        SyntaxProvider {
+            syntax_set,
+            aliases,
+            strict: false,
+        }
+    }
+
+    // Merge the `.sublime-syntax` files and the prebuilt `.packdump` files of the folders into the
+    // default syntaxes, the syntaxes which are loaded later take precedence when they have the
+    // same file extensions, so that the user syntaxes can override the default ones
+    pub fn from(syntaxes_folders: &[String]) -> anyhow::Result<SyntaxProvider> {
+        if syntaxes_folders.is_empty() {
+            return Ok(SyntaxProvider::new());
+        }
+
+        let mut builder = two_face::syntax::extra_newlines().into_builder();
+
+        for folder in syntaxes_folders {
+            let folder = PathBuf::from(folder);
+
+            for file in packdump_files(&folder)? {
+                let syntax_set: SyntaxSet = from_dump_file(&file)
+                    .context(format!("Cannot load syntaxes from {}", file.display()))?;
+
+                for syntax in syntax_set.into_builder().syntaxes() {
+                    builder.add(syntax.clone());
+                }
+            }
+
+            builder
+                .add_from_folder(&folder, true)
+                .context(format!("Cannot load syntaxes from {}", folder.display()))?;
+        }
+
+        Ok(SyntaxProvider::with_syntax_set(builder.build()))
+    }
+
+    pub fn from_config(config: &SnapshotConfig) -> anyhow::Result<SyntaxProvider> {
+        let mut syntax_provider = SyntaxProvider::from(&config.syntaxes_folders)?;
+        let language_config = &config.language_config;
+
+        syntax_provider.aliases.extend(
+            language_config
+                .aliases
+                .iter()
+                .map(|(alias, language)| (alias.to_lowercase(), language.clone())),
+        );
+        syntax_provider.strict = language_config.strict;
 
-        SyntaxProvider { syntax_set }
+        Ok(syntax_provider)
     }
 }
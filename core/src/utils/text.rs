@@ -1,11 +1,14 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use cosmic_text::{
-    fontdb::Source, Align, Attrs, AttrsList, Buffer, BufferLine, Color, Family, FontSystem,
-    LayoutRunIter, LineEnding, Metrics, Shaping, SwashCache,
+    fontdb::{self, Source},
+    Align, Attrs, AttrsList, Buffer, BufferLine, CacheKeyFlags, Color, Family, FontSystem,
+    LayoutRunIter, LineEnding, Metrics, Shaping, Style, SwashCache,
 };
 use tiny_skia::{Paint, Pixmap, Rect, Transform};
 
+use crate::components::interface::scene::{SceneElement, SceneFont, SceneGlyph};
+
 const CASKAYDIA_COVE_NERD_FONT: &[u8] =
     include_bytes!("../../assets/fonts/CaskaydiaCoveNerdFont-Regular.ttf");
 const CASKAYDIA_COVE_BOLD_NERD_FONT: &[u8] =
@@ -15,9 +18,32 @@ const CASKAYDIA_COVE_ITALIC_NERD_FONT: &[u8] =
 
 const PACIFICO_FONT: &[u8] = include_bytes!("../../assets/fonts/Pacifico-Regular.ttf");
 
+// The number of chars measured to get the advance of monospace font
+const ADVANCE_SAMPLE_CHARS: usize = 100;
+
+// cosmic-text doesn't draw the text decorations, the decorated spans are marked by the high bits
+// of the `metadata` of attrs, so that the low bits can still be used to tag the spans
+pub const UNDERLINE_METADATA: usize = 1 << (usize::BITS - 1);
+pub const STRIKETHROUGH_METADATA: usize = 1 << (usize::BITS - 2);
+pub const DECORATION_METADATA: usize = UNDERLINE_METADATA | STRIKETHROUGH_METADATA;
+
+// The underline or strikethrough of the adjacent glyphs, in physical coordinates relative to the
+// buffer
+struct DecorationLine {
+    decoration: usize,
+    color: Color,
+    // The index of the last glyph in the run
+    last: usize,
+    left: f32,
+    right: f32,
+    top: f32,
+    thickness: f32,
+}
+
 pub struct FontRenderer {
     font_system: FontSystem,
     scale_factor: f32,
+    scene_fonts: HashMap<fontdb::ID, Arc<SceneFont>>,
 }
 
 fn get_default_attrs<'a>() -> Attrs<'a> {
@@ -46,18 +72,25 @@ impl FontRenderer {
         FontRenderer {
             font_system,
             scale_factor,
+            scene_fonts: HashMap::new(),
         }
     }
 
     pub fn measure_text(&mut self, metrics: Metrics, text: &str) -> (f32, f32) {
+        self.measure_text_with_attrs(metrics, text, &get_default_attrs())
+    }
+
+    // Measure the text by the shaped glyphs, the width of CJK chars, emoji and ligatures depends
+    // on the font, so the text should be measured with the same attrs as it's drawn
+    pub fn measure_text_with_attrs(
+        &mut self,
+        metrics: Metrics,
+        text: &str,
+        attrs: &Attrs,
+    ) -> (f32, f32) {
         let mut buffer = Buffer::new(&mut self.font_system, metrics.scale(self.scale_factor));
 
-        buffer.set_text(
-            &mut self.font_system,
-            text,
-            &get_default_attrs(),
-            Shaping::Advanced,
-        );
+        buffer.set_text(&mut self.font_system, text, attrs, Shaping::Advanced);
 
         let layout_runs: LayoutRunIter = buffer.layout_runs();
         let line_height = buffer.lines.len() as f32 * buffer.metrics().line_height;
@@ -69,6 +102,15 @@ impl FontRenderer {
         )
     }
 
+    // The advance of a char of the monospace font, the letter spacing is included. A run of chars
+    // is measured, so that the rounding of the measured width is negligible
+    pub fn measure_advance(&mut self, metrics: Metrics, attrs: &Attrs) -> f32 {
+        let (width, _) =
+            self.measure_text_with_attrs(metrics, &"0".repeat(ADVANCE_SAMPLE_CHARS), attrs);
+
+        width / ADVANCE_SAMPLE_CHARS as f32
+    }
+
     pub fn draw_text(
         &mut self,
         x: f32,
@@ -76,8 +118,56 @@ impl FontRenderer {
         metrics: Metrics,
         spans: Vec<(&str, Attrs)>,
         pixmap: &mut Pixmap,
-    ) {
+    ) -> SceneElement {
+        let mut buffer = self.rich_text_buffer(metrics, spans, None);
+
+        self.draw(x, y, &mut buffer, pixmap)
+    }
+
+    // Measure the rich text which is wrapped at the width (in logical pixels), the height is
+    // counted by the visual rows instead of the lines
+    pub fn measure_rich_text(
+        &mut self,
+        metrics: Metrics,
+        spans: Vec<(&str, Attrs)>,
+        width: Option<f32>,
+    ) -> (f32, f32) {
+        let buffer = self.rich_text_buffer(metrics, spans, width);
+        let (width, height) = buffer
+            .layout_runs()
+            .fold((0f32, 0f32), |(width, height), run| {
+                (width.max(run.line_w), height + run.line_height)
+            });
+
+        (width.ceil() / self.scale_factor, height / self.scale_factor)
+    }
+
+    // Same as `draw_text`, but the text is wrapped at the width (in logical pixels)
+    pub fn draw_wrapped_text(
+        &mut self,
+        x: f32,
+        y: f32,
+        metrics: Metrics,
+        spans: Vec<(&str, Attrs)>,
+        width: f32,
+        pixmap: &mut Pixmap,
+    ) -> SceneElement {
+        let mut buffer = self.rich_text_buffer(metrics, spans, Some(width));
+
+        self.draw(x, y, &mut buffer, pixmap)
+    }
+
+    fn rich_text_buffer(
+        &mut self,
+        metrics: Metrics,
+        spans: Vec<(&str, Attrs)>,
+        width: Option<f32>,
+    ) -> Buffer {
         let mut buffer = Buffer::new(&mut self.font_system, metrics.scale(self.scale_factor));
+        let spans = spans
+            .into_iter()
+            .map(|(text, attrs)| (text, self.synthesize_italic(attrs)))
+            .collect::<Vec<_>>();
 
         buffer.set_rich_text(
             &mut self.font_system,
@@ -86,8 +176,167 @@ impl FontRenderer {
             Shaping::Advanced,
             None,
         );
+        buffer.set_size(
+            &mut self.font_system,
+            width.map(|width| width * self.scale_factor),
+            None,
+        );
+        buffer
+    }
+
+    // The italic face of the weight may not exist (such as the bundled font has no bold italic
+    // face), the regular italic face would be chosen and the weight is lost. The upright face of
+    // the weight is slanted instead in this case
+    fn synthesize_italic<'a>(&self, attrs: Attrs<'a>) -> Attrs<'a> {
+        if attrs.style != Style::Italic {
+            return attrs;
+        }
+
+        let db = self.font_system.db();
+        let has_face = |style| {
+            db.query(&fontdb::Query {
+                families: &[attrs.family],
+                weight: attrs.weight,
+                stretch: attrs.stretch,
+                style,
+            })
+            .and_then(|id| db.face(id))
+            .is_some_and(|face| face.style == style && face.weight == attrs.weight)
+        };
 
-        self.draw(x, y, &mut buffer, pixmap);
+        if has_face(Style::Italic) || !has_face(Style::Normal) {
+            return attrs;
+        }
+
+        let cache_key_flags = attrs.cache_key_flags | CacheKeyFlags::FAKE_ITALIC;
+
+        attrs.style(Style::Normal).cache_key_flags(cache_key_flags)
+    }
+
+    // The rects (in logical coordinates) of the glyphs of tagged spans, the spans are tagged by
+    // the `metadata` of attrs, the adjacent glyphs with the same tag in a visual row are merged
+    // into one rect, the untagged glyphs (metadata is 0) are ignored
+    pub fn span_rects(
+        &mut self,
+        x: f32,
+        y: f32,
+        metrics: Metrics,
+        spans: Vec<(&str, Attrs)>,
+        width: Option<f32>,
+    ) -> Vec<(usize, Rect)> {
+        let buffer = self.rich_text_buffer(metrics, spans, width);
+        let mut rects = vec![];
+
+        for run in buffer.layout_runs() {
+            // The tag, the index of last glyph, and the horizontal bounds of merged glyphs
+            let mut groups: Vec<(usize, usize, f32, f32)> = vec![];
+
+            for (index, glyph) in run.glyphs.iter().enumerate() {
+                let glyph_tag = glyph.metadata & !DECORATION_METADATA;
+
+                if glyph_tag == 0 {
+                    continue;
+                }
+
+                match groups.last_mut() {
+                    Some((tag, last, _, right)) if *tag == glyph_tag && *last + 1 == index => {
+                        *last = index;
+                        *right = glyph.x + glyph.w;
+                    }
+                    _ => groups.push((glyph_tag, index, glyph.x, glyph.x + glyph.w)),
+                }
+            }
+
+            rects.extend(groups.into_iter().filter_map(|(tag, _, left, right)| {
+                Rect::from_xywh(
+                    x + left / self.scale_factor,
+                    y + run.line_top / self.scale_factor,
+                    (right - left) / self.scale_factor,
+                    run.line_height / self.scale_factor,
+                )
+                .map(|rect| (tag, rect))
+            }));
+        }
+
+        rects
+    }
+
+    // The rects (in logical coordinates) and colors of the underlines and strikethroughs of the
+    // decorated spans, the position and thickness of the lines are read from the font, the
+    // adjacent glyphs with the same decoration and color in a visual row are merged into one line.
+    // The text is wrapped at the width if it's provided, as `draw_wrapped_text`
+    pub fn decoration_rects(
+        &mut self,
+        x: f32,
+        y: f32,
+        metrics: Metrics,
+        spans: Vec<(&str, Attrs)>,
+        width: Option<f32>,
+    ) -> Vec<(Rect, Color)> {
+        let buffer = self.rich_text_buffer(metrics, spans, width);
+        let default_font_color = Color::rgb(255, 255, 255);
+        let mut rects = vec![];
+
+        for run in buffer.layout_runs() {
+            let mut lines: Vec<DecorationLine> = vec![];
+
+            for (index, glyph) in run.glyphs.iter().enumerate() {
+                if glyph.metadata & DECORATION_METADATA == 0 {
+                    continue;
+                }
+
+                let Some(font) = self.font_system.get_font(glyph.font_id) else {
+                    continue;
+                };
+                let font_metrics = font.as_swash().metrics(&[]).scale(glyph.font_size);
+                let color = glyph.color_opt.unwrap_or(default_font_color);
+
+                for (decoration, offset) in [
+                    (UNDERLINE_METADATA, font_metrics.underline_offset),
+                    (STRIKETHROUGH_METADATA, font_metrics.strikeout_offset),
+                ] {
+                    if glyph.metadata & decoration == 0 {
+                        continue;
+                    }
+
+                    let top = run.line_y - offset;
+
+                    match lines
+                        .iter_mut()
+                        .rev()
+                        .find(|line| line.decoration == decoration)
+                    {
+                        Some(line)
+                            if line.color == color && line.last + 1 == index && line.top == top =>
+                        {
+                            line.last = index;
+                            line.right = glyph.x + glyph.w;
+                        }
+                        _ => lines.push(DecorationLine {
+                            decoration,
+                            color,
+                            last: index,
+                            left: glyph.x,
+                            right: glyph.x + glyph.w,
+                            top,
+                            thickness: font_metrics.stroke_size.max(1.),
+                        }),
+                    }
+                }
+            }
+
+            rects.extend(lines.into_iter().filter_map(|line| {
+                Rect::from_xywh(
+                    x + line.left / self.scale_factor,
+                    y + line.top / self.scale_factor,
+                    (line.right - line.left) / self.scale_factor,
+                    line.thickness / self.scale_factor,
+                )
+                .map(|rect| (rect, line.color))
+            }));
+        }
+
+        rects
     }
 
     pub fn draw_line(
@@ -99,7 +348,7 @@ impl FontRenderer {
         attrs: Attrs,
         align: Option<Align>,
         pixmap: &mut Pixmap,
-    ) {
+    ) -> SceneElement {
         let mut buffer = Buffer::new(&mut self.font_system, metrics.scale(self.scale_factor));
         let attrs_list = AttrsList::new(&attrs);
         let mut line = if cfg!(windows) {
@@ -115,35 +364,107 @@ impl FontRenderer {
             Some(pixmap.width() as f32),
             Some(pixmap.height() as f32),
         );
-        self.draw(x, y, &mut buffer, pixmap);
+        self.draw(x, y, &mut buffer, pixmap)
     }
 
-    fn draw<'a>(&mut self, x: f32, y: f32, buffer: &mut Buffer, pixmap: &mut Pixmap) {
+    fn draw(&mut self, x: f32, y: f32, buffer: &mut Buffer, pixmap: &mut Pixmap) -> SceneElement {
         let mut swash_cache = SwashCache::new();
         let default_font_color = Color::rgb(255, 255, 255);
 
-        buffer.draw(
-            &mut self.font_system,
-            &mut swash_cache,
-            default_font_color,
-            |font_x, font_y, w, h, color| {
-                let mut paint = Paint {
-                    anti_alias: true,
-                    ..Default::default()
-                };
+        // The `Buffer::draw` of cosmic-text replaces the alpha of the glyph color with the
+        // coverage of the glyph, the glyphs are drawn manually to blend them, so that the
+        // translucent spans (such as the unfocused lines) are drawn translucently
+        for run in buffer.layout_runs() {
+            for glyph in run.glyphs.iter() {
+                let physical_glyph = glyph.physical((0., 0.), 1.0);
+                let glyph_color = glyph.color_opt.unwrap_or(default_font_color);
 
-                paint.set_color_rgba8(color.r(), color.g(), color.b(), color.a());
+                swash_cache.with_pixels(
+                    &mut self.font_system,
+                    physical_glyph.cache_key,
+                    glyph_color,
+                    |font_x, font_y, color| {
+                        let mut paint = Paint {
+                            anti_alias: true,
+                            ..Default::default()
+                        };
+                        let alpha = (color.a() as u16 * glyph_color.a() as u16 / 255) as u8;
 
-                let rect = Rect::from_xywh(
-                    font_x as f32 + x * self.scale_factor,
-                    font_y as f32 + y * self.scale_factor,
-                    w as f32,
-                    h as f32,
-                )
-                .expect("Cannot draw text on pixmap");
+                        paint.set_color_rgba8(color.r(), color.g(), color.b(), alpha);
 
-                pixmap.fill_rect(rect, &paint, Transform::identity(), None);
-            },
-        );
+                        let rect = Rect::from_xywh(
+                            (physical_glyph.x + font_x) as f32 + x * self.scale_factor,
+                            (run.line_y as i32 + physical_glyph.y + font_y) as f32
+                                + y * self.scale_factor,
+                            1.,
+                            1.,
+                        )
+                        .expect("Cannot draw text on pixmap");
+
+                        pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+                    },
+                );
+            }
+        }
+
+        SceneElement::Text(self.collect_glyphs(x, y, buffer, default_font_color))
+    }
+
+    // Collect the laid out glyphs of the buffer in logical coordinates, the vector renderers use
+    // these glyphs to place text at exactly the same position as the pixmap
+    fn collect_glyphs(
+        &mut self,
+        x: f32,
+        y: f32,
+        buffer: &Buffer,
+        default_font_color: Color,
+    ) -> Vec<SceneGlyph> {
+        let mut glyphs = vec![];
+
+        for run in buffer.layout_runs() {
+            for glyph in run.glyphs {
+                let color = glyph.color_opt.unwrap_or(default_font_color);
+                let font = self.scene_font(glyph.font_id);
+
+                glyphs.push(SceneGlyph {
+                    x: x + (glyph.x + glyph.font_size * glyph.x_offset) / self.scale_factor,
+                    y: y + (run.line_y + glyph.y - glyph.font_size * glyph.y_offset)
+                        / self.scale_factor,
+                    width: glyph.w / self.scale_factor,
+                    font_size: glyph.font_size / self.scale_factor,
+                    glyph_id: glyph.glyph_id,
+                    text: run.text[glyph.start..glyph.end].to_string(),
+                    color: tiny_skia::Color::from_rgba8(color.r(), color.g(), color.b(), color.a()),
+                    font,
+                    oblique: glyph.cache_key_flags.contains(CacheKeyFlags::FAKE_ITALIC),
+                });
+            }
+        }
+
+        glyphs
+    }
+
+    fn scene_font(&mut self, id: fontdb::ID) -> Arc<SceneFont> {
+        if let Some(font) = self.scene_fonts.get(&id) {
+            return font.clone();
+        }
+
+        let face = self.font_system.db().face(id);
+        let font = Arc::new(SceneFont {
+            id,
+            family: face
+                .and_then(|face| face.families.first().map(|(family, _)| family.clone()))
+                .unwrap_or_default(),
+            post_script_name: face
+                .map(|face| face.post_script_name.clone())
+                .unwrap_or_default(),
+            weight: face.map(|face| face.weight.0).unwrap_or(400),
+            italic: face.is_some_and(|face| face.style != fontdb::Style::Normal),
+            index: face.map(|face| face.index).unwrap_or(0),
+            font: self.font_system.get_font(id),
+        });
+
+        self.scene_fonts.insert(id, font.clone());
+        font
     }
 }
@@ -0,0 +1,174 @@
+use std::ops::Range;
+
+use anyhow::Context;
+use regex::Regex;
+
+use crate::config::{HighlightToken, TokenTarget};
+
+use super::code::prepared_ranges;
+
+// The byte offset of the column (counted by chars from 0) in the line, the column after the end
+// of line is clamped to the end
+fn column_offset(line: &str, column: usize) -> usize {
+    line.char_indices()
+        .nth(column)
+        .map(|(offset, _)| offset)
+        .unwrap_or(line.len())
+}
+
+// Locate the targets (the line and the token of the line) in the code, returns the byte ranges in
+// the prepared code and the index of the target they belong to, a target may have multiple ranges
+// if its pattern matches multiple times, the targets out of the code are ignored
+pub fn target_ranges(
+    code: &str,
+    targets: &[(u32, &TokenTarget)],
+) -> anyhow::Result<Vec<(Range<usize>, usize)>> {
+    let lines = code.split('\n').collect::<Vec<&str>>();
+    let mut line_ranges = vec![];
+
+    for (target_index, (line_number, target)) in targets.iter().enumerate() {
+        let index = (*line_number as usize).saturating_sub(1);
+        let Some(line) = lines.get(index).filter(|_| *line_number > 0) else {
+            continue;
+        };
+
+        match target {
+            TokenTarget::Columns(start, end) => {
+                let (start, end) = (*start.min(end) as usize, *start.max(end) as usize);
+
+                line_ranges.push((
+                    index,
+                    column_offset(line, start.saturating_sub(1))..column_offset(line, end),
+                    target_index,
+                ));
+            }
+            TokenTarget::Pattern(pattern) => {
+                let regex = Regex::new(pattern)
+                    .with_context(|| format!("Invalid pattern of token: {}", pattern))?;
+
+                line_ranges.extend(
+                    regex
+                        .find_iter(line)
+                        .map(|matched| (index, matched.range(), target_index)),
+                );
+            }
+        }
+    }
+
+    let ranges = prepared_ranges(
+        code,
+        &line_ranges
+            .iter()
+            .map(|(line, range, _)| (*line, range.clone()))
+            .collect::<Vec<_>>(),
+    );
+
+    Ok(ranges
+        .into_iter()
+        .zip(line_ranges)
+        .filter(|(range, _)| !range.is_empty())
+        .map(|(range, (_, _, target_index))| (range, target_index))
+        .collect())
+}
+
+// Locate the highlight tokens in the code, returns the byte ranges in the prepared code
+pub fn token_ranges(
+    code: &str,
+    highlight_tokens: &[HighlightToken],
+) -> anyhow::Result<Vec<(Range<usize>, HighlightToken)>> {
+    let targets = highlight_tokens
+        .iter()
+        .map(|highlight_token| (highlight_token.line, &highlight_token.target))
+        .collect::<Vec<_>>();
+
+    Ok(target_ranges(code, &targets)?
+        .into_iter()
+        .map(|(range, index)| (range, highlight_tokens[index].clone()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::code::prepare_code;
+
+    fn located<'a>(
+        code: &str,
+        prepared_code: &'a str,
+        targets: &[(u32, TokenTarget)],
+    ) -> Vec<(&'a str, usize)> {
+        let targets = targets
+            .iter()
+            .map(|(line, target)| (*line, target))
+            .collect::<Vec<_>>();
+
+        assert_eq!(prepare_code(code), prepared_code);
+        target_ranges(code, &targets)
+            .unwrap()
+            .into_iter()
+            .map(|(range, index)| (&prepared_code[range], index))
+            .collect()
+    }
+
+    #[test]
+    fn locates_columns() {
+        let code = "  let a = 1;\n  let bc = 2;";
+        let prepared_code = "let a = 1;\nlet bc = 2;";
+
+        assert_eq!(
+            located(
+                code,
+                prepared_code,
+                &[
+                    (1, TokenTarget::Columns(3, 5)),
+                    // The reversed columns are swapped, and the columns after the end are clamped
+                    (2, TokenTarget::Columns(20, 7)),
+                    (2, TokenTarget::Columns(1, 2))
+                ]
+            ),
+            [("let", 0), ("bc = 2;", 1)]
+        );
+    }
+
+    #[test]
+    fn locates_multibyte_columns() {
+        assert_eq!(
+            located("“中文” x", "“中文” x", &[(1, TokenTarget::Columns(2, 3))]),
+            [("中文", 0)]
+        );
+    }
+
+    #[test]
+    fn locates_patterns() {
+        assert_eq!(
+            located(
+                "foo(bar)\nbar.foo()",
+                "foo(bar)\nbar.foo()",
+                &[
+                    (2, TokenTarget::Pattern(r"\bfoo\b".to_string())),
+                    (1, TokenTarget::Pattern("o".to_string())),
+                    (1, TokenTarget::Pattern("x".to_string()))
+                ]
+            ),
+            [("foo", 0), ("o", 1), ("o", 1)]
+        );
+    }
+
+    #[test]
+    fn ignores_targets_out_of_code() {
+        assert!(located(
+            "a\nb",
+            "a\nb",
+            &[
+                (0, TokenTarget::Columns(1, 1)),
+                (3, TokenTarget::Columns(1, 1))
+            ]
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(target_ranges("a", &[(1, &TokenTarget::Pattern("(".to_string()))]).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use syntect::{
+    highlighting::{Highlighter, Style, Theme},
+    parsing::{Scope, SyntaxReference},
+};
+use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent};
+
+use crate::components::interface::render_error::{self, RenderError};
+
+// The tree-sitter captures and the TextMate scopes which they are mapped onto, the captures are
+// matched by the longest prefix (such as `function.method.call` is matched by `function.method`),
+// so that the themes of syntect can be used to color the captures
+const CAPTURE_SCOPES: &[(&str, &str)] = &[
+    ("attribute", "entity.other.attribute-name"),
+    ("comment", "comment"),
+    ("constant", "constant.other"),
+    ("constant.builtin", "constant.language"),
+    ("constructor", "entity.name.type"),
+    ("embedded", "source"),
+    ("escape", "constant.character.escape"),
+    ("function", "entity.name.function"),
+    ("function.builtin", "support.function"),
+    ("function.macro", "entity.name.function.macro"),
+    ("function.method", "entity.name.function"),
+    ("keyword", "keyword"),
+    ("keyword.function", "storage.type.function"),
+    ("keyword.storage", "storage.type"),
+    ("label", "entity.name.label"),
+    ("module", "entity.name.namespace"),
+    ("number", "constant.numeric"),
+    ("operator", "keyword.operator"),
+    ("property", "variable.other.member"),
+    ("punctuation", "punctuation"),
+    ("punctuation.bracket", "punctuation.section"),
+    ("punctuation.delimiter", "punctuation.separator"),
+    ("punctuation.special", "punctuation.definition"),
+    ("string", "string.quoted"),
+    ("string.special", "string.regexp"),
+    ("tag", "entity.name.tag"),
+    ("type", "entity.name.type"),
+    ("type.builtin", "storage.type"),
+    ("variable", "variable.other"),
+    ("variable.builtin", "variable.language"),
+    ("variable.parameter", "variable.parameter"),
+];
+
+type Configurations = HashMap<&'static str, HighlightConfiguration>;
+type StyledLines<'a> = Vec<Vec<(Style, &'a str)>>;
+
+// The name of the grammar which highlights the syntax, the syntax of syntect is named by the
+// Sublime Text grammar, such as `TypeScriptReact`
+fn grammar_name(syntax: &SyntaxReference) -> Option<&'static str> {
+    match syntax.name.to_lowercase().as_str() {
+        "rust" => Some("rust"),
+        "javascript" | "javascript (babel)" | "jsx" => Some("javascript"),
+        "typescript" => Some("typescript"),
+        "typescriptreact" | "tsx" => Some("tsx"),
+        "zig" => Some("zig"),
+        _ => None,
+    }
+}
+
+fn create_configuration(
+    language: tree_sitter::Language,
+    name: &str,
+    highlights_query: &str,
+    injections_query: &str,
+    locals_query: &str,
+) -> render_error::Result<HighlightConfiguration> {
+    let mut configuration = HighlightConfiguration::new(
+        language,
+        name,
+        highlights_query,
+        injections_query,
+        locals_query,
+    )
+    .map_err(|error| RenderError::InvalidHighlightQuery(name.to_string(), error.to_string()))?;
+    let capture_names = CAPTURE_SCOPES
+        .iter()
+        .map(|(capture, _)| *capture)
+        .collect::<Vec<_>>();
+
+    configuration.configure(&capture_names);
+    Ok(configuration)
+}
+
+// The queries are compiled once, since the animated snapshot highlights the code in each frame.
+// The TypeScript queries only contain the syntax which is added to JavaScript, so they are
+// combined with the JavaScript queries
+fn configurations() -> render_error::Result<&'static Configurations> {
+    static CONFIGURATIONS: OnceLock<render_error::Result<Configurations>> = OnceLock::new();
+
+    let configurations = CONFIGURATIONS.get_or_init(|| {
+        let javascript_highlights = [
+            tree_sitter_javascript::JSX_HIGHLIGHT_QUERY,
+            tree_sitter_javascript::HIGHLIGHT_QUERY,
+        ]
+        .join("\n");
+        let typescript_highlights = [
+            tree_sitter_typescript::HIGHLIGHTS_QUERY,
+            tree_sitter_javascript::HIGHLIGHT_QUERY,
+        ]
+        .join("\n");
+        let tsx_highlights = [
+            tree_sitter_typescript::HIGHLIGHTS_QUERY,
+            &javascript_highlights,
+        ]
+        .join("\n");
+        let typescript_locals = [
+            tree_sitter_typescript::LOCALS_QUERY,
+            tree_sitter_javascript::LOCALS_QUERY,
+        ]
+        .join("\n");
+
+        Ok(HashMap::from([
+            (
+                "rust",
+                create_configuration(
+                    tree_sitter_rust::LANGUAGE.into(),
+                    "rust",
+                    tree_sitter_rust::HIGHLIGHTS_QUERY,
+                    tree_sitter_rust::INJECTIONS_QUERY,
+                    "",
+                )?,
+            ),
+            (
+                "javascript",
+                create_configuration(
+                    tree_sitter_javascript::LANGUAGE.into(),
+                    "javascript",
+                    &javascript_highlights,
+                    tree_sitter_javascript::INJECTIONS_QUERY,
+                    tree_sitter_javascript::LOCALS_QUERY,
+                )?,
+            ),
+            (
+                "typescript",
+                create_configuration(
+                    tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+                    "typescript",
+                    &typescript_highlights,
+                    tree_sitter_javascript::INJECTIONS_QUERY,
+                    &typescript_locals,
+                )?,
+            ),
+            (
+                "tsx",
+                create_configuration(
+                    tree_sitter_typescript::LANGUAGE_TSX.into(),
+                    "tsx",
+                    &tsx_highlights,
+                    tree_sitter_javascript::INJECTIONS_QUERY,
+                    &typescript_locals,
+                )?,
+            ),
+            (
+                "zig",
+                create_configuration(
+                    tree_sitter_zig::LANGUAGE.into(),
+                    "zig",
+                    tree_sitter_zig::HIGHLIGHTS_QUERY,
+                    "",
+                    "",
+                )?,
+            ),
+        ]))
+    });
+
+    configurations.as_ref().map_err(Clone::clone)
+}
+
+// Split the regions at the line breaks, so that each line has its own regions as the result of
+// `HighlightLines` of syntect, the line break is kept at the end of the line
+fn split_lines(regions: Vec<(Style, &str)>) -> StyledLines<'_> {
+    let mut lines = vec![vec![]];
+
+    for (style, text) in regions {
+        for piece in text.split_inclusive('\n') {
+            lines.last_mut().unwrap().push((style, piece));
+
+            if piece.ends_with('\n') {
+                lines.push(vec![]);
+            }
+        }
+    }
+
+    // The content ends with a line break doesn't have an extra line
+    if lines.len() > 1 && lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    lines
+}
+
+// Highlight the code by the tree-sitter grammar of the syntax, the styles of the captures are
+// resolved by the theme as if they were the scopes of syntect. Returns `None` if the syntax has no
+// tree-sitter grammar, or the code can't be parsed
+pub fn highlight_lines<'a>(
+    code: &'a str,
+    syntax: &SyntaxReference,
+    theme: &Theme,
+) -> render_error::Result<Option<StyledLines<'a>>> {
+    let configurations = configurations()?;
+
+    Ok(grammar_name(syntax)
+        .and_then(|name| configurations.get(name))
+        .and_then(|configuration| highlight_regions(code, configuration, configurations, theme))
+        .map(split_lines))
+}
+
+fn highlight_regions<'a>(
+    code: &'a str,
+    configuration: &HighlightConfiguration,
+    configurations: &'static Configurations,
+    theme: &Theme,
+) -> Option<Vec<(Style, &'a str)>> {
+    let scopes = CAPTURE_SCOPES
+        .iter()
+        .map(|(_, scope)| Scope::new(scope).unwrap())
+        .collect::<Vec<_>>();
+    let style_highlighter = Highlighter::new(theme);
+    let mut highlighter = tree_sitter_highlight::Highlighter::new();
+    let events = highlighter
+        .highlight(configuration, code.as_bytes(), None, |name| {
+            configurations.get(name)
+        })
+        .ok()?;
+    let mut scope_stack: Vec<Scope> = vec![];
+    let mut regions = vec![];
+
+    for event in events {
+        match event.ok()? {
+            HighlightEvent::HighlightStart(highlight) => scope_stack.push(scopes[highlight.0]),
+            HighlightEvent::HighlightEnd => {
+                scope_stack.pop();
+            }
+            HighlightEvent::Source { start, end } => regions.push((
+                style_highlighter.style_for_stack(&scope_stack),
+                &code[start..end],
+            )),
+        }
+    }
+
+    Some(regions)
+}
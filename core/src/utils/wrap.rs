@@ -0,0 +1,216 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    components::editor::code::{code_attrs, code_metrics},
+    config::{CodeConfig, MaxWidth, WrapMode},
+};
+
+use super::{code::calc_display_width, text::FontRenderer};
+
+// The continuation rows of a wrapped line start with the indicator
+pub const WRAP_INDICATOR: &str = "↪ ";
+
+// The max width in pixels is converted to columns by the advance of the code font, which is
+// measured with the font, size and letter spacing of the code
+fn max_columns(code_config: &CodeConfig, font_renderer: &mut FontRenderer) -> Option<usize> {
+    if code_config.wrap_mode == WrapMode::None {
+        return None;
+    }
+
+    let columns = match code_config.max_width.as_ref()? {
+        MaxWidth::Columns(columns) => *columns,
+        MaxWidth::Pixels(pixels) => {
+            let char_width =
+                font_renderer.measure_advance(code_metrics(code_config), &code_attrs(code_config));
+
+            (pixels / char_width).floor() as usize
+        }
+    };
+
+    // Each row should be able to hold at least one char after the wrap indicator
+    Some(columns.max(calc_display_width(WRAP_INDICATOR) + 1))
+}
+
+// Find the byte offsets where the line is wrapped
+fn wrap_line(line: &str, columns: usize, wrap_mode: &WrapMode) -> Vec<usize> {
+    let indicator_width = calc_display_width(WRAP_INDICATOR);
+    let continuation_capacity = columns - indicator_width;
+    let mut breaks = vec![];
+    let mut row_start = 0;
+    let mut row_width = 0;
+    // The last word boundary in current row, and the width of the row before the boundary
+    let mut word_boundary: Option<(usize, usize)> = None;
+
+    for (offset, grapheme) in line.grapheme_indices(true) {
+        let capacity = if breaks.is_empty() {
+            columns
+        } else {
+            continuation_capacity
+        };
+        let grapheme_width = grapheme.width().min(2);
+
+        if row_width + grapheme_width > capacity && offset > row_start {
+            let break_offset = match word_boundary {
+                // The word is carried to the next row only if it fits the row, which is narrower
+                // than the first row because of the wrap indicator, otherwise the word is broken
+                Some((boundary, width))
+                    if *wrap_mode == WrapMode::Word
+                        && row_width - width + grapheme_width <= continuation_capacity =>
+                {
+                    row_width -= width;
+                    boundary
+                }
+                _ => {
+                    row_width = 0;
+                    offset
+                }
+            };
+
+            breaks.push(break_offset);
+            row_start = break_offset;
+            word_boundary = None;
+        }
+
+        row_width += grapheme_width;
+
+        if grapheme.chars().all(char::is_whitespace) {
+            word_boundary = Some((offset + grapheme.len(), row_width));
+        }
+    }
+
+    breaks
+}
+
+// Calculate the byte offsets (in the whole code) where the lines are wrapped, the offsets are
+// grouped by lines, a line without any offset is not wrapped
+pub fn wrap_code(
+    code: &str,
+    code_config: &CodeConfig,
+    font_renderer: &mut FontRenderer,
+) -> Vec<Vec<usize>> {
+    let lines = code.split('\n');
+    let Some(columns) = max_columns(code_config, font_renderer) else {
+        return lines.map(|_| vec![]).collect();
+    };
+    let mut line_start = 0;
+
+    lines
+        .map(|line| {
+            let breaks = wrap_line(line, columns, &code_config.wrap_mode)
+                .into_iter()
+                .map(|offset| offset + line_start)
+                .collect();
+
+            line_start += line.len() + 1;
+            breaks
+        })
+        .collect()
+}
+
+// Insert the line breaks and the wrap indicators into the code
+pub fn wrap_text(code: &str, code_config: &CodeConfig, font_renderer: &mut FontRenderer) -> String {
+    let breaks = wrap_code(code, code_config, font_renderer).concat();
+    let mut text = String::new();
+    let mut last_offset = 0;
+
+    for offset in breaks {
+        text.push_str(&code[last_offset..offset]);
+        text.push('\n');
+        text.push_str(WRAP_INDICATOR);
+        last_offset = offset;
+    }
+
+    text.push_str(&code[last_offset..]);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The widths of the rows, the continuation rows include the wrap indicator
+    fn row_widths(line: &str, breaks: &[usize]) -> Vec<usize> {
+        let indicator_width = calc_display_width(WRAP_INDICATOR);
+        let offsets = [0].into_iter().chain(breaks.iter().copied());
+        let ends = breaks.iter().copied().chain([line.len()]);
+
+        offsets
+            .zip(ends)
+            .enumerate()
+            .map(|(index, (start, end))| {
+                calc_display_width(&line[start..end]) + if index > 0 { indicator_width } else { 0 }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wraps_at_chars() {
+        assert_eq!(
+            wrap_line("abcdefghijkl", 5, &WrapMode::Char),
+            vec![5, 8, 11]
+        );
+    }
+
+    #[test]
+    fn keeps_short_lines() {
+        assert!(wrap_line("abc", 5, &WrapMode::Char).is_empty());
+        assert!(wrap_line("abc def", 7, &WrapMode::Word).is_empty());
+        assert!(wrap_line("", 5, &WrapMode::Word).is_empty());
+    }
+
+    #[test]
+    fn wraps_at_word_boundaries() {
+        assert_eq!(wrap_line("aaa bbb ccc", 8, &WrapMode::Word), vec![8]);
+        assert_eq!(wrap_line("aaa bbb ccc", 9, &WrapMode::Word), vec![8]);
+        assert_eq!(wrap_line("aaa bbb ccc", 6, &WrapMode::Word), vec![4, 8]);
+    }
+
+    #[test]
+    fn breaks_words_which_do_not_fit_continuation_rows() {
+        let line = "a bcdefghij";
+        let breaks = wrap_line(line, 10, &WrapMode::Word);
+
+        assert_eq!(breaks, vec![10]);
+        assert!(row_widths(line, &breaks).iter().all(|width| *width <= 10));
+    }
+
+    #[test]
+    fn breaks_long_words() {
+        let line = "abcdefghijklmnop qrs";
+        let breaks = wrap_line(line, 6, &WrapMode::Word);
+
+        assert_eq!(breaks, vec![6, 10, 14, 17]);
+        assert!(row_widths(line, &breaks).iter().all(|width| *width <= 6));
+    }
+
+    #[test]
+    fn wraps_wide_graphemes() {
+        // Each CJK char takes two columns and three bytes
+        assert_eq!(wrap_line("中文字符测试", 6, &WrapMode::Char), vec![9, 15]);
+        // The wide char is moved to the next row instead of exceeding the row
+        assert_eq!(wrap_line("ab中", 3, &WrapMode::Char), vec![2]);
+        // The emoji sequence is a single grapheme of two columns, it's never broken
+        assert_eq!(wrap_line("abc👨‍👩‍👧d", 4, &WrapMode::Char), vec![3, 21]);
+    }
+
+    #[test]
+    fn fits_rows_with_wrap_indicator() {
+        let line = "lorem ipsum dolor sit amet, consectetur adipiscing elit";
+
+        for wrap_mode in [WrapMode::Char, WrapMode::Word] {
+            for columns in 3..20 {
+                let breaks = wrap_line(line, columns, &wrap_mode);
+
+                assert!(
+                    row_widths(line, &breaks)
+                        .iter()
+                        .all(|width| *width <= columns),
+                    "{:?} rows exceed {} columns",
+                    wrap_mode,
+                    columns
+                );
+            }
+        }
+    }
+}